@@ -0,0 +1,150 @@
+//! Deciding whether the cookies in a response are stored at all
+use crate::target::{host_matches, normalize_pattern};
+use publicsuffix::List;
+use std::{
+    fmt::{self, Debug},
+    net::IpAddr,
+    sync::Arc,
+};
+use surf::{StatusCode, Url};
+
+/// # Which responses a middleware stores cookies from
+///
+/// Set with
+/// [`CookieMiddleware::with_accept_policy`](crate::CookieMiddleware::with_accept_policy).
+/// Cookies that are refused are reported as
+/// [`RejectionReason::NotAccepted`](crate::events::RejectionReason::NotAccepted).
+/// Cookies already in the jar, such as those loaded from a file or
+/// added with [`set_cookie`](crate::CookieMiddleware::set_cookie), are
+/// still sent.
+///
+/// ```rust
+/// use surf_cookie_middleware::{CookieAcceptPolicy, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new()
+///     .with_redirects(10)
+///     .with_accept_policy(CookieAcceptPolicy::FirstPartyOnly);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CookieAcceptPolicy {
+    /// Store every cookie that a server sends
+    #[default]
+    AcceptAll,
+
+    /// Store no cookies
+    AcceptNone,
+
+    /// Store cookies only from responses on the same site (registrable
+    /// domain) as the request the client sent. While following
+    /// redirects with
+    /// [`with_redirects`](crate::CookieMiddleware::with_redirects),
+    /// cookies set by other sites along the way, such as tracking
+    /// redirects, are refused.
+    FirstPartyOnly,
+}
+
+impl CookieAcceptPolicy {
+    /// Whether cookies set by a response from `url` are stored, when
+    /// the client sent a request to `first_party`
+    pub(crate) fn accepts(self, url: &Url, first_party: &Url, list: Option<&List>) -> bool {
+        match self {
+            CookieAcceptPolicy::AcceptAll => true,
+            CookieAcceptPolicy::AcceptNone => false,
+            CookieAcceptPolicy::FirstPartyOnly => site(url, list) == site(first_party, list),
+        }
+    }
+}
+
+/// Which response statuses cookies are stored from
+#[derive(Clone, Default)]
+pub(crate) struct StatusFilter(Option<Arc<dyn Fn(StatusCode) -> bool + Send + Sync + 'static>>);
+
+impl StatusFilter {
+    pub(crate) fn new(stores: impl Fn(StatusCode) -> bool + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(stores)))
+    }
+
+    /// Whether cookies from a response with `status` may be stored
+    pub(crate) fn allows(&self, status: StatusCode) -> bool {
+        self.0.as_ref().is_none_or(|stores| stores(status))
+    }
+}
+
+impl Debug for StatusFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StatusFilter")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+/// Domain patterns that cookies must, or must not, be scoped to in
+/// order to be stored
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DomainFilter {
+    allowed: Vec<String>,
+    blocked: Vec<String>,
+}
+
+impl DomainFilter {
+    pub(crate) fn allow(&mut self, patterns: impl IntoIterator<Item = String>) {
+        self.allowed.extend(
+            patterns
+                .into_iter()
+                .map(|pattern| normalize_pattern(&pattern)),
+        );
+    }
+
+    pub(crate) fn block(&mut self, patterns: impl IntoIterator<Item = String>) {
+        self.blocked.extend(
+            patterns
+                .into_iter()
+                .map(|pattern| normalize_pattern(&pattern)),
+        );
+    }
+
+    /// Whether a cookie scoped to `domain` may be stored: it must match
+    /// an allowed pattern, if there are any, and no blocked pattern
+    pub(crate) fn allows(&self, domain: &str) -> bool {
+        let matches = |pattern: &String| host_matches(pattern, domain);
+        (self.allowed.is_empty() || self.allowed.iter().any(matches))
+            && !self.blocked.iter().any(matches)
+    }
+}
+
+/// The registrable domain of the host of `url`, as in [`domain_site`]
+pub(crate) fn site(url: &Url, list: Option<&List>) -> Option<String> {
+    url.host_str().map(|host| domain_site(host, list))
+}
+
+/// The registrable domain of `domain`, according to `list` if there is
+/// one and otherwise its last two labels. An ip address is its own
+/// site.
+pub(crate) fn domain_site(domain: &str, list: Option<&List>) -> String {
+    let domain = domain
+        .trim_start_matches('.')
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    if domain
+        .trim_matches(|c| c == '[' || c == ']')
+        .parse::<IpAddr>()
+        .is_ok()
+    {
+        return domain;
+    }
+
+    let root = match list {
+        Some(list) => list
+            .parse_domain(&domain)
+            .ok()
+            .and_then(|parsed| parsed.root().map(String::from)),
+        None => {
+            let labels = domain.rsplitn(3, '.').collect::<Vec<_>>();
+            match labels[..] {
+                [tld, name, _] => Some(format!("{}.{}", name, tld)),
+                _ => None,
+            }
+        }
+    };
+    root.unwrap_or(domain)
+}
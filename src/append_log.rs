@@ -0,0 +1,207 @@
+//! Jar files that cookie changes are appended to
+use crate::{
+    metadata::{self, CookieKey},
+    runtime::{
+        self,
+        fs::{self, OpenOptions},
+        io::WriteExt,
+    },
+    storage::{replace_atomically, SerializeError},
+};
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::PathBuf,
+};
+
+/// How many superseded lines a log may hold, on top of one per live
+/// cookie, before it is compacted
+const MIN_STALE_LINES: usize = 64;
+
+/// The line appended when a cookie is removed from the jar
+#[derive(Serialize, Deserialize)]
+struct Removal {
+    removed: CookieKey,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Removal(Removal),
+    Cookie(Cookie<'static>),
+}
+
+/// An ndjson jar with one line per change: a cookie that was set or
+/// replaced, or the key of one that was removed. Replaying the lines
+/// in order gives the jar.
+#[derive(Debug)]
+pub(crate) struct AppendLog {
+    path: PathBuf,
+    /// The unexpired cookies in the file, as last loaded or written
+    written: HashMap<CookieKey, Cookie<'static>>,
+    /// Lines in the file, including superseded ones
+    lines: usize,
+    loaded: bool,
+    /// The file ends in a partial line, such as from a crash while
+    /// appending, and must be rewritten before it is appended to
+    damaged: bool,
+}
+
+impl AppendLog {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            written: HashMap::new(),
+            lines: 0,
+            loaded: false,
+            damaged: false,
+        }
+    }
+
+    pub(crate) fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Replays the log. Lines that can't be parsed are skipped, so a
+    /// partial last line doesn't lose the rest of the jar.
+    pub(crate) async fn load(&mut self) -> io::Result<Vec<Cookie<'static>>> {
+        let contents = match fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut cookies: Vec<Option<Cookie<'static>>> = vec![];
+        let mut positions = HashMap::new();
+        self.lines = 0;
+        self.damaged = !contents.is_empty() && !contents.ends_with('\n');
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            self.lines += 1;
+            match serde_json::from_str(line) {
+                Ok(Line::Cookie(cookie)) => {
+                    let key = metadata::cookie_key(&cookie);
+                    match positions.get(&key) {
+                        Some(&position) => cookies[position] = Some(cookie),
+                        None => {
+                            positions.insert(key, cookies.len());
+                            cookies.push(Some(cookie));
+                        }
+                    }
+                }
+
+                Ok(Line::Removal(Removal { removed })) => {
+                    if let Some(position) = positions.remove(&removed) {
+                        cookies[position] = None;
+                    }
+                }
+
+                Err(e) => {
+                    log::warn!("skipping unreadable line in {:?}: {}", self.path, e);
+                    self.damaged = true;
+                }
+            }
+        }
+
+        let cookies = cookies
+            .into_iter()
+            .flatten()
+            .filter(|cookie| !cookie.is_expired())
+            .collect::<Vec<_>>();
+        self.written = cookies
+            .iter()
+            .map(|cookie| (metadata::cookie_key(cookie), cookie.clone()))
+            .collect();
+        self.loaded = true;
+        Ok(cookies)
+    }
+
+    /// Appends the differences between `cookies` and the jar as last
+    /// written, or rewrites the file with just `cookies` if `compact`
+    /// is set or too many lines have been superseded
+    pub(crate) async fn persist(
+        &mut self,
+        cookies: &[Cookie<'static>],
+        compact: bool,
+    ) -> io::Result<()> {
+        if !self.loaded {
+            self.load().await?;
+        }
+
+        let mut bytes = Vec::new();
+        let mut changed = vec![];
+        let mut keys = HashSet::with_capacity(cookies.len());
+        for cookie in cookies {
+            let key = metadata::cookie_key(cookie);
+            if self.written.get(&key) != Some(cookie) {
+                write_line(&mut bytes, cookie, Some(cookie))?;
+                changed.push((key.clone(), cookie));
+            }
+            keys.insert(key);
+        }
+        let removed = self
+            .written
+            .keys()
+            .filter(|key| !keys.contains(*key))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in &removed {
+            write_line(
+                &mut bytes,
+                &Removal {
+                    removed: key.clone(),
+                },
+                None,
+            )?;
+        }
+
+        let appended = changed.len() + removed.len();
+        let stale = (self.lines + appended).saturating_sub(cookies.len());
+        if compact || self.damaged || stale > cookies.len().max(MIN_STALE_LINES) {
+            self.rewrite(cookies).await?;
+        } else if appended > 0 {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await?;
+            file.write_all(&bytes).await?;
+            file.sync_all().await?;
+            self.lines += appended;
+        }
+
+        for key in &removed {
+            self.written.remove(key);
+        }
+        for (key, cookie) in changed {
+            self.written.insert(key, cookie.clone());
+        }
+        Ok(())
+    }
+
+    /// Atomically replaces the file with one line per cookie
+    async fn rewrite(&mut self, cookies: &[Cookie<'static>]) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        for cookie in cookies {
+            write_line(&mut bytes, cookie, Some(cookie))?;
+        }
+
+        let path = self.path.clone();
+        runtime::spawn_blocking(move || replace_atomically(&path, &bytes)).await?;
+        log::debug!("compacted {:?} from {} lines", self.path, self.lines);
+        self.lines = cookies.len();
+        self.damaged = false;
+        Ok(())
+    }
+}
+
+fn write_line(
+    bytes: &mut Vec<u8>,
+    line: &impl Serialize,
+    cookie: Option<&Cookie<'_>>,
+) -> io::Result<()> {
+    serde_json::to_writer(&mut *bytes, line).map_err(|e| SerializeError::wrap(cookie, e))?;
+    bytes.push(b'\n');
+    Ok(())
+}
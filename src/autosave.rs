@@ -0,0 +1,40 @@
+//! Saving the jar on an interval in the background
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// A background save interval, shared by all clones of a middleware.
+/// The task is started by the first request or change to the jar
+/// rather than when the interval is configured, so that it saves with
+/// the middleware's finished configuration.
+#[derive(Debug)]
+pub(crate) struct AutosaveTask {
+    interval: Duration,
+    started: AtomicBool,
+}
+
+impl AutosaveTask {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            started: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Claims the start of the task, returning false if it has
+    /// already been started
+    pub(crate) fn start(&self) -> bool {
+        !self.started.swap(true, Ordering::SeqCst)
+    }
+
+    /// Releases a claimed start that could not spawn the task, so a
+    /// later request or change tries again
+    pub(crate) fn not_started(&self) {
+        self.started.store(false, Ordering::SeqCst);
+    }
+}
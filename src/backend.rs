@@ -0,0 +1,79 @@
+//! The cookie store behind the middleware
+use cookie_store::{Cookie, CookieError, CookieStore};
+use std::fmt::Debug;
+use surf::Url;
+
+/// # Storage and matching for the cookies held by a [`CookieMiddleware`](crate::CookieMiddleware)
+///
+/// [`CookieStore`] implements this trait and is the default backend.
+/// Implement it to keep cookies somewhere else, such as an
+/// application's database, while reusing the surf integration, and
+/// build the middleware with
+/// [`with_cookie_store`](crate::CookieMiddleware::with_cookie_store).
+///
+/// Cookies are identified by their domain, path, and name, as in
+/// [`CookieStore`].
+pub trait CookieJarBackend: Debug + Send + Sync + 'static {
+    /// The unexpired cookies that should be sent with a request to
+    /// `url`
+    fn matches(&self, url: &Url) -> Vec<&Cookie<'static>>;
+
+    /// Stores `cookie`, received in a response from `url`, replacing
+    /// any cookie with the same domain, path, and name. An expired
+    /// cookie removes the cookie it replaces.
+    fn insert(&mut self, cookie: Cookie<'static>, url: &Url) -> Result<(), CookieError>;
+
+    /// The unexpired cookie with this domain, path, and name
+    fn get(&self, domain: &str, path: &str, name: &str) -> Option<&Cookie<'_>>;
+
+    /// Whether there is an unexpired cookie with this domain, path,
+    /// and name
+    fn contains(&self, domain: &str, path: &str, name: &str) -> bool {
+        self.get(domain, path, name).is_some()
+    }
+
+    /// Removes the cookie with this domain, path, and name, whether or
+    /// not it has expired
+    fn remove(&mut self, domain: &str, path: &str, name: &str) -> Option<Cookie<'static>>;
+
+    /// All unexpired cookies
+    fn iter_unexpired(&self) -> Box<dyn Iterator<Item = &Cookie<'static>> + '_>;
+
+    /// All cookies, including expired cookies that have not been
+    /// removed yet
+    fn iter_any(&self) -> Box<dyn Iterator<Item = &Cookie<'static>> + '_> {
+        self.iter_unexpired()
+    }
+}
+
+impl CookieJarBackend for CookieStore {
+    fn matches(&self, url: &Url) -> Vec<&Cookie<'static>> {
+        CookieStore::matches(self, url)
+    }
+
+    fn insert(&mut self, cookie: Cookie<'static>, url: &Url) -> Result<(), CookieError> {
+        let action = CookieStore::insert(self, cookie, url)?;
+        log::trace!("cookie action: {:?}", action);
+        Ok(())
+    }
+
+    fn get(&self, domain: &str, path: &str, name: &str) -> Option<&Cookie<'_>> {
+        CookieStore::get(self, domain, path, name)
+    }
+
+    fn contains(&self, domain: &str, path: &str, name: &str) -> bool {
+        CookieStore::contains(self, domain, path, name)
+    }
+
+    fn remove(&mut self, domain: &str, path: &str, name: &str) -> Option<Cookie<'static>> {
+        CookieStore::remove(self, domain, path, name)
+    }
+
+    fn iter_unexpired(&self) -> Box<dyn Iterator<Item = &Cookie<'static>> + '_> {
+        Box::new(CookieStore::iter_unexpired(self))
+    }
+
+    fn iter_any(&self) -> Box<dyn Iterator<Item = &Cookie<'static>> + '_> {
+        Box::new(CookieStore::iter_any(self))
+    }
+}
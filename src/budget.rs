@@ -0,0 +1,67 @@
+//! Keeping the Cookie header within a size limit
+use crate::pruning::AccessLog;
+use cookie_store::Cookie;
+
+/// # Which cookies to leave out when the Cookie header is too large
+///
+/// See
+/// [`with_cookie_header_budget`](crate::CookieMiddleware::with_cookie_header_budget).
+/// Cookies are dropped one at a time until the header fits, and the
+/// cookies that remain are sent in their usual order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncationStrategy {
+    /// Drop the cookies with the longest name and value first
+    DropLargest,
+
+    /// Drop the cookies that were first set the longest ago first.
+    /// Cookies loaded from a file count as set when the middleware was
+    /// built.
+    DropOldest,
+
+    /// Drop the cookies with the shortest, least specific path first,
+    /// keeping those scoped most closely to the request
+    DropLeastSpecificPath,
+}
+
+/// Removes cookies from `pairs`, which are in the order they would be
+/// sent, until the joined header is at most `max_bytes` long. Returns
+/// the names of the cookies that were removed.
+pub(crate) fn truncate(
+    pairs: &mut Vec<(&Cookie<'static>, String)>,
+    max_bytes: usize,
+    strategy: TruncationStrategy,
+    access_log: &AccessLog,
+) -> Vec<String> {
+    let mut dropped = vec![];
+
+    while header_len(pairs) > max_bytes {
+        let index = match strategy {
+            TruncationStrategy::DropLargest => pairs
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, (_, pair))| pair.len())
+                .map(|(index, _)| index),
+
+            TruncationStrategy::DropOldest => pairs
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (cookie, _))| access_log.created(cookie))
+                .map(|(index, _)| index),
+
+            // pairs are sorted by descending path length
+            TruncationStrategy::DropLeastSpecificPath => pairs.len().checked_sub(1),
+        };
+
+        match index {
+            Some(index) => dropped.push(pairs.remove(index).0.name().to_string()),
+            None => break,
+        }
+    }
+
+    dropped
+}
+
+fn header_len(pairs: &[(&Cookie<'static>, String)]) -> usize {
+    let separators = pairs.len().saturating_sub(1) * "; ".len();
+    pairs.iter().map(|(_, pair)| pair.len()).sum::<usize>() + separators
+}
@@ -0,0 +1,294 @@
+//! Combining a cookie store with persistence
+use crate::{
+    events::Listeners,
+    runtime::{fs::OpenOptions, File},
+    storage, CookieJarBackend, CookieMiddleware, CookieStorage, FileStorage, PersistenceSummary,
+    PruningPolicy,
+};
+use cookie_store::CookieStore;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[derive(Debug)]
+enum Persistence {
+    Path(PathBuf),
+    Storage(Arc<dyn CookieStorage>),
+}
+
+/// # Builds a [`CookieMiddleware`] from a cookie store and a jar file
+///
+/// Start with [`CookieMiddleware::builder`]. Unlike the constructors,
+/// the builder can persist an existing cookie store to a file or any
+/// other [`CookieStorage`]. Cookies loaded from storage are added to
+/// the store, except where the store
+/// already holds a cookie with the same domain, path, and name.
+///
+/// Every other option is set on the built middleware with its `with_`
+/// methods.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{CookieStore, CookieMiddleware};
+///
+/// let cookie_store = CookieStore::default();
+/// let middleware = CookieMiddleware::builder()
+///     .cookie_store(cookie_store)
+///     .path("./cookies.ndjson")
+///     .build()
+///     .await?
+///     .with_redirects(5);
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug)]
+pub struct CookieMiddlewareBuilder<B = CookieStore> {
+    cookie_store: B,
+    persistence: Option<Persistence>,
+    atomic_writes: bool,
+    file_locking: bool,
+    lazy_create: bool,
+    blocking_io: bool,
+    append_only: bool,
+    persist_session_cookies: bool,
+    discard_session_cookies: bool,
+    pruning: Option<PruningPolicy>,
+    listeners: Listeners,
+}
+
+impl Default for CookieMiddlewareBuilder {
+    fn default() -> Self {
+        Self {
+            cookie_store: CookieStore::default(),
+            persistence: None,
+            atomic_writes: false,
+            file_locking: false,
+            lazy_create: false,
+            blocking_io: false,
+            append_only: false,
+            persist_session_cookies: false,
+            discard_session_cookies: false,
+            pruning: None,
+            listeners: Listeners::default(),
+        }
+    }
+}
+
+impl<B: CookieJarBackend> CookieMiddlewareBuilder<B> {
+    /// Starts from an existing [`CookieStore`], or any other
+    /// [`CookieJarBackend`], instead of an empty jar
+    pub fn cookie_store<S: CookieJarBackend>(self, cookie_store: S) -> CookieMiddlewareBuilder<S> {
+        CookieMiddlewareBuilder {
+            cookie_store,
+            persistence: self.persistence,
+            atomic_writes: self.atomic_writes,
+            file_locking: self.file_locking,
+            lazy_create: self.lazy_create,
+            blocking_io: self.blocking_io,
+            append_only: self.append_only,
+            persist_session_cookies: self.persist_session_cookies,
+            discard_session_cookies: self.discard_session_cookies,
+            pruning: self.pruning,
+            listeners: self.listeners,
+        }
+    }
+
+    /// Persists the jar to the file at `path`, as with
+    /// [`CookieMiddleware::from_path`]. The file is created if it does
+    /// not exist, unless [`lazy_create`](Self::lazy_create) is set.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.persistence = Some(Persistence::Path(path.into()));
+        self
+    }
+
+    /// Replaces the file set with [`path`](Self::path) atomically on
+    /// every save, by writing a temporary file in the same directory
+    /// and renaming it over the jar, so the jar is never left
+    /// half-written if the process dies while saving. See
+    /// [`FileStorage::atomic`].
+    pub fn atomic_writes(mut self, atomic_writes: bool) -> Self {
+        self.atomic_writes = atomic_writes;
+        self
+    }
+
+    /// Holds an advisory lock on `<path>.lock` while loading and saving
+    /// the file set with [`path`](Self::path), so several processes
+    /// can share a jar file. See [`FileStorage::with_lock_file`].
+    pub fn file_locking(mut self, file_locking: bool) -> Self {
+        self.file_locking = file_locking;
+        self
+    }
+
+    /// Defers creating the file set with [`path`](Self::path) until
+    /// the jar is first saved with a persistent cookie in it, so that
+    /// tools that never receive one don't leave empty jar files
+    /// behind. See [`FileStorage::lazy`]. With
+    /// [`atomic_writes`](Self::atomic_writes), the file is always
+    /// created by the first save.
+    pub fn lazy_create(mut self, lazy_create: bool) -> Self {
+        self.lazy_create = lazy_create;
+        self
+    }
+
+    /// Reads and writes the file set with [`path`](Self::path) with
+    /// blocking [`std::fs`] calls instead of the runtime's async files.
+    /// See [`FileStorage::blocking`]. This has no effect with
+    /// [`atomic_writes`](Self::atomic_writes), which always writes
+    /// with `std::fs`, or with [`lazy_create`](Self::lazy_create).
+    pub fn blocking_io(mut self, blocking_io: bool) -> Self {
+        self.blocking_io = blocking_io;
+        self
+    }
+
+    /// Appends the changes to the jar to the file set with
+    /// [`path`](Self::path) on each save, instead of rewriting the
+    /// whole file, and compacts it as superseded lines build up. See
+    /// [`FileStorage::append_only`]. This takes precedence over
+    /// [`atomic_writes`](Self::atomic_writes),
+    /// [`lazy_create`](Self::lazy_create) and
+    /// [`blocking_io`](Self::blocking_io); compaction always replaces
+    /// the file atomically, and the file is created by the first save
+    /// that changes the jar.
+    pub fn append_only(mut self, append_only: bool) -> Self {
+        self.append_only = append_only;
+        self
+    }
+
+    /// Persists session cookies, which have no expiry, as well as
+    /// persistent cookies, so that a tool such as a scraper can resume
+    /// a session after it restarts. Session cookies are then loaded
+    /// along with persistent cookies, and kept until they are replaced
+    /// or the jar is cleared.
+    pub fn persist_session_cookies(mut self, persist_session_cookies: bool) -> Self {
+        self.persist_session_cookies = persist_session_cookies;
+        self
+    }
+
+    /// Discards any session cookies in the jar when it is loaded, as a
+    /// browser does when it restarts, and each time it is
+    /// [reloaded](CookieMiddleware::reload). This is useful with jar
+    /// files written by other tools, which may contain session cookies.
+    pub fn discard_session_cookies(mut self, discard_session_cookies: bool) -> Self {
+        self.discard_session_cookies = discard_session_cookies;
+        self
+    }
+
+    /// Removes cookies that have been neither sent nor set for a
+    /// while, as with [`CookieMiddleware::with_pruning`], starting with
+    /// the cookies loaded from storage. Cookies in a jar built with a
+    /// [`path`](Self::path) are pruned by the use times persisted next
+    /// to it, so cookies that went unused before a restart are dropped
+    /// as the jar is loaded.
+    pub fn pruning(mut self, pruning: Option<PruningPolicy>) -> Self {
+        self.pruning = pruning;
+        self
+    }
+
+    /// Persists the jar to `file`, as with
+    /// [`CookieMiddleware::from_file`]
+    pub fn file(self, file: impl Into<File>) -> Self {
+        self.storage(FileStorage::new(file))
+    }
+
+    /// Persists the jar to `storage`, such as a database
+    pub fn storage(mut self, storage: impl CookieStorage) -> Self {
+        self.persistence = Some(Persistence::Storage(Arc::new(storage)));
+        self
+    }
+
+    /// Registers a callback that is called with a
+    /// [`PersistenceSummary`] when the persisted cookies are loaded as
+    /// the middleware is built, and each time it is
+    /// [reloaded](CookieMiddleware::reload) afterward, as with
+    /// [`CookieMiddleware::on_load`]
+    pub fn on_load(
+        mut self,
+        callback: impl Fn(&PersistenceSummary) + Send + Sync + 'static,
+    ) -> Self {
+        self.listeners.on_load(callback);
+        self
+    }
+
+    /// Opens the jar file and loads the persisted cookies, if any, and
+    /// builds the middleware
+    pub async fn build(self) -> io::Result<CookieMiddleware<B>> {
+        let Self {
+            cookie_store,
+            persistence,
+            atomic_writes,
+            file_locking,
+            lazy_create,
+            blocking_io,
+            append_only,
+            persist_session_cookies,
+            discard_session_cookies,
+            pruning,
+            listeners,
+        } = self;
+
+        let (storage, path) = match persistence {
+            None => (None, None),
+            Some(Persistence::Storage(storage)) => (Some(storage), None),
+            Some(Persistence::Path(path)) => {
+                let storage = if append_only {
+                    FileStorage::append_only(path.clone())
+                } else if atomic_writes {
+                    FileStorage::atomic(path.clone())
+                } else if lazy_create {
+                    FileStorage::lazy(path.clone())
+                } else if blocking_io {
+                    FileStorage::blocking(&path)?
+                } else {
+                    FileStorage::new(
+                        OpenOptions::new()
+                            .create(true)
+                            .truncate(false)
+                            .read(true)
+                            .write(true)
+                            .open(&path)
+                            .await?,
+                    )
+                };
+
+                let storage = if file_locking {
+                    storage.with_lock_file(lock_path(&path))
+                } else {
+                    storage
+                };
+
+                let storage: Arc<dyn CookieStorage> = Arc::new(storage);
+                (Some(storage), Some(path))
+            }
+        };
+
+        let middleware = CookieMiddleware {
+            storage,
+            path,
+            persist_session_cookies,
+            discard_session_cookies,
+            pruning,
+            listeners,
+            ..CookieMiddleware::with_cookie_store(cookie_store)
+        };
+
+        if let Some(storage) = &middleware.storage {
+            let cookies = middleware.load_persisted(&**storage).await?;
+            storage::insert_loaded(&mut *middleware.cookie_store.write().await, cookies)?;
+        }
+
+        if let Some(path) = &middleware.path {
+            middleware.load_annotations(path).await?;
+        }
+        middleware.prune().await?;
+        middleware.record_modified().await?;
+
+        Ok(middleware)
+    }
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    PathBuf::from(lock_path)
+}
@@ -0,0 +1,158 @@
+use cookie::{Cookie as RawCookie, Expiration};
+use std::convert::TryFrom;
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+/// 9999-12-31T23:59:59Z, the latest expiry that can be written as a
+/// four-digit year and read back by every format
+const MAX_UNIX_TIMESTAMP: i64 = 253_402_300_799;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+pub(crate) fn datetime_from_unix(seconds: i64) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(seconds.clamp(0, MAX_UNIX_TIMESTAMP))
+        .expect("clamped timestamps are always in range")
+}
+
+/// Saturates Max-Age and Expires at [`MAX_UNIX_TIMESTAMP`], so that
+/// far-future cookies neither overflow when their expiry is computed
+/// nor persist a date that can't be loaded again
+pub(crate) fn clamp_expiry(raw: &mut RawCookie<'_>) {
+    if let Some(max_age) = raw.max_age() {
+        let remaining =
+            Duration::seconds(MAX_UNIX_TIMESTAMP - OffsetDateTime::now_utc().unix_timestamp());
+        if max_age > remaining {
+            raw.set_max_age(remaining);
+        }
+    }
+
+    if let Some(Expiration::DateTime(expires)) = raw.expires() {
+        if expires.unix_timestamp() > MAX_UNIX_TIMESTAMP {
+            raw.set_expires(datetime_from_unix(MAX_UNIX_TIMESTAMP));
+        }
+    }
+}
+
+/// Limits Max-Age and Expires to `max_lifetime` from now
+pub(crate) fn cap_lifetime(raw: &mut RawCookie<'_>, max_lifetime: std::time::Duration) {
+    let max_seconds = i64::try_from(max_lifetime.as_secs()).unwrap_or(i64::MAX);
+
+    if let Some(max_age) = raw.max_age() {
+        if max_age.whole_seconds() > max_seconds {
+            raw.set_max_age(Duration::seconds(max_seconds));
+        }
+    }
+
+    if let Some(Expiration::DateTime(expires)) = raw.expires() {
+        let latest = OffsetDateTime::now_utc()
+            .unix_timestamp()
+            .saturating_add(max_seconds);
+        if expires.unix_timestamp() > latest {
+            raw.set_expires(datetime_from_unix(latest));
+        }
+    }
+}
+
+/// Returns the value of the last Expires attribute in a Set-Cookie
+/// header, if any
+pub(crate) fn expires_attribute(header: &str) -> Option<&str> {
+    header
+        .split(';')
+        .skip(1)
+        .filter_map(|attribute| attribute.split_once('='))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("expires"))
+        .map(|(_, value)| value.trim())
+        .last()
+}
+
+/// Parses a cookie-date with the algorithm from [RFC 6265 section
+/// 5.1.1](https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.1),
+/// which accepts dates in any order and with any delimiters.
+///
+/// Two-digit years from 69 through 99 are read as 19xx and years 00
+/// through 68 as 20xx.
+pub(crate) fn parse_cookie_date(input: &str) -> Option<OffsetDateTime> {
+    let mut time = None;
+    let mut day_of_month = None;
+    let mut month = None;
+    let mut year = None;
+
+    for token in input.split(is_delimiter).filter(|token| !token.is_empty()) {
+        if time.is_none() {
+            if let Some(parsed) = parse_time(token) {
+                time = Some(parsed);
+                continue;
+            }
+        }
+
+        if day_of_month.is_none() {
+            if let Some((day, _)) = leading_digits(token, 1, 2) {
+                day_of_month = Some(day);
+                continue;
+            }
+        }
+
+        if month.is_none() {
+            if let Some(parsed) = parse_month(token) {
+                month = Some(parsed);
+                continue;
+            }
+        }
+
+        if year.is_none() {
+            if let Some((parsed, _)) = leading_digits(token, 2, 4) {
+                year = Some(parsed);
+                continue;
+            }
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    let year = match year? {
+        year @ 69..=99 => year + 1900,
+        year @ 0..=68 => year + 2000,
+        year => year,
+    };
+
+    if year < 1601 {
+        return None;
+    }
+
+    let month = Month::try_from(month?).ok()?;
+    let date = Date::from_calendar_date(year as i32, month, day_of_month? as u8).ok()?;
+    let time = Time::from_hms(hour as u8, minute as u8, second as u8).ok()?;
+    Some(PrimitiveDateTime::new(date, time).assume_utc())
+}
+
+fn is_delimiter(c: char) -> bool {
+    matches!(c, '\x09' | '\x20'..='\x2f' | '\x3b'..='\x40' | '\x5b'..='\x60' | '\x7b'..='\x7e')
+}
+
+/// Reads between `min` and `max` leading ascii digits, returning the
+/// number and the rest of the token. The digits must not be followed
+/// by another digit.
+fn leading_digits(token: &str, min: usize, max: usize) -> Option<(u32, &str)> {
+    let len = token.bytes().take_while(u8::is_ascii_digit).count();
+    if len < min || len > max {
+        return None;
+    }
+
+    let (digits, rest) = token.split_at(len);
+    Some((digits.parse().ok()?, rest))
+}
+
+fn parse_time(token: &str) -> Option<(u32, u32, u32)> {
+    let (hour, rest) = leading_digits(token, 1, 2)?;
+    let (minute, rest) = leading_digits(rest.strip_prefix(':')?, 1, 2)?;
+    let (second, _) = leading_digits(rest.strip_prefix(':')?, 1, 2)?;
+    Some((hour, minute, second))
+}
+
+fn parse_month(token: &str) -> Option<u8> {
+    let prefix = token.get(..3)?;
+    MONTHS
+        .iter()
+        .position(|month| month.eq_ignore_ascii_case(prefix))
+        .map(|index| index as u8 + 1)
+}
@@ -0,0 +1,61 @@
+//! Coalescing jar saves
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// What an automatic save should do under a debounce interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Schedule {
+    /// Save now
+    Now,
+
+    /// Save once this delay has passed, which covers every change
+    /// made until then
+    Later(Duration),
+
+    /// A later save is already scheduled and will include this change
+    Scheduled,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    last_save: Option<Instant>,
+    scheduled: bool,
+}
+
+/// When the jar was last saved automatically, shared by all clones of
+/// a middleware
+#[derive(Debug, Default)]
+pub(crate) struct Debounce(Mutex<State>);
+
+impl Debounce {
+    /// Decides when to save a change, allowing at most one save per
+    /// `interval`
+    pub(crate) fn schedule(&self, interval: Duration) -> Schedule {
+        let mut state = self.0.lock().unwrap();
+        if state.scheduled {
+            return Schedule::Scheduled;
+        }
+
+        let now = Instant::now();
+        match state.last_save {
+            Some(last_save) if now.duration_since(last_save) < interval => {
+                state.scheduled = true;
+                Schedule::Later(interval - now.duration_since(last_save))
+            }
+            _ => {
+                state.last_save = Some(now);
+                Schedule::Now
+            }
+        }
+    }
+
+    /// Records that a scheduled save is starting, so later changes
+    /// schedule another
+    pub(crate) fn trailing_save(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.scheduled = false;
+        state.last_save = Some(Instant::now());
+    }
+}
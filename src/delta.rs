@@ -0,0 +1,138 @@
+use cookie_store::{Cookie, CookieExpiration};
+
+/// # The cookies changed by one response
+///
+/// Each response handled by the middleware carries a `CookieDelta` as
+/// an extension, so applications can notice session rotation, consent
+/// changes, or a logout performed by the server.
+///
+/// ```rust
+/// # async fn example(client: surf::Client) -> surf::Result<()> {
+/// use surf_cookie_middleware::CookieDelta;
+///
+/// let res = client.get("https://example.com/logout").await?;
+/// if let Some(delta) = res.ext::<CookieDelta>() {
+///     for deleted in &delta.deleted {
+///         println!("server removed {}", deleted.name);
+///     }
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookieDelta {
+    /// Cookies that were not in the jar before this response
+    pub added: Vec<CookieChange>,
+
+    /// Cookies that replaced one already in the jar
+    pub updated: Vec<CookieChange>,
+
+    /// Cookies that this response expired
+    pub deleted: Vec<CookieChange>,
+}
+
+impl CookieDelta {
+    /// Whether this response changed no cookies
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+
+    /// Records that `cookie` was stored. `previous` is the expiry and
+    /// value of the unexpired cookie it replaced, if there was one.
+    pub(crate) fn record(
+        &mut self,
+        previous: Option<(CookieExpiration, String)>,
+        cookie: &Cookie<'_>,
+    ) -> Option<JarChange> {
+        let change = CookieChange {
+            name: cookie.name().to_string(),
+            domain: String::from(&cookie.domain),
+            path: String::from(&cookie.path),
+            old_expiry: previous.as_ref().map(|(expires, _)| expires.clone()),
+            new_expiry: Some(cookie.expires.clone()).filter(|_| !cookie.is_expired()),
+            value_changed: previous
+                .as_ref()
+                .is_none_or(|(_, value)| value != cookie.value()),
+        };
+
+        let (changes, jar_change): (_, fn(CookieChange) -> JarChange) =
+            match (previous, cookie.is_expired()) {
+                (None, false) => (&mut self.added, JarChange::Added),
+                (Some(_), false) => (&mut self.updated, JarChange::Updated),
+                (Some(_), true) => (&mut self.deleted, JarChange::Removed),
+                (None, true) => return None,
+            };
+        changes.push(change.clone());
+        Some(jar_change(change))
+    }
+}
+
+/// # A change to the jar
+///
+/// Sent to each receiver returned by
+/// [`CookieMiddleware::subscribe`](crate::CookieMiddleware::subscribe)
+/// when a cookie is stored from a response or with
+/// [`set_cookie`](crate::CookieMiddleware::set_cookie), or removed
+/// from the jar. Cookies added or replaced by an import or a reload are
+/// not reported individually.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum JarChange {
+    /// A cookie that was not in the jar was stored
+    Added(CookieChange),
+
+    /// A cookie replaced one with the same name, domain, and path
+    Updated(CookieChange),
+
+    /// An unexpired cookie was removed, because a server expired it,
+    /// it was removed through the middleware, or it was
+    /// [pruned](crate::CookieMiddleware::with_pruning) or
+    /// [evicted](crate::CookieMiddleware::with_limits)
+    Removed(CookieChange),
+
+    /// A cookie that had expired was purged from memory
+    Expired(CookieChange),
+}
+
+impl JarChange {
+    /// The change made by removing `cookie` from the jar
+    pub(crate) fn removal(cookie: &Cookie<'_>) -> Self {
+        let change = CookieChange {
+            name: cookie.name().to_string(),
+            domain: String::from(&cookie.domain),
+            path: String::from(&cookie.path),
+            old_expiry: Some(cookie.expires.clone()),
+            new_expiry: None,
+            value_changed: true,
+        };
+
+        if cookie.is_expired() {
+            JarChange::Expired(change)
+        } else {
+            JarChange::Removed(change)
+        }
+    }
+}
+
+/// One cookie in a [`CookieDelta`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookieChange {
+    /// The cookie name
+    pub name: String,
+
+    /// The cookie domain
+    pub domain: String,
+
+    /// The cookie path
+    pub path: String,
+
+    /// When the cookie expired before this response, or None if it
+    /// was added
+    pub old_expiry: Option<CookieExpiration>,
+
+    /// When the cookie expires now, or None if it was deleted
+    pub new_expiry: Option<CookieExpiration>,
+
+    /// Whether the value differs from the previous one. Always true
+    /// for added and deleted cookies.
+    pub value_changed: bool,
+}
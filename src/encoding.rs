@@ -0,0 +1,23 @@
+//! Percent-encoding of cookie values outside the RFC 6265 cookie-octet
+//! grammar
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use std::borrow::Cow;
+
+// cookie-octet excludes CTLs, whitespace, DQUOTE, comma, semicolon,
+// and backslash. Non-ascii bytes are always encoded. `%` is left alone
+// so that values that are already encoded pass through unchanged.
+const NON_COOKIE_OCTET: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b',').add(b';').add(b'\\');
+
+/// Percent-encodes any characters in `value` that are not valid in a
+/// cookie value
+pub(crate) fn encode_value(value: &str) -> Cow<'_, str> {
+    utf8_percent_encode(value, NON_COOKIE_OCTET).into()
+}
+
+/// Percent-decodes `value`, returning it unchanged if the decoded
+/// bytes are not utf8
+pub(crate) fn decode_value(value: &str) -> Cow<'_, str> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .unwrap_or(Cow::Borrowed(value))
+}
@@ -0,0 +1,409 @@
+//! # Structured notifications about cookie handling
+use crate::{JarChange, PersistenceSummary};
+use async_channel::{self as channel, Receiver, Sender};
+use cookie_store::{Cookie, CookieError};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+use surf::{StatusCode, Url};
+
+/// Something that happened while this middleware handled cookies.
+///
+/// Register a listener with
+/// [`CookieMiddleware::with_event_listener`](crate::CookieMiddleware::with_event_listener).
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub enum CookieEvent {
+    /// A Set-Cookie header was received but no cookie was stored
+    Rejected(Rejection),
+
+    /// A response set the same cookie more than once. Which one is
+    /// kept depends on
+    /// [`with_duplicate_cookies`](crate::CookieMiddleware::with_duplicate_cookies).
+    Duplicate(Duplicate),
+
+    /// A cookie about to be sent has the same value as a cookie scoped
+    /// to an unrelated domain. Only reported when
+    /// [`with_leak_detection`](crate::CookieMiddleware::with_leak_detection)
+    /// is enabled.
+    PossibleLeak(PossibleLeak),
+
+    /// Cookies were left out of a request because the Cookie header
+    /// would have exceeded the budget set with
+    /// [`with_cookie_header_budget`](crate::CookieMiddleware::with_cookie_header_budget)
+    Truncated(Truncation),
+}
+
+/// A change to the jar made by storing a cookie, from a Set-Cookie
+/// header or with [`set_cookie`](crate::CookieMiddleware::set_cookie).
+/// Passed to callbacks registered with
+/// [`on_cookie_set`](crate::CookieMiddleware::on_cookie_set).
+#[derive(Debug, Clone)]
+pub struct Stored {
+    /// The cookie as it was stored. When the action is
+    /// [`StoreOutcome::Removed`], this is the expired cookie that
+    /// removed it.
+    pub cookie: Cookie<'static>,
+
+    /// The url of the request that received the cookie, or the url
+    /// passed to [`set_cookie`](crate::CookieMiddleware::set_cookie)
+    pub url: Url,
+
+    /// What storing the cookie did
+    pub action: StoreOutcome,
+}
+
+/// What storing a cookie did to the jar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreOutcome {
+    /// There was no cookie with the same name, domain, and path
+    Created,
+
+    /// The cookie replaced one with the same name, domain, and path
+    Updated,
+
+    /// The cookie was already expired, and removed the cookie with
+    /// the same name, domain, and path
+    Removed,
+}
+
+impl StoreOutcome {
+    /// The action taken by storing `cookie`, given whether the jar
+    /// already held a cookie with the same name, domain, and path
+    pub(crate) fn new(replaced: bool, cookie: &Cookie<'_>) -> Self {
+        match (replaced, cookie.is_expired()) {
+            (_, true) => StoreOutcome::Removed,
+            (true, false) => StoreOutcome::Updated,
+            (false, false) => StoreOutcome::Created,
+        }
+    }
+}
+
+/// A Set-Cookie header that was not stored
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    /// The raw Set-Cookie header value, as received
+    pub header: String,
+
+    /// The url of the request that received the header
+    pub url: Url,
+
+    /// Why the cookie was not stored
+    pub reason: RejectionReason,
+}
+
+/// Two Set-Cookie headers in one response for the same cookie name,
+/// domain, and path
+#[derive(Debug, Clone)]
+pub struct Duplicate {
+    /// The cookie name
+    pub name: String,
+
+    /// The cookie domain
+    pub domain: String,
+
+    /// The cookie path
+    pub path: String,
+
+    /// The url of the request that received the headers
+    pub url: Url,
+
+    /// The raw Set-Cookie header value that was stored
+    pub kept: String,
+
+    /// The raw Set-Cookie header value that was ignored or replaced
+    pub discarded: String,
+}
+
+/// A cookie value that is about to be sent to one domain and is also
+/// held by a cookie for an unrelated domain, such as a copied token.
+/// The value itself is not included.
+#[derive(Debug, Clone)]
+pub struct PossibleLeak {
+    /// The url the cookie is about to be sent to
+    pub url: Url,
+
+    /// The name of the cookie about to be sent
+    pub name: String,
+
+    /// The domain of the other cookie with the same value
+    pub source_domain: String,
+
+    /// The name of the other cookie with the same value
+    pub source_name: String,
+}
+
+/// Cookies that matched a request but were not sent, to keep the
+/// Cookie header within its budget
+#[derive(Debug, Clone)]
+pub struct Truncation {
+    /// The url of the request
+    pub url: Url,
+
+    /// The configured budget in bytes
+    pub max_bytes: usize,
+
+    /// The names of the cookies that were left out, in the order they
+    /// were dropped
+    pub dropped: Vec<String>,
+}
+
+/// Why a Set-Cookie header was not stored
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum RejectionReason {
+    /// The header only contained attributes, such as `HttpOnly;
+    /// Path=/`, and no cookie name-value pair
+    AttributeOnly,
+
+    /// The cookie name was empty or only whitespace, as in `=value`
+    EmptyName,
+
+    /// The cookie value was empty or only whitespace, as in `name=`.
+    /// Only reported when
+    /// [`with_blank_values_rejected`](crate::CookieMiddleware::with_blank_values_rejected)
+    /// is enabled.
+    BlankValue,
+
+    /// The [`CookieAcceptPolicy`](crate::CookieAcceptPolicy) does not
+    /// store cookies from this response
+    NotAccepted,
+
+    /// The response had a status that cookies are not stored from. Only
+    /// reported with
+    /// [`with_error_responses_ignored`](crate::CookieMiddleware::with_error_responses_ignored)
+    /// or [`with_status_filter`](crate::CookieMiddleware::with_status_filter).
+    IgnoredStatus(StatusCode),
+
+    /// The cookie's domain is not
+    /// [allowed](crate::CookieMiddleware::with_allowed_domains) or is
+    /// [blocked](crate::CookieMiddleware::with_blocked_domains)
+    DomainNotAllowed,
+
+    /// The cookie has the Secure attribute but was set over plain
+    /// http. Only reported with
+    /// [`with_strict_secure_cookies`](crate::CookieMiddleware::with_strict_secure_cookies).
+    InsecureOrigin,
+
+    /// The cookie's name starts with `__Secure-` or `__Host-` but it
+    /// does not meet the requirements of that prefix
+    InvalidPrefix,
+
+    /// The cookie's name and value are longer than the
+    /// [maximum size](crate::CookieMiddleware::with_max_cookie_size)
+    TooLarge {
+        /// The length of the name and value in bytes
+        size: usize,
+
+        /// The configured maximum
+        max_bytes: usize,
+    },
+
+    /// The [`CookiePolicy`](crate::CookiePolicy) refused to store the
+    /// cookie
+    Policy,
+
+    /// The cookie store could not parse or refused to store the cookie
+    CookieStore(CookieError),
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectionReason::AttributeOnly => {
+                f.write_str("set-cookie header has no name-value pair")
+            }
+            RejectionReason::EmptyName => f.write_str("cookie name is empty"),
+            RejectionReason::BlankValue => f.write_str("cookie value is blank"),
+            RejectionReason::NotAccepted => {
+                f.write_str("cookies from this response are not accepted")
+            }
+            RejectionReason::IgnoredStatus(status) => {
+                write!(f, "cookies from {} responses are ignored", status)
+            }
+            RejectionReason::DomainNotAllowed => f.write_str("cookie domain is not allowed"),
+            RejectionReason::InsecureOrigin => {
+                f.write_str("secure cookie was set by an insecure origin")
+            }
+            RejectionReason::InvalidPrefix => {
+                f.write_str("cookie does not meet the requirements of its name prefix")
+            }
+            RejectionReason::TooLarge { size, max_bytes } => write!(
+                f,
+                "cookie is {} bytes, more than the maximum of {}",
+                size, max_bytes
+            ),
+            RejectionReason::Policy => f.write_str("cookie was refused by the cookie policy"),
+            RejectionReason::CookieStore(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl RejectionReason {
+    /// Whether the header could not be parsed as a cookie at all, as
+    /// opposed to a well-formed cookie that was not stored
+    pub fn is_malformed(&self) -> bool {
+        matches!(
+            self,
+            RejectionReason::AttributeOnly
+                | RejectionReason::EmptyName
+                | RejectionReason::CookieStore(CookieError::Parse)
+        )
+    }
+}
+
+/// # Set-Cookie headers that could not be parsed
+///
+/// The error a request fails with when the middleware is built
+/// [`with_strict_parsing`](crate::CookieMiddleware::with_strict_parsing)
+/// and a response has malformed Set-Cookie headers. Recover it with
+/// [`surf::Error::downcast_ref`].
+#[derive(Debug, Clone)]
+pub struct MalformedSetCookie {
+    /// Each malformed header, and why it could not be parsed
+    pub rejections: Vec<Rejection>,
+}
+
+impl fmt::Display for MalformedSetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("malformed set-cookie header")?;
+        for (i, rejection) in self.rejections.iter().enumerate() {
+            let separator = if i == 0 { ": " } else { ", " };
+            write!(
+                f,
+                "{}{:?} from {} ({})",
+                separator, rejection.header, rejection.url, rejection.reason
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MalformedSetCookie {}
+
+impl From<CookieError> for RejectionReason {
+    fn from(e: CookieError) -> Self {
+        RejectionReason::CookieStore(e)
+    }
+}
+
+type Listener = Arc<dyn Fn(&CookieEvent) + Send + Sync + 'static>;
+type StoreHook = Arc<dyn Fn(&Stored) + Send + Sync + 'static>;
+type PersistenceHook = Arc<dyn Fn(&PersistenceSummary) + Send + Sync + 'static>;
+
+#[derive(Default)]
+struct Subscribers {
+    listeners: Vec<Listener>,
+    streams: Vec<Sender<CookieEvent>>,
+    store_hooks: Vec<StoreHook>,
+    save_hooks: Vec<PersistenceHook>,
+    load_hooks: Vec<PersistenceHook>,
+    change_streams: Vec<Sender<JarChange>>,
+}
+
+/// The listeners and event streams of a middleware, shared by all of
+/// its clones
+#[derive(Clone, Default)]
+pub(crate) struct Listeners(Arc<Mutex<Subscribers>>);
+
+impl Listeners {
+    pub(crate) fn push(&mut self, listener: impl Fn(&CookieEvent) + Send + Sync + 'static) {
+        self.0.lock().unwrap().listeners.push(Arc::new(listener));
+    }
+
+    pub(crate) fn on_store(&mut self, hook: impl Fn(&Stored) + Send + Sync + 'static) {
+        self.0.lock().unwrap().store_hooks.push(Arc::new(hook));
+    }
+
+    pub(crate) fn on_save(&mut self, hook: impl Fn(&PersistenceSummary) + Send + Sync + 'static) {
+        self.0.lock().unwrap().save_hooks.push(Arc::new(hook));
+    }
+
+    pub(crate) fn on_load(&mut self, hook: impl Fn(&PersistenceSummary) + Send + Sync + 'static) {
+        self.0.lock().unwrap().load_hooks.push(Arc::new(hook));
+    }
+
+    pub(crate) fn observes_saves(&self) -> bool {
+        !self.0.lock().unwrap().save_hooks.is_empty()
+    }
+
+    pub(crate) fn observes_loads(&self) -> bool {
+        !self.0.lock().unwrap().load_hooks.is_empty()
+    }
+
+    pub(crate) fn saved(&self, summary: &PersistenceSummary) {
+        let hooks = self.0.lock().unwrap().save_hooks.clone();
+        for hook in &hooks {
+            hook(summary);
+        }
+    }
+
+    pub(crate) fn loaded(&self, summary: &PersistenceSummary) {
+        let hooks = self.0.lock().unwrap().load_hooks.clone();
+        for hook in &hooks {
+            hook(summary);
+        }
+    }
+
+    pub(crate) fn stream(&self) -> Receiver<CookieEvent> {
+        let (sender, receiver) = channel::unbounded();
+        self.0.lock().unwrap().streams.push(sender);
+        receiver
+    }
+
+    pub(crate) fn subscribe(&self) -> Receiver<JarChange> {
+        let (sender, receiver) = channel::unbounded();
+        self.0.lock().unwrap().change_streams.push(sender);
+        receiver
+    }
+
+    pub(crate) fn changed(&self, changes: impl IntoIterator<Item = JarChange>) {
+        let mut subscribers = self.0.lock().unwrap();
+        if subscribers.change_streams.is_empty() {
+            return;
+        }
+
+        for change in changes {
+            subscribers
+                .change_streams
+                .retain(|sender| sender.try_send(change.clone()).is_ok());
+        }
+    }
+
+    pub(crate) fn emit(&self, event: CookieEvent) {
+        // called without the lock held, so a listener can subscribe
+        let listeners = {
+            let mut subscribers = self.0.lock().unwrap();
+            subscribers
+                .streams
+                .retain(|sender| sender.try_send(event.clone()).is_ok());
+            subscribers.listeners.clone()
+        };
+
+        for listener in &listeners {
+            listener(&event);
+        }
+    }
+
+    pub(crate) fn stored(&self, stored: Stored) {
+        let hooks = self.0.lock().unwrap().store_hooks.clone();
+        for hook in &hooks {
+            hook(&stored);
+        }
+    }
+}
+
+impl fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subscribers = self.0.lock().unwrap();
+        f.debug_struct("Listeners")
+            .field("listeners", &subscribers.listeners.len())
+            .field("streams", &subscribers.streams.len())
+            .field("store_hooks", &subscribers.store_hooks.len())
+            .field("save_hooks", &subscribers.save_hooks.len())
+            .field("load_hooks", &subscribers.load_hooks.len())
+            .field("change_streams", &subscribers.change_streams.len())
+            .finish()
+    }
+}
@@ -0,0 +1,155 @@
+//! Searching the jar by domain and cookie name
+use cookie_store::{Cookie, CookieExpiration};
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Glob(String),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Matcher {
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Glob(pattern) => glob_match(pattern, text),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// # A pattern for [`CookieMiddleware::find`](crate::CookieMiddleware::find)
+///
+/// A cookie matches if the pattern matches its domain or its name, or
+/// its value when [`matching_values`](Self::matching_values) is
+/// enabled. Cookie values are redacted from the results unless
+/// [`revealing_values`](Self::revealing_values) is enabled.
+///
+/// A `&str` converts into a glob pattern.
+///
+/// ```rust
+/// use surf_cookie_middleware::CookiePattern;
+///
+/// let by_domain = CookiePattern::glob("*.example.com");
+/// let by_name: CookiePattern = "session_*".into();
+/// let by_value = CookiePattern::glob("*deadbeef*")
+///     .matching_values(true)
+///     .revealing_values(true);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CookiePattern {
+    matcher: Matcher,
+    match_values: bool,
+    reveal_values: bool,
+}
+
+impl CookiePattern {
+    fn new(matcher: Matcher) -> Self {
+        Self {
+            matcher,
+            match_values: false,
+            reveal_values: false,
+        }
+    }
+
+    /// A glob pattern, where `*` matches any run of characters and `?`
+    /// matches any single character. The pattern must match the whole
+    /// domain or name.
+    pub fn glob(pattern: &str) -> Self {
+        Self::new(Matcher::Glob(pattern.to_string()))
+    }
+
+    /// A regular expression, which matches if it is found anywhere in
+    /// the domain or name. Anchor it with `^` and `$` to match the
+    /// whole field.
+    ///
+    /// Requires the `regex` feature.
+    #[cfg(feature = "regex")]
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self::new(Matcher::Regex(regex::Regex::new(pattern)?)))
+    }
+
+    /// Also match cookie values against this pattern
+    pub fn matching_values(mut self, match_values: bool) -> Self {
+        self.match_values = match_values;
+        self
+    }
+
+    /// Include cookie values in the results
+    pub fn revealing_values(mut self, reveal_values: bool) -> Self {
+        self.reveal_values = reveal_values;
+        self
+    }
+
+    pub(crate) fn find(&self, cookie: &Cookie<'_>) -> Option<CookieView> {
+        let domain = String::from(&cookie.domain);
+        let is_match = self.matcher.is_match(&domain)
+            || self.matcher.is_match(cookie.name())
+            || (self.match_values && self.matcher.is_match(cookie.value()));
+
+        is_match.then(|| CookieView {
+            domain,
+            path: String::from(&cookie.path),
+            name: cookie.name().to_string(),
+            value: Some(cookie.value().to_string()).filter(|_| self.reveal_values),
+            expires: cookie.expires.clone(),
+        })
+    }
+}
+
+impl From<&str> for CookiePattern {
+    fn from(pattern: &str) -> Self {
+        Self::glob(pattern)
+    }
+}
+
+/// A cookie found with [`CookieMiddleware::find`](crate::CookieMiddleware::find)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CookieView {
+    /// The cookie domain
+    pub domain: String,
+
+    /// The cookie path
+    pub path: String,
+
+    /// The cookie name
+    pub name: String,
+
+    /// The cookie value, or None if it was redacted
+    pub value: Option<String>,
+
+    /// When the cookie expires
+    pub expires: CookieExpiration,
+}
+
+/// Whether `text` matches the whole of the glob `pattern`
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    // the position after the last `*`, and the text it has matched up to
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,54 @@
+//! Reading the cookies of a Firefox profile
+use crate::formats::{invalid_data, CookieRecord};
+use cookie::SameSite;
+use cookie_store::Cookie;
+use rusqlite::{params, Connection, OpenFlags};
+use std::{io, path::Path};
+
+// Firefox stores expiry in seconds, and in milliseconds since version
+// 127. A timestamp this large is past the year 5000 in seconds.
+const MILLISECOND_THRESHOLD: i64 = 100_000_000_000;
+
+/// Reads every cookie in a Firefox `cookies.sqlite` database. The
+/// database is opened read-only.
+pub(crate) fn read_cookies(path: &Path) -> io::Result<Vec<Cookie<'static>>> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(io::Error::other)?;
+
+    let mut statement = connection
+        .prepare(
+            "SELECT name, value, host, path, expiry, isSecure, isHttpOnly, sameSite
+             FROM moz_cookies",
+        )
+        .map_err(invalid_data)?;
+
+    let records = statement
+        .query_map(params![], |row| {
+            let host: String = row.get(2)?;
+            let expiry: i64 = row.get(4)?;
+            Ok(CookieRecord {
+                name: row.get(0)?,
+                value: row.get(1)?,
+                host_only: !host.starts_with('.'),
+                domain: host,
+                path: row.get(3)?,
+                expires: Some(if expiry > MILLISECOND_THRESHOLD {
+                    expiry / 1000
+                } else {
+                    expiry
+                }),
+                secure: row.get(5)?,
+                http_only: row.get(6)?,
+                same_site: match row.get::<_, i64>(7)? {
+                    1 => Some(SameSite::Lax),
+                    2 => Some(SameSite::Strict),
+                    _ => None,
+                },
+            })
+        })
+        .map_err(invalid_data)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(invalid_data)?;
+
+    records.into_iter().map(CookieRecord::into_cookie).collect()
+}
@@ -0,0 +1,100 @@
+use super::{CookieFormat, CookieRecord};
+use cookie::SameSite;
+use cookie_store::Cookie;
+use serde::Deserialize;
+use std::io;
+
+/// # EditThisCookie / Cookie-Editor JSON
+///
+/// This is the JSON array exported by the EditThisCookie and
+/// Cookie-Editor browser extensions. This format can only be
+/// imported.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{formats::CookieEditor, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// middleware
+///     .import(&CookieEditor, r#"[{
+///       "domain": ".example.com", "expirationDate": 4102444800.25,
+///       "hostOnly": false, "httpOnly": false, "name": "session",
+///       "path": "/", "sameSite": "no_restriction", "secure": true,
+///       "session": false, "storeId": "0", "value": "abc123", "id": 1
+///     }]"#)
+///     .await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CookieEditor;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CookieEditorCookie {
+    name: String,
+    value: String,
+    domain: String,
+    #[serde(default)]
+    host_only: Option<bool>,
+    #[serde(default = "default_path")]
+    path: String,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default)]
+    session: bool,
+    #[serde(default)]
+    expiration_date: Option<f64>,
+    #[serde(default)]
+    same_site: Option<String>,
+}
+
+fn default_path() -> String {
+    String::from("/")
+}
+
+impl From<CookieEditorCookie> for CookieRecord {
+    fn from(cookie: CookieEditorCookie) -> Self {
+        let host_only = cookie
+            .host_only
+            .unwrap_or_else(|| !cookie.domain.starts_with('.'));
+
+        let expires = if cookie.session {
+            None
+        } else {
+            cookie.expiration_date.map(|expires| expires as i64)
+        };
+
+        // chrome's extension api spells SameSite=None "no_restriction",
+        // and reports cookies without a SameSite attribute as "unspecified"
+        let same_site = match cookie.same_site.as_deref() {
+            Some("strict") => Some(SameSite::Strict),
+            Some("lax") => Some(SameSite::Lax),
+            Some("no_restriction") | Some("none") => Some(SameSite::None),
+            _ => None,
+        };
+
+        Self {
+            name: cookie.name,
+            value: cookie.value,
+            domain: cookie.domain,
+            host_only,
+            path: cookie.path,
+            expires,
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+            same_site,
+        }
+    }
+}
+
+impl CookieFormat for CookieEditor {
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>> {
+        let cookies: Vec<CookieEditorCookie> = serde_json::from_str(input)?;
+        cookies
+            .into_iter()
+            .map(|cookie| CookieRecord::from(cookie).into_cookie())
+            .collect()
+    }
+}
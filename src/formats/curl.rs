@@ -0,0 +1,268 @@
+use super::{invalid_data, CookieFormat, CookieRecord};
+use cookie_store::Cookie;
+use std::{io, iter::Peekable, str::Chars};
+use surf::Url;
+
+/// # Browser devtools "Copy as cURL"
+///
+/// Extracts the cookies from the `-H 'Cookie: …'` header or `-b '…'`
+/// argument of a curl command line as produced by the "Copy as cURL"
+/// action in browser devtools. This format can only be imported.
+///
+/// A Cookie header carries no attributes, so each cookie is stored as
+/// a host-only session cookie with `Path=/` for the url of the curl
+/// command, or for the url provided with [`CopyAsCurl::with_url`].
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{formats::CopyAsCurl, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// middleware
+///     .import(
+///         &CopyAsCurl::new(),
+///         r#"curl 'https://example.com/account' \
+///           -H 'accept: text/html' \
+///           -b 'session=abc123; theme=dark' \
+///           --compressed"#,
+///     )
+///     .await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CopyAsCurl {
+    url: Option<Url>,
+}
+
+impl CopyAsCurl {
+    /// Builds a new CopyAsCurl that stores cookies for the url in the
+    /// curl command
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores cookies for `url` instead of the url in the curl command
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::formats::CopyAsCurl;
+    /// let format = CopyAsCurl::new().with_url(surf::Url::parse("https://example.com")?);
+    /// # Ok::<(), surf::http::url::ParseError>(())
+    /// ```
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = Some(url);
+        self
+    }
+}
+
+// curl options that take an argument, so that argument is not
+// mistaken for the url
+const OPTIONS_WITH_ARGUMENTS: &[&str] = &[
+    "-A",
+    "--user-agent",
+    "-X",
+    "--request",
+    "-d",
+    "--data",
+    "--data-raw",
+    "--data-binary",
+    "--data-urlencode",
+    "-e",
+    "--referer",
+    "-F",
+    "--form",
+    "-u",
+    "--user",
+    "-o",
+    "--output",
+    "-x",
+    "--proxy",
+];
+
+impl CookieFormat for CopyAsCurl {
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>> {
+        let words = shell_words(input)?;
+        let mut words = words.iter().map(String::as_str);
+        let mut url = None;
+        let mut cookie_values = vec![];
+
+        while let Some(word) = words.next() {
+            match word {
+                "-H" | "--header" => {
+                    if let Some((name, value)) = words.next().and_then(|h| h.split_once(':')) {
+                        if name.trim().eq_ignore_ascii_case("cookie") {
+                            cookie_values.push(value.trim());
+                        }
+                    }
+                }
+
+                "-b" | "--cookie" => {
+                    // without an `=`, the argument is a cookie file name
+                    if let Some(value) = words.next().filter(|value| value.contains('=')) {
+                        cookie_values.push(value);
+                    }
+                }
+
+                "--url" => url = url.or_else(|| words.next()),
+
+                option if OPTIONS_WITH_ARGUMENTS.contains(&option) => {
+                    words.next();
+                }
+
+                word if url.is_none() && !word.starts_with('-') && word != "curl" => {
+                    url = Some(word);
+                }
+
+                _ => {}
+            }
+        }
+
+        let url = match &self.url {
+            Some(url) => url.clone(),
+            None => Url::parse(url.ok_or_else(|| invalid_data("no url in curl command"))?)
+                .map_err(invalid_data)?,
+        };
+
+        let host = url
+            .host_str()
+            .ok_or_else(|| invalid_data("curl url has no host"))?;
+
+        cookie_values
+            .into_iter()
+            .flat_map(|value| value.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, value)| {
+                CookieRecord {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                    domain: host.to_string(),
+                    host_only: true,
+                    path: String::from("/"),
+                    expires: None,
+                    secure: false,
+                    http_only: false,
+                    same_site: None,
+                }
+                .into_cookie()
+            })
+            .collect()
+    }
+}
+
+/// Splits a posix shell command line into words, handling the
+/// quoting styles browsers use when copying requests as curl
+fn shell_words(input: &str) -> io::Result<Vec<String>> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(unterminated()),
+                    }
+                }
+            }
+
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(unterminated()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(unterminated()),
+                    }
+                }
+            }
+
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                ansi_c_quoted(&mut chars, &mut word)?;
+            }
+
+            '\\' => match chars.next() {
+                Some('\n') | None => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                }
+            },
+
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+/// Reads the remainder of a bash `$'…'` string, which chrome uses for
+/// values containing quotes or non-printable characters
+fn ansi_c_quoted(chars: &mut Peekable<Chars<'_>>, word: &mut String) -> io::Result<()> {
+    loop {
+        match chars.next() {
+            Some('\'') => return Ok(()),
+            Some('\\') => match chars.next() {
+                Some('n') => word.push('\n'),
+                Some('t') => word.push('\t'),
+                Some('r') => word.push('\r'),
+                Some(c @ ('x' | 'u' | 'U')) => {
+                    let len = match c {
+                        'x' => 2,
+                        'u' => 4,
+                        _ => 8,
+                    };
+                    let mut hex = String::new();
+                    while hex.len() < len && chars.peek().is_some_and(char::is_ascii_hexdigit) {
+                        hex.extend(chars.next());
+                    }
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(std::char::from_u32)
+                        .ok_or_else(|| invalid_data("invalid escape in curl command"))?;
+                    word.push(c);
+                }
+                Some(c) => word.push(c),
+                None => return Err(unterminated()),
+            },
+            Some(c) => word.push(c),
+            None => return Err(unterminated()),
+        }
+    }
+}
+
+fn unterminated() -> io::Error {
+    invalid_data("unterminated quote in curl command")
+}
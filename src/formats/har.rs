@@ -0,0 +1,252 @@
+use super::{
+    invalid_data,
+    readable::{format_rfc3339, parse_rfc3339},
+    CookieFormat, CookieRecord,
+};
+use cookie::SameSite;
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::BTreeMap, io};
+use surf::Url;
+use time::OffsetDateTime;
+
+/// # HTTP Archive (HAR) cookies
+///
+/// HAR files are the network recordings saved by browser devtools and
+/// many proxies. Importing a HAR file replays the cookies of each
+/// entry in order: the cookies its request sent, then the cookies its
+/// response set, so the jar ends up as the browser's jar was at the
+/// end of the recording. Cookies without a `domain` are scoped to the
+/// host of their request. Request cookies don't record their path and
+/// are imported with a path of `/`.
+///
+/// Exported HAR files contain only cookies, as the response cookies of
+/// one synthetic `GET` entry per domain, and no other traffic.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{formats::Har, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// middleware
+///     .import(&Har, r#"{ "log": { "entries": [{
+///       "request": { "url": "https://example.com/login", "cookies": [] },
+///       "response": { "cookies": [{
+///         "name": "session", "value": "abc123", "path": "/",
+///         "expires": "2030-01-01T00:00:00.000Z", "httpOnly": true, "secure": true
+///       }] }
+///     }] } }"#)
+///     .await?;
+///
+/// let har = middleware.export(&Har).await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Har;
+
+#[derive(Deserialize)]
+struct Document {
+    log: Log,
+}
+
+#[derive(Deserialize)]
+struct Log {
+    #[serde(default)]
+    entries: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    request: EntryRequest,
+    #[serde(default)]
+    response: Option<EntryResponse>,
+}
+
+#[derive(Deserialize)]
+struct EntryRequest {
+    url: String,
+    #[serde(default)]
+    cookies: Vec<HarCookie>,
+}
+
+#[derive(Deserialize)]
+struct EntryResponse {
+    #[serde(default)]
+    cookies: Vec<HarCookie>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarCookie {
+    name: String,
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<String>,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    same_site: Option<String>,
+}
+
+impl HarCookie {
+    /// The cookie as received in an entry for `url`, with
+    /// `default_path` if it has no path
+    fn into_cookie(self, url: &Url, default_path: &str) -> io::Result<Cookie<'static>> {
+        let (domain, host_only) = match self.domain.filter(|domain| !domain.is_empty()) {
+            // devtools write domain cookies with a leading dot
+            Some(domain) => {
+                let host_only = !domain.starts_with('.');
+                (domain, host_only)
+            }
+            None => {
+                let host = url
+                    .host_str()
+                    .ok_or_else(|| invalid_data("har request url has no host"))?;
+                (host.to_string(), true)
+            }
+        };
+
+        CookieRecord {
+            expires: self
+                .expires
+                .as_deref()
+                .filter(|expires| !expires.is_empty())
+                .map(parse_rfc3339)
+                .transpose()?,
+            path: self.path.unwrap_or_else(|| default_path.to_string()),
+            same_site: self.same_site.as_deref().and_then(parse_same_site),
+            name: self.name,
+            value: self.value,
+            domain,
+            host_only,
+            secure: self.secure,
+            http_only: self.http_only,
+        }
+        .into_cookie()
+    }
+}
+
+impl From<CookieRecord> for HarCookie {
+    fn from(record: CookieRecord) -> Self {
+        Self {
+            domain: Some(if record.host_only {
+                record.domain
+            } else {
+                format!(".{}", record.domain)
+            }),
+            name: record.name,
+            value: record.value,
+            path: Some(record.path),
+            expires: record.expires.map(format_rfc3339),
+            http_only: record.http_only,
+            secure: record.secure,
+            same_site: record.same_site.map(|same_site| same_site.to_string()),
+        }
+    }
+}
+
+/// SameSite as written by browsers, which differ in case
+fn parse_same_site(same_site: &str) -> Option<SameSite> {
+    match same_site.to_ascii_lowercase().as_str() {
+        "strict" => Some(SameSite::Strict),
+        "lax" => Some(SameSite::Lax),
+        "none" => Some(SameSite::None),
+        _ => None,
+    }
+}
+
+/// The path of a cookie set without a Path attribute by a response
+/// to `url`, as defined by RFC 6265
+fn default_path(url: &Url) -> &str {
+    match url.path().rfind('/') {
+        Some(0) | None => "/",
+        Some(end) => &url.path()[..end],
+    }
+}
+
+impl CookieFormat for Har {
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>> {
+        let document: Document = serde_json::from_str(input).map_err(invalid_data)?;
+        let mut cookies = vec![];
+        for entry in document.log.entries {
+            let url = Url::parse(&entry.request.url).map_err(invalid_data)?;
+            for cookie in entry.request.cookies {
+                cookies.push(cookie.into_cookie(&url, "/")?);
+            }
+            for cookie in entry.response.into_iter().flat_map(|res| res.cookies) {
+                cookies.push(cookie.into_cookie(&url, default_path(&url))?);
+            }
+        }
+        Ok(cookies)
+    }
+
+    fn serialize(&self, cookies: &[&Cookie<'static>]) -> io::Result<String> {
+        let mut domains = BTreeMap::<String, Vec<CookieRecord>>::new();
+        for cookie in cookies {
+            let record = CookieRecord::from_cookie(cookie);
+            domains
+                .entry(record.domain.clone())
+                .or_default()
+                .push(record);
+        }
+
+        let started = format_rfc3339(OffsetDateTime::now_utc().unix_timestamp());
+        let entries = domains
+            .into_iter()
+            .map(|(domain, records)| {
+                let scheme = if records.iter().any(|record| record.secure) {
+                    "https"
+                } else {
+                    "http"
+                };
+                let cookies = records.into_iter().map(HarCookie::from).collect::<Vec<_>>();
+                json!({
+                    "startedDateTime": started,
+                    "time": 0,
+                    "request": {
+                        "method": "GET",
+                        "url": format!("{}://{}/", scheme, domain),
+                        "httpVersion": "HTTP/1.1",
+                        "cookies": [],
+                        "headers": [],
+                        "queryString": [],
+                        "headersSize": -1,
+                        "bodySize": -1,
+                    },
+                    "response": {
+                        "status": 200,
+                        "statusText": "OK",
+                        "httpVersion": "HTTP/1.1",
+                        "cookies": cookies,
+                        "headers": [],
+                        "content": { "size": 0, "mimeType": "x-unknown" },
+                        "redirectURL": "",
+                        "headersSize": -1,
+                        "bodySize": -1,
+                    },
+                    "cache": {},
+                    "timings": { "send": 0, "wait": 0, "receive": 0 },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let document = json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "entries": entries,
+            }
+        });
+        serde_json::to_string_pretty(&document).map_err(invalid_data)
+    }
+}
@@ -0,0 +1,58 @@
+use super::{invalid_data, readable::ReadableCookie, CookieFormat};
+use cookie_store::Cookie;
+use std::io;
+
+/// # Readable JSON cookie list
+///
+/// A human-editable JSON array of cookies, with the same fields as the
+#[cfg_attr(feature = "yaml", doc = "[`Yaml`](super::Yaml)")]
+#[cfg_attr(not(feature = "yaml"), doc = "`Yaml`")]
+/// format. Only `name`, `value`, and `domain` are required. A cookie
+/// without `expires` is a session cookie, and `expires` is an RFC 3339
+/// timestamp.
+///
+/// ```json
+/// [
+///   {
+///     "name": "consent",
+///     "value": "yes",
+///     "domain": "example.com",
+///     "host_only": false,
+///     "expires": "2030-01-01T00:00:00Z"
+///   },
+///   { "name": "region", "value": "eu", "domain": "shop.example.com", "same_site": "Lax" }
+/// ]
+/// ```
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{formats::Json, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// middleware
+///     .import(&Json, r#"[{ "name": "session", "value": "abc123", "domain": "example.com" }]"#)
+///     .await?;
+/// let json = middleware.export(&Json).await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+impl CookieFormat for Json {
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>> {
+        let cookies: Vec<ReadableCookie> = serde_json::from_str(input).map_err(invalid_data)?;
+        cookies
+            .into_iter()
+            .map(ReadableCookie::into_cookie)
+            .collect()
+    }
+
+    fn serialize(&self, cookies: &[&Cookie<'static>]) -> io::Result<String> {
+        let cookies = cookies
+            .iter()
+            .map(|cookie| ReadableCookie::from_cookie(cookie))
+            .collect::<Vec<_>>();
+
+        serde_json::to_string_pretty(&cookies).map_err(invalid_data)
+    }
+}
@@ -0,0 +1,208 @@
+//! # Cookie interchange formats
+//!
+//! Formats implement [`CookieFormat`] and can be handed to
+//! [`CookieMiddleware::import`](crate::CookieMiddleware::import) and
+//! [`CookieMiddleware::export`](crate::CookieMiddleware::export) to
+//! move cookies between this middleware and other tools.
+//!
+//! ```rust
+//! # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+//! use surf_cookie_middleware::{formats::StorageState, CookieMiddleware};
+//!
+//! let middleware = CookieMiddleware::new();
+//! middleware.import(&StorageState, r#"{"cookies": [], "origins": []}"#).await?;
+//! let storage_state = middleware.export(&StorageState).await?;
+//! # Ok(()) }) }
+//! ```
+use crate::dates::datetime_from_unix;
+use cookie::{Cookie as RawCookie, SameSite};
+use cookie_store::{Cookie, CookieDomain, CookieExpiration};
+use serde::{Deserialize, Serialize};
+use std::io;
+use surf::Url;
+
+mod cookie_editor;
+pub use cookie_editor::CookieEditor;
+
+mod curl;
+pub use curl::CopyAsCurl;
+
+mod har;
+pub use har::Har;
+
+mod json;
+pub use json::Json;
+
+mod netscape;
+pub use netscape::Netscape;
+
+mod readable;
+
+mod selenium;
+pub use selenium::Selenium;
+
+mod storage_state;
+pub use storage_state::StorageState;
+
+#[cfg(feature = "yaml")]
+mod yaml;
+#[cfg(feature = "yaml")]
+pub use yaml::Yaml;
+
+/// A serialization format for cookies
+pub trait CookieFormat {
+    /// Parses cookies from `input`
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>>;
+
+    /// Serializes `cookies` into this format. Formats that can only be
+    /// imported return an error.
+    fn serialize(&self, cookies: &[&Cookie<'static>]) -> io::Result<String> {
+        let _ = cookies;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "this cookie format does not support export",
+        ))
+    }
+}
+
+/// The attributes of a cookie as most interchange formats represent
+/// them, as an intermediate step to and from [`Cookie`]
+#[derive(Debug, Clone)]
+pub(crate) struct CookieRecord {
+    pub(crate) name: String,
+    pub(crate) value: String,
+    pub(crate) domain: String,
+    pub(crate) host_only: bool,
+    pub(crate) path: String,
+    pub(crate) expires: Option<i64>,
+    pub(crate) secure: bool,
+    pub(crate) http_only: bool,
+    pub(crate) same_site: Option<SameSite>,
+}
+
+impl CookieRecord {
+    pub(crate) fn into_cookie(self) -> io::Result<Cookie<'static>> {
+        let domain = self.domain.trim_start_matches('.').to_string();
+        let path = if self.path.starts_with('/') {
+            self.path
+        } else {
+            String::from("/")
+        };
+
+        let url = origin_url(&domain, &path, self.secure)?;
+        let mut builder = RawCookie::build(self.name, self.value).path(path);
+
+        if !self.host_only {
+            builder = builder.domain(domain);
+        }
+
+        if self.secure {
+            builder = builder.secure(true);
+        }
+
+        if self.http_only {
+            builder = builder.http_only(true);
+        }
+
+        if let Some(same_site) = self.same_site {
+            builder = builder.same_site(same_site);
+        }
+
+        if let Some(expires) = self.expires {
+            builder = builder.expires(datetime_from_unix(expires));
+        }
+
+        Cookie::try_from_raw_cookie(&builder.finish(), &url)
+            .map(Cookie::into_owned)
+            .map_err(invalid_data)
+    }
+
+    pub(crate) fn from_cookie(cookie: &Cookie<'_>) -> Self {
+        let (domain, host_only) = match &cookie.domain {
+            CookieDomain::HostOnly(domain) => (domain.clone(), true),
+            CookieDomain::Suffix(domain) => (domain.clone(), false),
+            CookieDomain::NotPresent | CookieDomain::Empty => (String::new(), true),
+        };
+
+        let expires = match &cookie.expires {
+            CookieExpiration::AtUtc(at) => Some(at.unix_timestamp()),
+            CookieExpiration::SessionEnd => None,
+        };
+
+        Self {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain,
+            host_only,
+            path: String::from(&cookie.path),
+            expires,
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            same_site: cookie.same_site(),
+        }
+    }
+}
+
+/// SameSite as spelled by browser tooling
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub(crate) enum SameSiteName {
+    Strict,
+    Lax,
+    None,
+}
+
+impl From<SameSiteName> for SameSite {
+    fn from(same_site: SameSiteName) -> Self {
+        match same_site {
+            SameSiteName::Strict => SameSite::Strict,
+            SameSiteName::Lax => SameSite::Lax,
+            SameSiteName::None => SameSite::None,
+        }
+    }
+}
+
+impl From<SameSite> for SameSiteName {
+    fn from(same_site: SameSite) -> Self {
+        match same_site {
+            SameSite::Strict => SameSiteName::Strict,
+            SameSite::Lax => SameSiteName::Lax,
+            SameSite::None => SameSiteName::None,
+        }
+    }
+}
+
+/// Rebuilds `cookie` with its value replaced by `map(value)`
+pub(crate) fn map_value(
+    cookie: Cookie<'static>,
+    map: impl Fn(&str) -> std::borrow::Cow<'_, str>,
+) -> io::Result<Cookie<'static>> {
+    match map(cookie.value()) {
+        std::borrow::Cow::Borrowed(_) => Ok(cookie),
+        std::borrow::Cow::Owned(value) => CookieRecord {
+            value,
+            ..CookieRecord::from_cookie(&cookie)
+        }
+        .into_cookie(),
+    }
+}
+
+/// The url a cookie would have been received from, used as the
+/// request url when inserting an imported cookie into a
+/// [`CookieStore`](cookie_store::CookieStore)
+pub(crate) fn request_url(cookie: &Cookie<'_>) -> io::Result<Url> {
+    let domain = cookie
+        .domain
+        .as_cow()
+        .ok_or_else(|| invalid_data("cookie has no domain"))?;
+
+    origin_url(&domain, &cookie.path, cookie.secure().unwrap_or(false))
+}
+
+fn origin_url(domain: &str, path: &str, secure: bool) -> io::Result<Url> {
+    let scheme = if secure { "https" } else { "http" };
+    Url::parse(&format!("{}://{}{}", scheme, domain, path)).map_err(invalid_data)
+}
+
+pub(crate) fn invalid_data(error: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
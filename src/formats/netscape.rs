@@ -0,0 +1,119 @@
+use super::{invalid_data, CookieFormat, CookieRecord};
+use cookie_store::Cookie;
+use std::io;
+
+const HEADER: &str = "# Netscape HTTP Cookie File\n";
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// # Netscape `cookies.txt`
+///
+/// The tab-separated cookie file read and written by curl (`-b` and
+/// `-c`), wget, and many browser extensions. Each line holds the
+/// domain, whether subdomains match, the path, whether the cookie is
+/// secure, the expiry as a unix timestamp (`0` for a session cookie),
+/// the name, and the value. HttpOnly cookies are written with curl's
+/// `#HttpOnly_` domain prefix.
+///
+/// To share a jar file with curl, build the middleware with
+/// [`CookieMiddleware::from_netscape_path`](crate::CookieMiddleware::from_netscape_path).
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{formats::Netscape, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// middleware
+///     .import(
+///         &Netscape,
+///         "# Netscape HTTP Cookie File\n\
+///          .example.com\tTRUE\t/\tTRUE\t4102444800\tsession\tabc123\n",
+///     )
+///     .await?;
+///
+/// let cookies_txt = middleware.export(&Netscape).await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Netscape;
+
+fn flag(field: &str) -> bool {
+    field.eq_ignore_ascii_case("TRUE")
+}
+
+fn flag_str(flag: bool) -> &'static str {
+    if flag {
+        "TRUE"
+    } else {
+        "FALSE"
+    }
+}
+
+impl CookieFormat for Netscape {
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>> {
+        input
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter_map(|line| match line.strip_prefix(HTTP_ONLY_PREFIX) {
+                Some(line) => Some((line, true)),
+                None if line.trim().is_empty() || line.starts_with('#') => None,
+                None => Some((line, false)),
+            })
+            .map(|(line, http_only)| {
+                let fields = line.split('\t').collect::<Vec<_>>();
+                let (domain, include_subdomains, path, secure, expires, name) = match fields[..] {
+                    [domain, include_subdomains, path, secure, expires, name, ..] => {
+                        (domain, include_subdomains, path, secure, expires, name)
+                    }
+                    _ => {
+                        return Err(invalid_data(format!(
+                            "expected 7 tab-separated fields in cookies.txt line {:?}",
+                            line
+                        )))
+                    }
+                };
+
+                let expires = expires.parse::<i64>().map_err(invalid_data)?;
+
+                CookieRecord {
+                    name: name.to_string(),
+                    value: fields.get(6).copied().unwrap_or_default().to_string(),
+                    domain: domain.to_string(),
+                    host_only: !flag(include_subdomains),
+                    path: path.to_string(),
+                    expires: if expires == 0 { None } else { Some(expires) },
+                    secure: flag(secure),
+                    http_only,
+                    same_site: None,
+                }
+                .into_cookie()
+            })
+            .collect()
+    }
+
+    fn serialize(&self, cookies: &[&Cookie<'static>]) -> io::Result<String> {
+        let mut output = String::from(HEADER);
+        output.push('\n');
+
+        for cookie in cookies {
+            let record = CookieRecord::from_cookie(cookie);
+            output.push_str(&format!(
+                "{}{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                if record.http_only {
+                    HTTP_ONLY_PREFIX
+                } else {
+                    ""
+                },
+                if record.host_only { "" } else { "." },
+                record.domain,
+                flag_str(!record.host_only),
+                record.path,
+                flag_str(record.secure),
+                record.expires.unwrap_or(0),
+                record.name,
+                record.value,
+            ));
+        }
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,101 @@
+use super::{invalid_data, CookieRecord, SameSiteName};
+use crate::dates::datetime_from_unix;
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+use std::io;
+use time::{
+    format_description::FormatItem, macros::format_description, OffsetDateTime, PrimitiveDateTime,
+};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ReadableCookie {
+    name: String,
+    value: String,
+    domain: String,
+    #[serde(default = "default_true")]
+    host_only: bool,
+    #[serde(default = "default_path")]
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<String>,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    same_site: Option<SameSiteName>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_path() -> String {
+    String::from("/")
+}
+
+impl ReadableCookie {
+    pub(crate) fn into_cookie(self) -> io::Result<Cookie<'static>> {
+        CookieRecord {
+            expires: self.expires.as_deref().map(parse_rfc3339).transpose()?,
+            name: self.name,
+            value: self.value,
+            domain: self.domain,
+            host_only: self.host_only,
+            path: self.path,
+            secure: self.secure,
+            http_only: self.http_only,
+            same_site: self.same_site.map(Into::into),
+        }
+        .into_cookie()
+    }
+
+    pub(crate) fn from_cookie(cookie: &Cookie<'_>) -> Self {
+        let record = CookieRecord::from_cookie(cookie);
+        Self {
+            name: record.name,
+            value: record.value,
+            domain: record.domain,
+            host_only: record.host_only,
+            path: record.path,
+            expires: record.expires.map(format_rfc3339),
+            secure: record.secure,
+            http_only: record.http_only,
+            same_site: record.same_site.map(Into::into),
+        }
+    }
+}
+
+const RFC3339_FORMAT: &[FormatItem<'_>] =
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]Z");
+
+const RFC3339_OFFSET_FORMAT: &[FormatItem<'_>] = format_description!(
+    "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory][offset_minute]"
+);
+
+pub(crate) fn format_rfc3339(seconds: i64) -> String {
+    datetime_from_unix(seconds)
+        .format(RFC3339_FORMAT)
+        .expect("utc datetimes always format")
+}
+
+pub(crate) fn parse_rfc3339(s: &str) -> io::Result<i64> {
+    // browsers write milliseconds, which cookie expiry doesn't need
+    let s = &*without_fraction(s);
+    PrimitiveDateTime::parse(s, RFC3339_FORMAT)
+        .map(|datetime| datetime.assume_utc())
+        .or_else(|_| OffsetDateTime::parse(s, RFC3339_OFFSET_FORMAT))
+        .map(OffsetDateTime::unix_timestamp)
+        .map_err(invalid_data)
+}
+
+/// `s` without the fractional part of its seconds, if it has one
+fn without_fraction(s: &str) -> std::borrow::Cow<'_, str> {
+    match s.split_once('.') {
+        Some((datetime, rest)) => {
+            let zone = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+            format!("{}{}", datetime, zone).into()
+        }
+        None => s.into(),
+    }
+}
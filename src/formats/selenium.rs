@@ -0,0 +1,112 @@
+use super::{invalid_data, CookieFormat, CookieRecord, SameSiteName};
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// # Selenium WebDriver cookie JSON
+///
+/// This is the JSON array returned by selenium's `get_cookies()`. Each
+/// entry of an exported array can be passed to `add_cookie()`.
+///
+/// Selenium omits `expiry` for session cookies and reports domain
+/// cookies with a leading dot. Cookies without a `domain` cannot be
+/// imported, since there is no request url to scope them to.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{formats::Selenium, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// middleware
+///     .import(&Selenium, r#"[{
+///       "name": "session", "value": "abc123", "domain": "example.com",
+///       "path": "/", "secure": true, "httpOnly": true
+///     }]"#)
+///     .await?;
+///
+/// let cookies = middleware.export(&Selenium).await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Selenium;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SeleniumCookie {
+    name: String,
+    value: String,
+    #[serde(default)]
+    domain: Option<String>,
+    #[serde(default = "default_path")]
+    path: String,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expiry: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    same_site: Option<SameSiteName>,
+}
+
+fn default_path() -> String {
+    String::from("/")
+}
+
+impl CookieFormat for Selenium {
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>> {
+        let cookies: Vec<SeleniumCookie> = serde_json::from_str(input)?;
+        cookies
+            .into_iter()
+            .map(|cookie| {
+                let domain = match cookie.domain {
+                    Some(domain) => domain,
+                    None => {
+                        return Err(invalid_data(format!(
+                            "cookie {} has no domain",
+                            cookie.name
+                        )))
+                    }
+                };
+
+                CookieRecord {
+                    host_only: !domain.starts_with('.'),
+                    name: cookie.name,
+                    value: cookie.value,
+                    domain,
+                    path: cookie.path,
+                    expires: cookie.expiry,
+                    secure: cookie.secure,
+                    http_only: cookie.http_only,
+                    same_site: cookie.same_site.map(Into::into),
+                }
+                .into_cookie()
+            })
+            .collect()
+    }
+
+    fn serialize(&self, cookies: &[&Cookie<'static>]) -> io::Result<String> {
+        let cookies = cookies
+            .iter()
+            .map(|cookie| {
+                let record = CookieRecord::from_cookie(cookie);
+                SeleniumCookie {
+                    domain: Some(if record.host_only {
+                        record.domain
+                    } else {
+                        format!(".{}", record.domain)
+                    }),
+                    name: record.name,
+                    value: record.value,
+                    path: record.path,
+                    secure: record.secure,
+                    http_only: record.http_only,
+                    expiry: record.expires,
+                    same_site: record.same_site.map(Into::into),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok(serde_json::to_string_pretty(&cookies)?)
+    }
+}
@@ -0,0 +1,119 @@
+use super::{CookieFormat, CookieRecord, SameSiteName};
+use cookie::SameSite;
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// # Playwright / Puppeteer `storageState.json`
+///
+/// This is the format written by playwright's
+/// `browserContext.storageState({ path })` and read by
+/// `browser.newContext({ storageState })`. Only the `cookies` section
+/// is imported; exported documents contain an empty `origins` list.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{formats::StorageState, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// middleware
+///     .import(&StorageState, r#"{
+///       "cookies": [{
+///         "name": "session", "value": "abc123", "domain": "example.com",
+///         "path": "/", "expires": -1, "httpOnly": true, "secure": true,
+///         "sameSite": "Lax"
+///       }],
+///       "origins": []
+///     }"#)
+///     .await?;
+///
+/// // hand this back to playwright with `newContext({ storageState })`
+/// let storage_state = middleware.export(&StorageState).await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StorageState;
+
+#[derive(Serialize, Deserialize)]
+struct Document {
+    cookies: Vec<StorageStateCookie>,
+    #[serde(default)]
+    origins: Vec<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StorageStateCookie {
+    name: String,
+    value: String,
+    domain: String,
+    path: String,
+    expires: f64,
+    http_only: bool,
+    secure: bool,
+    same_site: SameSiteName,
+}
+
+impl From<StorageStateCookie> for CookieRecord {
+    fn from(cookie: StorageStateCookie) -> Self {
+        Self {
+            host_only: !cookie.domain.starts_with('.'),
+            name: cookie.name,
+            value: cookie.value,
+            domain: cookie.domain,
+            path: cookie.path,
+            // playwright uses -1 for session cookies
+            expires: if cookie.expires < 0.0 {
+                None
+            } else {
+                Some(cookie.expires as i64)
+            },
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+            same_site: Some(cookie.same_site.into()),
+        }
+    }
+}
+
+impl From<CookieRecord> for StorageStateCookie {
+    fn from(record: CookieRecord) -> Self {
+        Self {
+            domain: if record.host_only {
+                record.domain
+            } else {
+                format!(".{}", record.domain)
+            },
+            name: record.name,
+            value: record.value,
+            path: record.path,
+            expires: record.expires.map_or(-1.0, |expires| expires as f64),
+            http_only: record.http_only,
+            secure: record.secure,
+            // browsers treat an unspecified SameSite as Lax
+            same_site: record.same_site.unwrap_or(SameSite::Lax).into(),
+        }
+    }
+}
+
+impl CookieFormat for StorageState {
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>> {
+        let document: Document = serde_json::from_str(input)?;
+        document
+            .cookies
+            .into_iter()
+            .map(|cookie| CookieRecord::from(cookie).into_cookie())
+            .collect()
+    }
+
+    fn serialize(&self, cookies: &[&Cookie<'static>]) -> io::Result<String> {
+        let document = Document {
+            cookies: cookies
+                .iter()
+                .map(|cookie| CookieRecord::from_cookie(cookie).into())
+                .collect(),
+            origins: vec![],
+        };
+
+        Ok(serde_json::to_string_pretty(&document)?)
+    }
+}
@@ -0,0 +1,57 @@
+use super::{invalid_data, readable::ReadableCookie, CookieFormat};
+use cookie_store::Cookie;
+use std::io;
+
+/// # YAML cookie jar
+///
+/// A human-editable list of cookies, available with the `yaml` cargo
+/// feature. Only `name`, `value`, and `domain` are required. A
+/// cookie without `expires` is a session cookie, and `expires` is an
+/// RFC 3339 timestamp.
+///
+/// ```yaml
+/// - name: session
+///   value: abc123
+///   domain: example.com
+///   expires: 2030-01-01T00:00:00Z
+///   secure: true
+///   http_only: true
+/// - name: region
+///   value: eu
+///   domain: example.com
+///   host_only: false # also sent to subdomains
+///   same_site: Lax
+/// ```
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{formats::Yaml, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// middleware
+///     .import(&Yaml, "- { name: session, value: abc123, domain: example.com }")
+///     .await?;
+/// let yaml = middleware.export(&Yaml).await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Yaml;
+
+impl CookieFormat for Yaml {
+    fn parse(&self, input: &str) -> io::Result<Vec<Cookie<'static>>> {
+        let cookies: Vec<ReadableCookie> = serde_yaml::from_str(input).map_err(invalid_data)?;
+        cookies
+            .into_iter()
+            .map(ReadableCookie::into_cookie)
+            .collect()
+    }
+
+    fn serialize(&self, cookies: &[&Cookie<'static>]) -> io::Result<String> {
+        let cookies = cookies
+            .iter()
+            .map(|cookie| ReadableCookie::from_cookie(cookie))
+            .collect::<Vec<_>>();
+
+        serde_yaml::to_string(&cookies).map_err(invalid_data)
+    }
+}
@@ -0,0 +1,33 @@
+//! Access to the jar of a middleware that has been added to a client
+use crate::{CookieJarBackend, CookieMiddleware};
+use cookie_store::CookieStore;
+use std::ops::Deref;
+
+/// # A handle to the cookie jar of a client
+///
+/// Returned by [`CookieMiddleware::build_client`] alongside the
+/// client. It refers to the same cookies as the middleware registered
+/// on the client, and dereferences to that
+/// [`CookieMiddleware`] for inspecting and managing them.
+#[derive(Debug)]
+pub struct CookieJarHandle<B = CookieStore>(CookieMiddleware<B>);
+
+impl<B> CookieJarHandle<B> {
+    pub(crate) fn new(middleware: CookieMiddleware<B>) -> Self {
+        Self(middleware)
+    }
+}
+
+impl<B> Clone for CookieJarHandle<B> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<B: CookieJarBackend> Deref for CookieJarHandle<B> {
+    type Target = CookieMiddleware<B>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
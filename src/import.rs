@@ -0,0 +1,75 @@
+//! Resolving conflicts between imported cookies and the jar
+use cookie_store::{Cookie, CookieExpiration};
+
+/// # What to do when an imported cookie is already in the jar
+///
+/// A conflict is an imported cookie with the same domain, path, and
+/// name as an unexpired cookie in the jar. See
+/// [`CookieMiddleware::import_with`](crate::CookieMiddleware::import_with).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportConflicts {
+    /// Replace the cookie in the jar with the imported one
+    #[default]
+    PreferImported,
+
+    /// Keep the cookie in the jar and skip the imported one
+    PreferExisting,
+
+    /// Keep whichever cookie expires later. A session cookie counts as
+    /// expiring before any persistent cookie, and the cookie in the jar
+    /// is kept if both expire at the same time.
+    PreferNewerExpiry,
+}
+
+impl ImportConflicts {
+    /// Whether `imported` should replace `existing`
+    pub(crate) fn prefers_imported(self, existing: &Cookie<'_>, imported: &Cookie<'_>) -> bool {
+        match self {
+            ImportConflicts::PreferImported => true,
+            ImportConflicts::PreferExisting => false,
+            ImportConflicts::PreferNewerExpiry => {
+                expiry_order(&imported.expires) > expiry_order(&existing.expires)
+            }
+        }
+    }
+}
+
+fn expiry_order(expires: &CookieExpiration) -> Option<i64> {
+    match expires {
+        CookieExpiration::AtUtc(expires) => Some(expires.unix_timestamp()),
+        CookieExpiration::SessionEnd => None,
+    }
+}
+
+/// The outcome of an import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// The number of cookies that were stored, including those that
+    /// replaced a cookie in the jar
+    pub imported: usize,
+
+    /// Each imported cookie that was already in the jar, and how the
+    /// conflict was resolved
+    pub conflicts: Vec<ImportConflict>,
+
+    /// The number of cookies that could not be stored, such as a
+    /// cookie whose domain is not a valid host. The rest of the import
+    /// continues without them.
+    pub skipped: usize,
+}
+
+/// An imported cookie that was already in the jar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportConflict {
+    /// The cookie name
+    pub name: String,
+
+    /// The cookie domain
+    pub domain: String,
+
+    /// The cookie path
+    pub path: String,
+
+    /// Whether the imported cookie replaced the one in the jar
+    pub replaced: bool,
+}
@@ -0,0 +1,171 @@
+//! The bytes of a jar file, independent of the async runtime
+use crate::runtime::{
+    io::{ReadExt, SeekExt, SeekFrom, WriteExt},
+    File,
+};
+use futures_lite::io::{
+    AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt,
+};
+use std::{
+    fmt::{self, Debug},
+    io::{self, Read, Seek, Write},
+    time::SystemTime,
+};
+use surf::utils::async_trait;
+
+/// # Byte-level access to a jar file
+///
+/// [`FileStorage`](crate::FileStorage) serializes the jar, parses it
+/// in its format, and takes any lock file; a `JarIo` only reads and
+/// replaces the bytes. The files of the runtime this crate is built
+/// for implement it, and [`AsyncIo`] adapts any
+/// [`futures-io`](https://docs.rs/futures-io) handle, so a jar can be
+/// kept with smol, an embedded executor, or anything else that can
+/// read, write and seek. Build storage from one with
+/// [`FileStorage::from_io`](crate::FileStorage::from_io).
+#[async_trait]
+pub trait JarIo: Debug + Send + Sync + 'static {
+    /// Reads the whole jar
+    async fn read(&mut self) -> io::Result<Vec<u8>>;
+
+    /// Replaces the whole jar with `bytes`
+    async fn replace(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    /// When the jar was last written, if it can tell. The default
+    /// returns `None`.
+    async fn modified(&mut self) -> io::Result<Option<SystemTime>> {
+        Ok(None)
+    }
+
+    /// The size of the jar in bytes, if it can tell. The default
+    /// returns `None`.
+    async fn size(&mut self) -> io::Result<Option<u64>> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl JarIo for File {
+    async fn read(&mut self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        SeekExt::seek(self, SeekFrom::Start(0)).await?;
+        ReadExt::read_to_end(self, &mut bytes).await?;
+        Ok(bytes)
+    }
+
+    async fn replace(&mut self, bytes: &[u8]) -> io::Result<()> {
+        SeekExt::seek(self, SeekFrom::Start(0)).await?;
+        WriteExt::write_all(self, bytes).await?;
+        self.set_len(bytes.len() as u64).await?;
+        self.sync_all().await
+    }
+
+    async fn modified(&mut self) -> io::Result<Option<SystemTime>> {
+        Ok(Some(self.metadata().await?.modified()?))
+    }
+
+    async fn size(&mut self) -> io::Result<Option<u64>> {
+        Ok(Some(self.metadata().await?.len()))
+    }
+}
+
+// blocking calls made in place, for FileStorage::blocking
+#[async_trait]
+impl JarIo for std::fs::File {
+    async fn read(&mut self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        Seek::seek(self, SeekFrom::Start(0))?;
+        Read::read_to_end(self, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    async fn replace(&mut self, bytes: &[u8]) -> io::Result<()> {
+        Seek::seek(self, SeekFrom::Start(0))?;
+        Write::write_all(self, bytes)?;
+        self.set_len(bytes.len() as u64)?;
+        self.sync_all()
+    }
+
+    async fn modified(&mut self) -> io::Result<Option<SystemTime>> {
+        Ok(Some(self.metadata()?.modified()?))
+    }
+
+    async fn size(&mut self) -> io::Result<Option<u64>> {
+        Ok(Some(self.metadata()?.len()))
+    }
+}
+
+/// # A jar in any `AsyncRead + AsyncWrite + AsyncSeek` handle
+///
+/// Generic handles can't be truncated, so when the jar shrinks the
+/// rest of the previous jar is overwritten with spaces, which are
+/// dropped again when it is read. The handle must be open for reading
+/// and writing.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{AsyncIo, CookieMiddleware, FileStorage};
+///
+/// let file = async_std::fs::OpenOptions::new()
+///     .create(true)
+///     .read(true)
+///     .write(true)
+///     .open("./cookies.ndjson")
+///     .await?;
+/// let middleware = CookieMiddleware::builder()
+///     .storage(FileStorage::from_io(AsyncIo::new(file)))
+///     .build()
+///     .await?;
+/// # Ok(()) }) }
+/// ```
+pub struct AsyncIo<T>(T);
+
+impl<T> AsyncIo<T> {
+    /// Keeps the jar in `io`
+    pub fn new(io: T) -> Self {
+        Self(io)
+    }
+
+    /// The handle the jar is kept in
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Debug for AsyncIo<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AsyncIo")
+            .field(&std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<T> JarIo for AsyncIo<T>
+where
+    T: AsyncRead + AsyncWrite + AsyncSeek + Unpin + Send + Sync + 'static,
+{
+    async fn read(&mut self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        AsyncSeekExt::seek(&mut self.0, SeekFrom::Start(0)).await?;
+        AsyncReadExt::read_to_end(&mut self.0, &mut bytes).await?;
+        let len = bytes.iter().rposition(|b| *b != b' ').map_or(0, |i| i + 1);
+        bytes.truncate(len);
+        Ok(bytes)
+    }
+
+    async fn replace(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let previous = AsyncSeekExt::seek(&mut self.0, SeekFrom::End(0)).await?;
+        let padding = previous.saturating_sub(bytes.len() as u64) as usize;
+        AsyncSeekExt::seek(&mut self.0, SeekFrom::Start(0)).await?;
+        AsyncWriteExt::write_all(&mut self.0, bytes).await?;
+        AsyncWriteExt::write_all(&mut self.0, &vec![b' '; padding]).await?;
+        AsyncWriteExt::flush(&mut self.0).await
+    }
+
+    async fn size(&mut self) -> io::Result<Option<u64>> {
+        AsyncSeekExt::seek(&mut self.0, SeekFrom::End(0))
+            .await
+            .map(Some)
+    }
+}
@@ -0,0 +1,38 @@
+//! Detection of cookie values shared between unrelated domains
+use crate::events::PossibleLeak;
+use crate::CookieJarBackend;
+use cookie_store::Cookie;
+use surf::Url;
+
+/// Values shorter than this, like `1` or `true`, are too common to
+/// indicate a copied credential
+const MIN_VALUE_LEN: usize = 8;
+
+/// Finds cookies about to be sent to `url` whose value is identical to
+/// that of a cookie scoped to a domain that `url` does not match
+pub(crate) fn detect(
+    cookie_store: &impl CookieJarBackend,
+    matches: &[&Cookie<'static>],
+    url: &Url,
+) -> Vec<PossibleLeak> {
+    let mut leaks = vec![];
+
+    for sent in matches {
+        if sent.value().len() < MIN_VALUE_LEN {
+            continue;
+        }
+
+        for source in cookie_store.iter_unexpired() {
+            if source.value() == sent.value() && !source.domain.matches(url) {
+                leaks.push(PossibleLeak {
+                    url: url.clone(),
+                    name: sent.name().to_string(),
+                    source_domain: String::from(&source.domain),
+                    source_name: source.name().to_string(),
+                });
+            }
+        }
+    }
+
+    leaks
+}
@@ -11,26 +11,142 @@
 //!
 //! see [`CookieMiddleware`] for details
 //!
-use async_dup::{Arc, Mutex};
-use async_std::{
-    fs::{File, OpenOptions},
-    prelude::*,
-    sync::RwLock,
-};
+use async_dup::Arc;
+use async_lock::{RwLock, RwLockReadGuard};
+use cookie::Cookie as RawCookie;
+use futures_core::Stream;
 use std::{
-    convert::TryInto,
-    io::{self, Cursor, SeekFrom},
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    io,
     path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant, SystemTime},
 };
 use surf::{
-    http::headers::{COOKIE, SET_COOKIE},
+    http::{
+        self,
+        headers::{HeaderName, HOST, LOCATION, SET_COOKIE},
+        Method, StatusCode,
+    },
     middleware::{Middleware, Next},
     utils::async_trait,
     Client, Request, Response, Result, Url,
 };
 
+pub use cookie;
 pub use cookie_store;
 pub use cookie_store::CookieStore;
+use cookie_store::{Cookie, CookieError};
+pub use publicsuffix;
+
+pub mod formats;
+pub use formats::CookieFormat;
+
+mod acceptance;
+pub use acceptance::CookieAcceptPolicy;
+use acceptance::{DomainFilter, StatusFilter};
+
+mod append_log;
+
+mod autosave;
+use autosave::AutosaveTask;
+
+mod backend;
+pub use backend::CookieJarBackend;
+
+mod budget;
+pub use budget::TruncationStrategy;
+
+mod builder;
+pub use builder::CookieMiddlewareBuilder;
+
+mod dates;
+
+mod debounce;
+use debounce::{Debounce, Schedule};
+
+mod delta;
+pub use delta::{CookieChange, CookieDelta, JarChange};
+
+mod find;
+pub use find::{CookiePattern, CookieView};
+
+#[cfg(feature = "firefox")]
+mod firefox;
+
+mod handle;
+pub use handle::CookieJarHandle;
+
+mod import;
+pub use import::{ImportConflict, ImportConflicts, ImportReport};
+
+mod jar_io;
+pub use jar_io::{AsyncIo, JarIo};
+
+mod leaks;
+
+mod encoding;
+use encoding::{decode_value, encode_value};
+
+pub mod events;
+use events::{
+    CookieEvent, Duplicate, Listeners, Rejection, RejectionReason, StoreOutcome, Stored, Truncation,
+};
+
+mod limits;
+pub use limits::{CookieLimits, Eviction};
+
+mod metadata;
+use metadata::{CookieKey, MetadataTable};
+
+mod policy;
+pub use policy::CookiePolicy;
+
+mod pruning;
+use pruning::AccessLog;
+pub use pruning::PruningPolicy;
+
+mod validation;
+
+mod same_site;
+use same_site::SameSiteContext;
+pub use same_site::SiteForCookies;
+
+mod save_on_drop;
+use save_on_drop::SaveOnDrop;
+
+mod router;
+pub use router::JarRouter;
+
+mod runtime;
+use runtime::{fs::OpenOptions, File};
+
+mod target;
+use target::{CookieHeaderNames, HostAliases, SecureHosts, TargetUrl};
+
+mod snapshot;
+pub use snapshot::CookieJarSnapshot;
+
+mod stats;
+pub use stats::CookieStats;
+use stats::Counters;
+
+mod storage;
+pub use storage::{
+    CookieStorage, FileStorage, PersistenceFailurePolicy, PersistenceSummary, SerializeError,
+};
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteCookieStore;
+
+#[cfg(feature = "sled")]
+mod sled_store;
+#[cfg(feature = "sled")]
+pub use sled_store::SledCookieStore;
 
 /// # A middleware for sending received cookies in surf
 ///
@@ -38,7 +154,28 @@ pub use cookie_store::CookieStore;
 ///
 /// This middleware can optionally be constructed with a file or path
 /// to enable writing "persistent cookies" to disk after every
-/// received response.
+/// received response. Other kinds of storage can be plugged in with
+/// [`CookieStorage`].
+///
+/// ## Tracing
+///
+/// With the `tracing` feature, preparing each request and storing the
+/// cookies from each response happen in `tracing` spans, and every
+/// cookie that is sent, stored, removed, or rejected is reported as a
+/// structured event with its name, domain, and the action taken.
+///
+/// ## Metrics
+///
+/// With the `metrics` feature, the middleware reports to the
+/// [`metrics`](https://docs.rs/metrics) facade:
+///
+/// * `surf_cookies_sent_total`, `surf_cookies_stored_total`, and
+///   `surf_cookies_rejected_total`, counters matching
+///   [`CookieStats`]
+/// * `surf_cookie_jar_size`, a gauge of the unexpired cookies in the
+///   jar, updated after every change
+/// * `surf_cookie_jar_save_duration_seconds`, a histogram of how long
+///   each save takes
 ///
 /// ## Cloning semantics
 ///
@@ -57,24 +194,172 @@ pub use cookie_store::CookieStore;
 /// //                            based on request url
 /// ```
 
-#[derive(Default, Clone, Debug)]
-pub struct CookieMiddleware {
-    cookie_store: Arc<RwLock<CookieStore>>,
-    file: Option<Arc<Mutex<File>>>,
+#[derive(Debug)]
+pub struct CookieMiddleware<B = CookieStore> {
+    cookie_store: Arc<RwLock<B>>,
+    insecure_store: Option<Arc<RwLock<B>>>,
+    storage: Option<std::sync::Arc<dyn CookieStorage>>,
+    path: Option<PathBuf>,
+    preserve_quotes: bool,
+    percent_encode: bool,
+    reject_blank_values: bool,
+    strict_secure: bool,
+    strict_parsing: bool,
+    detect_leaks: bool,
+    hide_http_only: bool,
+    max_lifetime: Option<Duration>,
+    max_cookie_size: Option<usize>,
+    duplicate_cookies: DuplicateCookies,
+    existing_cookie_header: ExistingCookieHeader,
+    accept_policy: CookieAcceptPolicy,
+    status_filter: StatusFilter,
+    domain_filter: DomainFilter,
+    policy: Option<std::sync::Arc<dyn CookiePolicy>>,
+    header_budget: Option<(usize, TruncationStrategy)>,
+    site_for_cookies: Option<Url>,
+    max_redirects: u8,
+    target_url: TargetUrl,
+    host_aliases: HostAliases,
+    secure_hosts: SecureHosts,
+    cookie_header_names: CookieHeaderNames,
+    public_suffix_list: Option<Arc<publicsuffix::List>>,
+    pruning: Option<PruningPolicy>,
+    limits: Option<CookieLimits>,
+    max_failed_saves: Option<usize>,
+    persistence_failure: PersistenceFailurePolicy,
+    manual_persistence: bool,
+    purge_on_save: bool,
+    save_debounce: Option<Duration>,
+    reload_on_change: bool,
+    save_on_drop: Option<Arc<SaveOnDrop>>,
+    persist_session_cookies: bool,
+    discard_session_cookies: bool,
+    autosave_task: Option<std::sync::Arc<AutosaveTask>>,
+    dirty: Arc<AtomicBool>,
+    last_modified: Arc<std::sync::Mutex<Option<SystemTime>>>,
+    access_log: Arc<AccessLog>,
+    metadata: Arc<MetadataTable>,
+    debounce: Arc<Debounce>,
+    listeners: Listeners,
+    counters: Arc<Counters>,
+}
+
+impl<B> Clone for CookieMiddleware<B> {
+    fn clone(&self) -> Self {
+        Self {
+            cookie_store: self.cookie_store.clone(),
+            insecure_store: self.insecure_store.clone(),
+            storage: self.storage.clone(),
+            path: self.path.clone(),
+            preserve_quotes: self.preserve_quotes,
+            percent_encode: self.percent_encode,
+            reject_blank_values: self.reject_blank_values,
+            strict_secure: self.strict_secure,
+            strict_parsing: self.strict_parsing,
+            detect_leaks: self.detect_leaks,
+            hide_http_only: self.hide_http_only,
+            max_lifetime: self.max_lifetime,
+            max_cookie_size: self.max_cookie_size,
+            duplicate_cookies: self.duplicate_cookies,
+            existing_cookie_header: self.existing_cookie_header,
+            accept_policy: self.accept_policy,
+            status_filter: self.status_filter.clone(),
+            domain_filter: self.domain_filter.clone(),
+            policy: self.policy.clone(),
+            header_budget: self.header_budget,
+            site_for_cookies: self.site_for_cookies.clone(),
+            max_redirects: self.max_redirects,
+            target_url: self.target_url.clone(),
+            host_aliases: self.host_aliases.clone(),
+            secure_hosts: self.secure_hosts.clone(),
+            cookie_header_names: self.cookie_header_names.clone(),
+            public_suffix_list: self.public_suffix_list.clone(),
+            pruning: self.pruning,
+            limits: self.limits,
+            max_failed_saves: self.max_failed_saves,
+            persistence_failure: self.persistence_failure.clone(),
+            manual_persistence: self.manual_persistence,
+            purge_on_save: self.purge_on_save,
+            save_debounce: self.save_debounce,
+            reload_on_change: self.reload_on_change,
+            save_on_drop: self.save_on_drop.clone(),
+            persist_session_cookies: self.persist_session_cookies,
+            discard_session_cookies: self.discard_session_cookies,
+            autosave_task: self.autosave_task.clone(),
+            dirty: self.dirty.clone(),
+            last_modified: self.last_modified.clone(),
+            access_log: self.access_log.clone(),
+            metadata: self.metadata.clone(),
+            debounce: self.debounce.clone(),
+            listeners: self.listeners.clone(),
+            counters: self.counters.clone(),
+        }
+    }
+}
+
+impl<B: CookieJarBackend + Default> Default for CookieMiddleware<B> {
+    fn default() -> Self {
+        Self::with_cookie_store(B::default())
+    }
 }
 
 #[async_trait]
-impl Middleware for CookieMiddleware {
+impl<B: CookieJarBackend> Middleware for CookieMiddleware<B> {
     async fn handle(&self, mut req: Request, client: Client, next: Next<'_>) -> Result<Response> {
-        let url = req.url().clone();
-        self.set_cookies(&mut req).await;
-        let res = next.run(req, client).await?;
-        self.store_cookies(&url, &res).await?;
-        Ok(res)
+        self.start_autosave();
+        let mut redirects = 0;
+        let first_party = self.target_url.resolve(&req);
+        loop {
+            let url = req.url().clone();
+            let target = self.target_url.resolve(&req);
+            let next_hop = (redirects < self.max_redirects).then(|| req.clone());
+            self.reload_if_changed().await?;
+            self.prepare_request(&mut req).await;
+            let mut res = next.run(req, client.clone()).await?;
+            let delta = self.store_cookies(&target, &res, &first_party).await?;
+            res.insert_ext(delta);
+
+            let location = match (next_hop, redirect_location(&url, &res)?) {
+                (Some(next_hop), Some(location)) => {
+                    req = next_hop;
+                    location
+                }
+                _ => return Ok(res),
+            };
+
+            log::trace!("following redirect from {} to {}", url, location);
+            let hop: &mut http::Request = req.as_mut();
+            if res.status() == StatusCode::SeeOther {
+                hop.set_method(Method::Get);
+            }
+            if location.host() != url.host() {
+                // cookies the caller set were meant for the original host
+                hop.remove_header(HOST);
+                hop.remove_header(self.cookie_header_names.for_url(&target));
+            }
+            *hop.url_mut() = location;
+            redirects += 1;
+        }
     }
 }
 
 impl CookieMiddleware {
+    /// The longest cookie lifetime that Chrome allows, 400 days. For
+    /// use with [`with_max_lifetime`](Self::with_max_lifetime).
+    pub const BROWSER_MAX_LIFETIME: Duration = Duration::from_secs(400 * 24 * 60 * 60);
+
+    /// The largest cookie that browsers store, 4096 bytes of name and
+    /// value, and the default for
+    /// [`with_max_cookie_size`](Self::with_max_cookie_size)
+    pub const BROWSER_MAX_COOKIE_SIZE: usize = 4096;
+
+    /// The environment variable read for additional
+    /// [secure hosts](Self::with_secure_hosts) by
+    /// [`with_secure_hosts_from_env`](Self::with_secure_hosts_from_env):
+    /// `SURF_COOKIE_SECURE_HOSTS`, a comma-separated list of host
+    /// patterns.
+    pub const SECURE_HOSTS_ENV: &'static str = "SURF_COOKIE_SECURE_HOSTS";
+
     /// Builds a new CookieMiddleware
     ///
     /// # Example
@@ -93,24 +378,6 @@ impl CookieMiddleware {
         Self::with_cookie_store(Default::default())
     }
 
-    /// Builds a CookieMiddleware with an existing [`cookie_store::CookieStore`]
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// use surf_cookie_middleware::{CookieStore, CookieMiddleware};
-    ///
-    /// let cookie_store = CookieStore::default();
-    /// let client = surf::Client::new()
-    ///     .with(CookieMiddleware::with_cookie_store(cookie_store));
-    /// ```
-    pub fn with_cookie_store(cookie_store: CookieStore) -> Self {
-        Self {
-            cookie_store: Arc::new(RwLock::new(cookie_store)),
-            file: None,
-        }
-    }
-
     /// Builds a CookieMiddleware from a path to a filesystem cookie
     /// jar. These jars are stored in [ndjson](http://ndjson.org/)
     /// format. If the file does not exist, it will be created. If the
@@ -133,25 +400,12 @@ impl CookieMiddleware {
     /// # Ok(()) }) }
     /// ```
     pub async fn from_path(path: impl Into<PathBuf>) -> io::Result<Self> {
-        let path = path.into();
-        let file = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .write(true)
-            .open(&path)
-            .await?;
-
-        Self::from_file(file).await
-    }
-
-    async fn load_from_file(file: &mut File) -> Option<CookieStore> {
-        let mut buf = Vec::new();
-        file.read_to_end(&mut buf).await.ok();
-        CookieStore::load_json(Cursor::new(&buf[..])).ok()
+        Self::builder().path(path).build().await
     }
 
     /// Builds a CookieMiddleware from a File (either
-    /// [`async_std::fs::File`] or [`std::fs::File`]) that represents
+    /// `async_std::fs::File`, `tokio::fs::File` with the `tokio`
+    /// feature, or [`std::fs::File`]) that represents
     /// a filesystem cookie jar. These jars are stored in
     /// [ndjson](http://ndjson.org/) format. The cookie jar will be
     /// initialized with any cookies contained in this file, and
@@ -174,63 +428,3404 @@ impl CookieMiddleware {
     /// # Ok(()) }) }
     /// ```
     pub async fn from_file(file: impl Into<File>) -> io::Result<Self> {
-        let mut file = file.into();
-        let cookie_store = Self::load_from_file(&mut file).await;
-        Ok(Self {
-            file: Some(Arc::new(Mutex::new(file))),
-            cookie_store: Arc::new(RwLock::new(cookie_store.unwrap_or_default())),
-        })
+        Self::builder().file(file).build().await
+    }
+
+    /// Builds a CookieMiddleware from a path to a Netscape
+    /// `cookies.txt` file, the format curl reads with `-b` and writes
+    /// with `-c`. If the file does not exist, it will be created. The
+    /// cookie jar is initialized with the cookies in the file, and
+    /// persistent cookies are written back to it in the same format,
+    /// so the jar can be shared with curl scripts.
+    ///
+    /// See [`formats::Netscape`] for details of the format.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let client = surf::Client::new()
+    ///     .with(CookieMiddleware::from_netscape_path("./cookies.txt").await?);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn from_netscape_path(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)
+            .await?;
+
+        Self::builder()
+            .storage(FileStorage::with_format(file, formats::Netscape))
+            .build()
+            .await
     }
 
-    async fn save(&self) -> Result<()> {
-        if let Some(ref file) = self.file {
-            let mut string: Vec<u8> = vec![0];
-            let mut cursor = Cursor::new(&mut string);
+    /// Starts a [`CookieMiddlewareBuilder`], for combining an existing
+    /// cookie store with file persistence
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::{CookieStore, CookieMiddleware};
+    ///
+    /// let client = surf::Client::new().with(
+    ///     CookieMiddleware::builder()
+    ///         .cookie_store(CookieStore::default())
+    ///         .path("./cookies.ndjson")
+    ///         .build()
+    ///         .await?,
+    /// );
+    /// # Ok(()) }) }
+    /// ```
+    pub fn builder() -> CookieMiddlewareBuilder {
+        CookieMiddlewareBuilder::default()
+    }
+}
 
-            self.cookie_store
-                .read()
-                .await
-                .save_json(&mut cursor)
-                .unwrap();
+impl<B: CookieJarBackend> CookieMiddleware<B> {
+    /// Builds a CookieMiddleware with an existing
+    /// [`cookie_store::CookieStore`], or any other [`CookieJarBackend`]
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{CookieStore, CookieMiddleware};
+    ///
+    /// let cookie_store = CookieStore::default();
+    /// let client = surf::Client::new()
+    ///     .with(CookieMiddleware::with_cookie_store(cookie_store));
+    /// ```
+    pub fn with_cookie_store(cookie_store: B) -> Self {
+        Self::with_shared_store(std::sync::Arc::new(RwLock::new(cookie_store)))
+    }
 
-            let mut file = file.lock();
-            file.seek(SeekFrom::Start(0)).await?;
-            file.write_all(&string[..]).await?;
-            file.set_len(string.len().try_into()?).await?;
-            file.sync_all().await?;
+    /// Builds a CookieMiddleware around a cookie store that is shared
+    /// with other components, such as another client or a debugging
+    /// task. Cookies received by this middleware are visible through
+    /// `cookie_store` immediately, and cookies it holds are sent with
+    /// the next request. Changes made through `cookie_store` directly
+    /// are not persisted until the jar is next saved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_lock::RwLock;
+    /// use std::sync::Arc;
+    /// use surf_cookie_middleware::{CookieStore, CookieMiddleware};
+    ///
+    /// let cookie_store = Arc::new(RwLock::new(CookieStore::default()));
+    /// let client = surf::Client::new()
+    ///     .with(CookieMiddleware::with_shared_store(cookie_store.clone()));
+    /// ```
+    pub fn with_shared_store(cookie_store: std::sync::Arc<RwLock<B>>) -> Self {
+        Self {
+            cookie_store: Arc(cookie_store),
+            insecure_store: None,
+            storage: None,
+            path: None,
+            preserve_quotes: false,
+            percent_encode: false,
+            reject_blank_values: false,
+            strict_secure: false,
+            strict_parsing: false,
+            detect_leaks: false,
+            hide_http_only: false,
+            max_lifetime: None,
+            max_cookie_size: Some(<CookieMiddleware>::BROWSER_MAX_COOKIE_SIZE),
+            duplicate_cookies: DuplicateCookies::default(),
+            existing_cookie_header: ExistingCookieHeader::default(),
+            accept_policy: CookieAcceptPolicy::default(),
+            status_filter: StatusFilter::default(),
+            domain_filter: DomainFilter::default(),
+            policy: None,
+            header_budget: None,
+            site_for_cookies: None,
+            max_redirects: 0,
+            target_url: TargetUrl::default(),
+            host_aliases: HostAliases::default(),
+            secure_hosts: SecureHosts::default(),
+            cookie_header_names: CookieHeaderNames::default(),
+            public_suffix_list: None,
+            pruning: None,
+            limits: None,
+            max_failed_saves: None,
+            persistence_failure: PersistenceFailurePolicy::default(),
+            manual_persistence: false,
+            purge_on_save: false,
+            save_debounce: None,
+            reload_on_change: false,
+            save_on_drop: None,
+            persist_session_cookies: false,
+            discard_session_cookies: false,
+            autosave_task: None,
+            dirty: Arc::default(),
+            last_modified: Arc::default(),
+            access_log: Arc::default(),
+            metadata: Arc::default(),
+            debounce: Arc::default(),
+            listeners: Listeners::default(),
+            counters: Arc::default(),
         }
-        Ok(())
     }
 
-    async fn set_cookies(&self, req: &mut Request) {
-        let cookie_store = self.cookie_store.read().await;
-        let mut matches = cookie_store.matches(req.url());
+    /// Keep the double quotes around quoted cookie values. By default,
+    /// a cookie received as `name="a b c"` is stored with the value
+    /// `a b c` and sent back as `name=a b c`. With this enabled, the
+    /// value is stored and sent back exactly as `"a b c"`, which some
+    /// legacy servers require.
+    ///
+    /// Quotes are retained in memory only; cookies reloaded from a
+    /// persisted jar are unquoted again.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_preserved_quotes(true);
+    /// ```
+    pub fn with_preserved_quotes(mut self, preserve_quotes: bool) -> Self {
+        self.preserve_quotes = preserve_quotes;
+        self
+    }
 
-        // clients "SHOULD" sort by path length
-        matches.sort_by(|a, b| b.path.len().cmp(&a.path.len()));
+    /// Percent-encode cookie values that contain characters outside
+    /// of the RFC 6265 cookie-octet grammar, such as spaces, commas,
+    /// semicolons, or non-ascii text.
+    ///
+    /// With this enabled, values are stored in the jar as they appear
+    /// on the wire: [`import`](Self::import) encodes imported values,
+    /// any remaining invalid characters are encoded when the Cookie
+    /// header is sent, and [`export`](Self::export) decodes values
+    /// again. Values that are already percent-encoded are left as
+    /// they are.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_percent_encoding(true);
+    /// ```
+    pub fn with_percent_encoding(mut self, percent_encode: bool) -> Self {
+        self.percent_encode = percent_encode;
+        self
+    }
 
-        let values = matches
-            .iter()
-            .map(|cookie| format!("{}={}", cookie.name(), cookie.value()))
-            .collect::<Vec<_>>()
-            .join("; ");
+    /// Drop cookies whose value is empty or only whitespace, such as
+    /// `name=` or `name="  "`, reporting them as
+    /// [`RejectionReason::BlankValue`]. By default these are stored
+    /// with an empty value. Cookies with an empty name are always
+    /// rejected.
+    ///
+    /// Note that servers commonly clear a cookie by sending an empty
+    /// value with an expiry in the past. Those are still honored, since
+    /// that removes the cookie rather than storing a blank one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_blank_values_rejected(true);
+    /// ```
+    pub fn with_blank_values_rejected(mut self, reject_blank_values: bool) -> Self {
+        self.reject_blank_values = reject_blank_values;
+        self
+    }
 
-        req.insert_header(COOKIE, values);
+    /// Shorten the lifetime of cookies that would otherwise outlive
+    /// `max_lifetime` from the time they are received, the way
+    /// browsers do. A longer Max-Age or Expires is clamped to
+    /// `max_lifetime` when the cookie is stored. By default, cookie
+    /// lifetimes are not limited.
+    ///
+    /// [`BROWSER_MAX_LIFETIME`](Self::BROWSER_MAX_LIFETIME) matches
+    /// Chrome's cap, which is useful when testing a web property
+    /// against what a browser would actually keep.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_max_lifetime(Some(CookieMiddleware::BROWSER_MAX_LIFETIME));
+    /// ```
+    pub fn with_max_lifetime(mut self, max_lifetime: Option<Duration>) -> Self {
+        self.max_lifetime = max_lifetime;
+        self
     }
 
-    async fn store_cookies(&self, request_url: &Url, res: &Response) -> Result<()> {
-        if let Some(set_cookies) = res.header(SET_COOKIE) {
-            let mut cookie_store = self.cookie_store.write().await;
-            for cookie in set_cookies {
-                match cookie_store.parse(cookie.as_str(), request_url) {
-                    Ok(action) => log::trace!("cookie action: {:?}", action),
-                    Err(e) => log::trace!("cookie parse error: {:?}", e),
-                }
+    /// Reject cookies whose name and value together are longer than
+    /// `max_bytes`, reporting them as
+    /// [`RejectionReason::TooLarge`],
+    /// so that a hostile or buggy server can't bloat the jar. The
+    /// default is
+    /// [`BROWSER_MAX_COOKIE_SIZE`](CookieMiddleware::BROWSER_MAX_COOKIE_SIZE),
+    /// and None accepts cookies of any size.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_max_cookie_size(Some(1024));
+    /// ```
+    pub fn with_max_cookie_size(mut self, max_bytes: Option<usize>) -> Self {
+        self.max_cookie_size = max_bytes;
+        self
+    }
+
+    /// Choose which cookie is kept when a single response sets the
+    /// same cookie (name, domain, and path) more than once. Defaults
+    /// to [`DuplicateCookies::LastWins`]. Either way, each collision
+    /// is reported as a [`CookieEvent::Duplicate`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{CookieMiddleware, DuplicateCookies};
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_duplicate_cookies(DuplicateCookies::FirstWins);
+    /// ```
+    pub fn with_duplicate_cookies(mut self, duplicate_cookies: DuplicateCookies) -> Self {
+        self.duplicate_cookies = duplicate_cookies;
+        self
+    }
+
+    /// Choose what happens when the application has already set a
+    /// Cookie header on a request. Defaults to
+    /// [`ExistingCookieHeader::Append`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{CookieMiddleware, ExistingCookieHeader};
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_existing_cookie_header(ExistingCookieHeader::Skip);
+    /// ```
+    pub fn with_existing_cookie_header(
+        mut self,
+        existing_cookie_header: ExistingCookieHeader,
+    ) -> Self {
+        self.existing_cookie_header = existing_cookie_header;
+        self
+    }
+
+    /// Keep the Cookie header sent with each request to at most
+    /// `max_bytes`. When the matching cookies would not fit, cookies
+    /// are left out according to `strategy` rather than sending an
+    /// oversized header, and a [`CookieEvent::Truncated`] event lists
+    /// the cookies that were dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{CookieMiddleware, TruncationStrategy};
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_cookie_header_budget(4096, TruncationStrategy::DropLargest);
+    /// ```
+    pub fn with_cookie_header_budget(
+        mut self,
+        max_bytes: usize,
+        strategy: TruncationStrategy,
+    ) -> Self {
+        self.header_budget = Some((max_bytes, strategy));
+        self
+    }
+
+    /// Choose which responses cookies are stored from. By default,
+    /// every cookie a server sends is stored. See
+    /// [`CookieAcceptPolicy`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{CookieAcceptPolicy, CookieMiddleware};
+    /// let middleware = CookieMiddleware::new().with_accept_policy(CookieAcceptPolicy::AcceptNone);
+    /// ```
+    pub fn with_accept_policy(mut self, accept_policy: CookieAcceptPolicy) -> Self {
+        self.accept_policy = accept_policy;
+        self
+    }
+
+    /// Don't store cookies from responses with a 4xx or 5xx status,
+    /// such as bogus cookies that a flaky backend sends with its error
+    /// pages. They are reported as
+    /// [`RejectionReason::IgnoredStatus`].
+    /// For other rules, see
+    /// [`with_status_filter`](Self::with_status_filter).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_error_responses_ignored(true);
+    /// ```
+    pub fn with_error_responses_ignored(self, ignored: bool) -> Self {
+        if ignored {
+            self.with_status_filter(|status| !status.is_client_error() && !status.is_server_error())
+        } else {
+            Self {
+                status_filter: StatusFilter::default(),
+                ..self
             }
         }
+    }
+
+    /// Store cookies only from responses whose status `stores` returns
+    /// true for. Cookies from other responses are reported as
+    /// [`RejectionReason::IgnoredStatus`].
+    /// This replaces
+    /// [`with_error_responses_ignored`](Self::with_error_responses_ignored).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf::StatusCode;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_status_filter(|status| status != StatusCode::ServiceUnavailable);
+    /// ```
+    pub fn with_status_filter(
+        mut self,
+        stores: impl Fn(StatusCode) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.status_filter = StatusFilter::new(stores);
+        self
+    }
 
-        self.save().await?;
+    /// Reject cookies with the Secure attribute that are set by a
+    /// response to a plain http request, as RFC 6265bis requires and
+    /// browsers do. By default they are stored, but like every Secure
+    /// cookie, they are only ever sent over https.
+    ///
+    /// Localhost and loopback addresses, and hosts configured
+    /// [`with_secure_hosts`](Self::with_secure_hosts), are secure
+    /// contexts even over plain http.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_strict_secure_cookies(true);
+    /// ```
+    pub fn with_strict_secure_cookies(mut self, strict_secure: bool) -> Self {
+        self.strict_secure = strict_secure;
+        self
+    }
 
-        Ok(())
+    /// Fail requests whose response has a Set-Cookie header that can't
+    /// be parsed, such as one with no name-value pair, with a
+    /// [`MalformedSetCookie`](events::MalformedSetCookie) error and a
+    /// 502 Bad Gateway status. The well-formed cookies in the response
+    /// are still stored. By default malformed headers are dropped and
+    /// only reported to [event listeners](Self::with_event_listener),
+    /// as browsers do. This is meant for test suites that want to
+    /// catch an api emitting malformed cookies.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_strict_parsing(true);
+    /// ```
+    pub fn with_strict_parsing(mut self, strict_parsing: bool) -> Self {
+        self.strict_parsing = strict_parsing;
+        self
+    }
+
+    /// Store only cookies scoped to a domain matching one of
+    /// `patterns`, such as `*.mycompany.com`. Any other cookie is
+    /// rejected when it is received, and so is never sent or persisted.
+    /// Cookies set with [`set_cookie`](Self::set_cookie) or imported
+    /// are not filtered.
+    ///
+    /// A pattern is either an exact domain, like `mycompany.com`, or a
+    /// domain suffix starting with `.` or `*.`, like `*.mycompany.com`,
+    /// which matches any subdomain but not `mycompany.com` itself.
+    /// Cookies set with `Domain=mycompany.com` are scoped to
+    /// `mycompany.com`, so list both to allow every cookie for a site.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware =
+    ///     CookieMiddleware::new().with_allowed_domains(vec!["mycompany.com", "*.mycompany.com"]);
+    /// ```
+    pub fn with_allowed_domains(
+        mut self,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.domain_filter
+            .allow(patterns.into_iter().map(Into::into));
+        self
     }
+
+    /// Reject cookies scoped to a domain matching one of `patterns`,
+    /// even if it is [allowed](Self::with_allowed_domains). Patterns
+    /// are as in [`with_allowed_domains`](Self::with_allowed_domains).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_blocked_domains(vec!["*.doubleclick.net"]);
+    /// ```
+    pub fn with_blocked_domains(
+        mut self,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.domain_filter
+            .block(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Consult `policy` before storing or sending each cookie. See
+    /// [`CookiePolicy`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf::Url;
+    /// use surf_cookie_middleware::{cookie_store::Cookie, CookieMiddleware, CookiePolicy};
+    ///
+    /// #[derive(Debug)]
+    /// struct NoAnalytics;
+    ///
+    /// impl CookiePolicy for NoAnalytics {
+    ///     fn allow_store(&self, cookie: &Cookie<'_>, _url: &Url) -> bool {
+    ///         !cookie.name().starts_with("_ga")
+    ///     }
+    /// }
+    ///
+    /// let middleware = CookieMiddleware::new().with_policy(NoAnalytics);
+    /// ```
+    pub fn with_policy(mut self, policy: impl CookiePolicy) -> Self {
+        self.policy = Some(std::sync::Arc::new(policy));
+        self
+    }
+
+    /// Send requests as if they were made by a page at
+    /// `site_for_cookies`, withholding SameSite cookies from requests
+    /// to other sites as a browser would. See [`SiteForCookies`], which
+    /// can also be set on a single request.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> {
+    /// use surf::Url;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_site_for_cookies(Some(Url::parse("https://blog.example.net/")?));
+    /// # Ok(()) }
+    /// ```
+    pub fn with_site_for_cookies(mut self, site_for_cookies: Option<Url>) -> Self {
+        self.site_for_cookies = site_for_cookies;
+        self
+    }
+
+    /// Follow up to `max_redirects` redirects within this
+    /// middleware. Each hop is sent with the cookies that match its
+    /// own url, and cookies set by the intermediate 3xx responses are
+    /// stored before the next hop is sent. The default, zero, leaves
+    /// redirects to the caller.
+    ///
+    /// Redirected requests are sent without a body, and a 303 See
+    /// Other is followed with a GET. A Cookie header set by the caller
+    /// is not sent to other hosts. When using this, don't also add
+    /// surf's [`Redirect`](surf::middleware::Redirect) middleware.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let client = surf::Client::new().with(CookieMiddleware::new().with_redirects(5));
+    /// ```
+    pub fn with_redirects(mut self, max_redirects: u8) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    /// Determine the url that a request's cookies are matched against
+    /// and stored for.
+    ///
+    /// By default this is the request url, with its host replaced by
+    /// the request's Host header if it has one, so a request addressed
+    /// to a proxy but carrying `Host: example.com` uses the cookies for
+    /// example.com. Requests sent through a proxy configured on the
+    /// http client already carry the target url and need nothing
+    /// special. For other setups, such as a gateway that encodes the
+    /// target in the path, provide the mapping here.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf::Url;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// // requests look like http://gateway.local/example.com/some/path
+    /// let middleware = CookieMiddleware::new().with_target_url(|req| {
+    ///     let url = req.url();
+    ///     let target = url.path().trim_start_matches('/');
+    ///     Url::parse(&format!("https://{}", target)).unwrap_or_else(|_| url.clone())
+    /// });
+    /// ```
+    pub fn with_target_url(
+        mut self,
+        resolver: impl Fn(&Request) -> Url + Send + Sync + 'static,
+    ) -> Self {
+        self.target_url = TargetUrl::new(resolver);
+        self
+    }
+
+    /// Share cookies between a group of hosts, such as
+    /// `app.internal` and `app.example.com` fronting the same backend.
+    /// Cookies received from any host in the group are stored for the
+    /// first host, and sent to every host in the group. A Domain
+    /// attribute naming a host in the group is treated the same way.
+    ///
+    /// Call this once per group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_host_aliases(vec!["app.example.com", "app.internal", "localhost"]);
+    /// ```
+    pub fn with_host_aliases(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.host_aliases
+            .add_group(hosts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keep cookies received over `http` entirely separate from those
+    /// received over `https` for the same host, so that neither scheme
+    /// sees the other's cookies. This is useful for security testing
+    /// tools that observe scheme-dependent server behavior.
+    ///
+    /// The `https` cookies are the main jar, which is the one that is
+    /// persisted, imported into, and exported. The `http` cookies are
+    /// kept in memory only, and are shared by all clones of this
+    /// middleware.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_scheme_separation(true);
+    /// ```
+    pub fn with_scheme_separation(mut self, separate_schemes: bool) -> Self
+    where
+        B: Default,
+    {
+        self.insecure_store = if separate_schemes {
+            Some(Arc::new(RwLock::new(B::default())))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Treat hosts matching any of `patterns` as secure contexts even
+    /// when they are requested over plain http, for example behind a
+    /// TLS-terminating corporate proxy. Secure cookies are accepted
+    /// from and sent to these hosts as if they used https.
+    ///
+    /// A pattern is either an exact host, like `proxy.corp`, or a
+    /// domain suffix starting with `.` or `*.`, like `*.internal`,
+    /// which matches any subdomain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_secure_hosts(vec!["*.internal", "localhost"]);
+    /// ```
+    pub fn with_secure_hosts(
+        mut self,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.secure_hosts
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds the [secure hosts](Self::with_secure_hosts) listed in the
+    /// [`SECURE_HOSTS_ENV`](CookieMiddleware::SECURE_HOSTS_ENV)
+    /// environment variable, if it is set, so operators can adjust
+    /// them without code changes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_secure_hosts_from_env();
+    /// ```
+    pub fn with_secure_hosts_from_env(mut self) -> Self {
+        if let Ok(patterns) = std::env::var(<CookieMiddleware>::SECURE_HOSTS_ENV) {
+            self.secure_hosts.parse(&patterns);
+        }
+        self
+    }
+
+    /// Send cookies to hosts matching `host_pattern` in the header
+    /// `header_name` instead of Cookie, for gateways that expect session
+    /// state in a header such as `X-Cookie`. Cookies are still received
+    /// from Set-Cookie. Patterns are matched as in
+    /// [`with_secure_hosts`](Self::with_secure_hosts), and the first
+    /// matching pattern wins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_cookie_header_name("*.gateway.example.com", "X-Cookie");
+    /// ```
+    pub fn with_cookie_header_name(
+        mut self,
+        host_pattern: &str,
+        header_name: impl Into<HeaderName>,
+    ) -> Self {
+        self.cookie_header_names
+            .push(host_pattern, header_name.into());
+        self
+    }
+
+    /// Provide a public suffix list, such as
+    /// `publicsuffix::List::from_path("public_suffix_list.dat")`, for
+    /// validating domains passed to
+    /// [`set_for_registrable_domain`](Self::set_for_registrable_domain).
+    ///
+    /// To also reject Set-Cookie headers that scope a cookie to a
+    /// public suffix, build the middleware
+    /// [`with_cookie_store`](Self::with_cookie_store) from a
+    /// [`CookieStore::with_suffix_list`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{publicsuffix::List, CookieMiddleware};
+    /// let list = List::from_str("// ===BEGIN ICANN DOMAINS===\ncom\nuk\nco.uk\n").unwrap();
+    /// let middleware = CookieMiddleware::new().with_public_suffix_list(list);
+    /// ```
+    pub fn with_public_suffix_list(mut self, list: publicsuffix::List) -> Self {
+        self.public_suffix_list = Some(Arc::new(list));
+        self
+    }
+
+    /// Report a [`CookieEvent::PossibleLeak`] whenever a cookie about
+    /// to be sent has the same value as a cookie scoped to a domain
+    /// that the request does not match, which can indicate a token that
+    /// was copied between sites. Short values such as `1` or `true` are
+    /// ignored. This is off by default, since it compares each sent
+    /// cookie against the whole jar.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{events::CookieEvent, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_leak_detection(true)
+    ///     .with_event_listener(|event| {
+    ///         if let CookieEvent::PossibleLeak(leak) = event {
+    ///             eprintln!("{} may leak a credential from {}", leak.name, leak.source_domain);
+    ///         }
+    ///     });
+    /// ```
+    pub fn with_leak_detection(mut self, detect_leaks: bool) -> Self {
+        self.detect_leaks = detect_leaks;
+        self
+    }
+
+    /// Leave HttpOnly cookies out of [`find`](Self::find),
+    /// [`cookies`](Self::cookies),
+    /// [`matches_for_url`](Self::matches_for_url), and
+    /// [`get`](Self::get), for applications that show cookie values to
+    /// scripts or users. They are still stored, sent with requests, and
+    /// persisted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_http_only_hidden(true);
+    /// ```
+    pub fn with_http_only_hidden(mut self, hide_http_only: bool) -> Self {
+        self.hide_http_only = hide_http_only;
+        self
+    }
+
+    /// Removes cookies that have been neither sent nor set for a while,
+    /// according to `pruning`. This keeps the jar of a long-running
+    /// client from growing without bound when servers set cookies with
+    /// distant expiry dates. Pruning is disabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use surf_cookie_middleware::{CookieMiddleware, PruningPolicy};
+    ///
+    /// let ninety_days = Duration::from_secs(90 * 24 * 60 * 60);
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_pruning(Some(PruningPolicy::new(ninety_days)));
+    /// ```
+    pub fn with_pruning(mut self, pruning: Option<PruningPolicy>) -> Self {
+        self.pruning = pruning;
+        self
+    }
+
+    /// Cap the number of cookies the jar holds, per site and in total,
+    /// evicting cookies as needed when a response or
+    /// [`set_cookie`](Self::set_cookie) takes it over a limit. See
+    /// [`CookieLimits`]. By default the jar is unlimited.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{CookieLimits, CookieMiddleware, Eviction};
+    ///
+    /// let limits = CookieLimits::new()
+    ///     .max_per_site(50)
+    ///     .eviction(Eviction::Oldest);
+    /// let middleware = CookieMiddleware::new().with_limits(Some(limits));
+    /// ```
+    pub fn with_limits(mut self, limits: Option<CookieLimits>) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Keep working when the jar file can't be written. Cookies are
+    /// always held in memory, so when persisting fails, the middleware
+    /// logs the error and counts a failed save instead of failing the
+    /// request. Each later save retries, and a successful save writes
+    /// every change that was held back. Once `max_failed_saves` saves
+    /// in a row have failed, errors are returned again. A save can
+    /// cover several changes, such as with
+    /// [`with_save_debounce`](Self::with_save_debounce), so this does
+    /// not bound the number of changes that are waiting.
+    ///
+    /// The consecutive failures are reported as
+    /// [`CookieStats::unsaved`]. By default there is no quarantine and
+    /// every persistence error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new().with_persistence_quarantine(Some(1000));
+    /// assert_eq!(middleware.stats().unsaved, 0);
+    /// ```
+    pub fn with_persistence_quarantine(mut self, max_failed_saves: Option<usize>) -> Self {
+        self.max_failed_saves = max_failed_saves;
+        self
+    }
+
+    /// Decide what a request does when the jar can't be saved after
+    /// its response is received. By default the request fails with the
+    /// error, as [`PersistenceFailurePolicy::FailRequest`]. Cookies from
+    /// the response are stored in memory either way, and the next save
+    /// retries. Errors held back by
+    /// [`with_persistence_quarantine`](Self::with_persistence_quarantine)
+    /// never reach this policy, and calls such as [`save`](Self::save)
+    /// always return their errors.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{CookieMiddleware, PersistenceFailurePolicy};
+    ///
+    /// let middleware = CookieMiddleware::new().with_persistence_failure_policy(
+    ///     PersistenceFailurePolicy::callback(|e| eprintln!("cookie jar not saved: {}", e)),
+    /// );
+    /// ```
+    pub fn with_persistence_failure_policy(mut self, policy: PersistenceFailurePolicy) -> Self {
+        self.persistence_failure = policy;
+        self
+    }
+
+    /// Only persist the jar when [`save`](Self::save) or
+    /// [`compact`](Self::compact) is called. By default, the jar is
+    /// persisted after every response that sets cookies and after
+    /// every change made through this middleware, such as an import.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_manual_persistence(true);
+    /// // ... requests ...
+    /// middleware.save().await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub fn with_manual_persistence(mut self, manual_persistence: bool) -> Self {
+        self.manual_persistence = manual_persistence;
+        self
+    }
+
+    /// [Purge](Self::purge_expired) expired cookies from memory each
+    /// time the jar is persisted. Expired cookies are never written
+    /// either way, but without this they stay in memory until the next
+    /// periodic purge.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_purge_on_save(true);
+    /// # Ok(()) }) }
+    /// ```
+    pub fn with_purge_on_save(mut self, purge_on_save: bool) -> Self {
+        self.purge_on_save = purge_on_save;
+        self
+    }
+
+    /// Save the jar at most once per `interval`. A change made less
+    /// than `interval` after the last save is written by a single
+    /// trailing save once the interval has passed, together with any
+    /// other changes made in the meantime. Errors from a trailing save
+    /// are logged. By default the jar is saved after every change.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use std::time::Duration;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_save_debounce(Some(Duration::from_millis(500)));
+    /// # Ok(()) }) }
+    /// ```
+    pub fn with_save_debounce(mut self, interval: Option<Duration>) -> Self {
+        self.save_debounce = interval;
+        self
+    }
+
+    /// Before each request, check whether the jar file or other
+    /// [`CookieStorage`] was changed by another process or by hand
+    /// since this middleware last loaded or saved it, and if so,
+    /// [`reload`](Self::reload) it. Files are compared by modification
+    /// time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_reload_on_change(true);
+    /// # Ok(()) }) }
+    /// ```
+    pub fn with_reload_on_change(mut self, reload_on_change: bool) -> Self {
+        self.reload_on_change = reload_on_change;
+        self
+    }
+
+    /// Registers a listener that is called with each [`CookieEvent`].
+    /// Listeners are called synchronously while the response is
+    /// being handled, so they should return quickly.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{events::CookieEvent, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new().with_event_listener(|event| {
+    ///     if let CookieEvent::Rejected(rejection) = event {
+    ///         eprintln!("{} rejected: {}", rejection.header, rejection.reason);
+    ///     }
+    /// });
+    /// ```
+    pub fn with_event_listener(
+        mut self,
+        listener: impl Fn(&CookieEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.listeners.push(listener);
+        self
+    }
+
+    /// Registers a callback that is called with each cookie that is
+    /// stored, replaced, or removed, whether from a response or with
+    /// [`set_cookie`](Self::set_cookie), along with the url it was set
+    /// for and what storing it did. Like
+    /// [event listeners](Self::with_event_listener), callbacks are
+    /// called synchronously and should return quickly.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::{events::StoreOutcome, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new().on_cookie_set(|cookie, url, action| {
+    ///     if cookie.name() == "auth" && action != StoreOutcome::Removed {
+    ///         println!("new auth token from {}", url);
+    ///     }
+    /// });
+    /// ```
+    pub fn on_cookie_set(
+        mut self,
+        callback: impl Fn(&Cookie<'static>, &Url, StoreOutcome) + Send + Sync + 'static,
+    ) -> Self {
+        self.listeners
+            .on_store(move |stored| callback(&stored.cookie, &stored.url, stored.action));
+        self
+    }
+
+    /// Registers a callback that is called with each Set-Cookie header
+    /// that was not stored, along with the url of the request that
+    /// received it and why it was rejected. This is a shorthand for an
+    /// [event listener](Self::with_event_listener) that handles
+    /// [`CookieEvent::Rejected`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new().on_cookie_rejected(|header, url, reason| {
+    ///     log::warn!("{} sent a cookie that was rejected ({}): {}", url, reason, header);
+    /// });
+    /// ```
+    pub fn on_cookie_rejected(
+        self,
+        callback: impl Fn(&str, &Url, &RejectionReason) + Send + Sync + 'static,
+    ) -> Self {
+        self.with_event_listener(move |event| {
+            if let CookieEvent::Rejected(rejection) = event {
+                callback(&rejection.header, &rejection.url, &rejection.reason);
+            }
+        })
+    }
+
+    /// Registers a callback that is called with a
+    /// [`PersistenceSummary`] each time the jar is saved to its
+    /// [`CookieStorage`], whether or not saving succeeded
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .on_save(|summary| {
+    ///         if let Some(error) = &summary.error {
+    ///             log::error!("could not save cookies: {}", error);
+    ///         }
+    ///     });
+    /// # Ok(()) }) }
+    /// ```
+    pub fn on_save(
+        mut self,
+        callback: impl Fn(&PersistenceSummary) + Send + Sync + 'static,
+    ) -> Self {
+        self.listeners.on_save(callback);
+        self
+    }
+
+    /// Registers a callback that is called with a
+    /// [`PersistenceSummary`] each time the jar is
+    /// [reloaded](Self::reload) from its [`CookieStorage`]. To also
+    /// observe the first load, register it with
+    /// [`CookieMiddlewareBuilder::on_load`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_reload_on_change(true)
+    ///     .on_load(|summary| log::info!("reloaded {} cookies", summary.cookies));
+    /// # Ok(()) }) }
+    /// ```
+    pub fn on_load(
+        mut self,
+        callback: impl Fn(&PersistenceSummary) + Send + Sync + 'static,
+    ) -> Self {
+        self.listeners.on_load(callback);
+        self
+    }
+
+    /// Returns a stream of the [`CookieEvent`]s emitted by this
+    /// middleware and all of its clones from now on, for applications
+    /// that would rather consume events in an async task than in a
+    /// listener. Events are buffered until they are read, and the
+    /// stream stops receiving events once it is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_std::{prelude::*, task};
+    /// use surf_cookie_middleware::{events::CookieEvent, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let mut events = middleware.events();
+    /// task::spawn(async move {
+    ///     while let Some(event) = events.next().await {
+    ///         if let CookieEvent::Rejected(rejection) = event {
+    ///             eprintln!("{} rejected: {}", rejection.header, rejection.reason);
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn events(&self) -> impl Stream<Item = CookieEvent> + Send + Unpin + 'static {
+        self.listeners.stream()
+    }
+
+    /// Returns a stream of the [`JarChange`]s made to the jar through
+    /// this middleware and all of its clones from now on, so that other
+    /// parts of an application, such as a UI or a sync service, can
+    /// react to cookies being added, updated, removed, or purged
+    /// without polling. Like [`events`](Self::events), changes are
+    /// buffered until they are read.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use async_std::{prelude::*, task};
+    /// use surf_cookie_middleware::{CookieMiddleware, JarChange};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let mut changes = middleware.subscribe();
+    /// task::spawn(async move {
+    ///     while let Some(change) = changes.next().await {
+    ///         if let JarChange::Added(cookie) = change {
+    ///             println!("new cookie {} for {}", cookie.name, cookie.domain);
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn subscribe(&self) -> impl Stream<Item = JarChange> + Send + Unpin + 'static {
+        self.listeners.subscribe()
+    }
+
+    /// Builds a [`Client`] from `config`, such as one with a base url
+    /// and timeout, with this middleware registered. Returns the client
+    /// and a [`CookieJarHandle`] for managing its cookies.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> {
+    /// use surf::{Config, Url};
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let config = Config::new().set_base_url(Url::parse("https://example.com")?);
+    /// let (client, cookies) = CookieMiddleware::new().build_client(config)?;
+    /// assert_eq!(cookies.stats().sent, 0);
+    /// # Ok(()) }
+    /// ```
+    pub fn build_client(self, config: surf::Config) -> Result<(Client, CookieJarHandle<B>)> {
+        let client = Client::try_from(config)
+            .map_err(Into::<surf::Error>::into)?
+            .with(self.clone());
+        Ok((client, CookieJarHandle::new(self)))
+    }
+
+    /// Returns counts of the cookies sent, stored, and rejected by
+    /// this middleware and all of its clones
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new();
+    /// assert_eq!(middleware.stats().rejected, 0);
+    /// ```
+    pub fn stats(&self) -> CookieStats {
+        self.counters.snapshot()
+    }
+
+    /// Returns a read guard to the cookie store, for inspecting the
+    /// cookies received so far. Responses can't be stored while the
+    /// guard is held, so drop it promptly. With
+    /// [`with_scheme_separation`](Self::with_scheme_separation),
+    /// cookies received over plain http are kept in a separate store
+    /// that this does not include; use [`find`](Self::find) to see
+    /// both.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let cookie_store = middleware.cookie_store().await;
+    /// assert_eq!(cookie_store.iter_unexpired().count(), 0);
+    /// # }) }
+    /// ```
+    pub async fn cookie_store(&self) -> RwLockReadGuard<'_, B> {
+        self.cookie_store.read().await
+    }
+
+    /// Returns the attributes that the cookie with this `domain`,
+    /// `path`, and `name` was set with but that are not otherwise
+    /// understood, such as `Priority=High` or vendor extensions, in the
+    /// order they were received. Attributes without a value, like
+    /// `Partitioned`, have a value of None.
+    ///
+    /// These attributes are kept in memory only, and are not sent back
+    /// to the server.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new();
+    /// assert!(middleware
+    ///     .extension_attributes("example.com", "/", "session")
+    ///     .is_empty());
+    /// ```
+    pub fn extension_attributes(
+        &self,
+        domain: &str,
+        path: &str,
+        name: &str,
+    ) -> Vec<(String, Option<String>)> {
+        self.metadata
+            .extensions(&(domain.to_string(), path.to_string(), name.to_string()))
+    }
+
+    /// Returns the unexpired cookies whose domain or name matches
+    /// `pattern`, sorted by domain, path, and name. Values are redacted
+    /// unless the pattern is
+    /// [`revealing_values`](CookiePattern::revealing_values).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::{CookieMiddleware, CookiePattern};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// for cookie in middleware.find("*.example.com").await {
+    ///     println!("{} {} {}", cookie.domain, cookie.path, cookie.name);
+    /// }
+    /// # }) }
+    /// ```
+    pub async fn find(&self, pattern: impl Into<CookiePattern>) -> Vec<CookieView> {
+        let pattern = pattern.into();
+        let mut views = vec![];
+        for store in std::iter::once(&self.cookie_store).chain(&self.insecure_store) {
+            views.extend(
+                store
+                    .read()
+                    .await
+                    .iter_unexpired()
+                    .filter(|cookie| self.readable(cookie))
+                    .filter_map(|cookie| pattern.find(cookie)),
+            );
+        }
+
+        views.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+        views
+    }
+
+    /// Returns a copy of every unexpired cookie in the jar, including
+    /// session cookies and, with
+    /// [`with_scheme_separation`](Self::with_scheme_separation),
+    /// cookies received over plain http, sorted by domain, path, and
+    /// name. Unlike [`find`](Self::find), values are not redacted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// for cookie in middleware.cookies().await {
+    ///     println!("{}: {}", cookie.name(), cookie.value());
+    /// }
+    /// # }) }
+    /// ```
+    pub async fn cookies(&self) -> Vec<Cookie<'static>> {
+        let mut cookies = vec![];
+        for store in std::iter::once(&self.cookie_store).chain(&self.insecure_store) {
+            cookies.extend(
+                store
+                    .read()
+                    .await
+                    .iter_unexpired()
+                    .filter(|cookie| self.readable(cookie))
+                    .cloned(),
+            );
+        }
+
+        cookies.sort_by_cached_key(metadata::cookie_key);
+        cookies
+    }
+
+    /// Returns the number of unexpired cookies in the jar, counted as
+    /// in [`cookies`](Self::cookies) but including any HttpOnly cookies
+    /// [hidden](Self::with_http_only_hidden) from it
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// assert_eq!(CookieMiddleware::new().len().await, 0);
+    /// # }) }
+    /// ```
+    pub async fn len(&self) -> usize {
+        let mut len = 0;
+        for store in std::iter::once(&self.cookie_store).chain(&self.insecure_store) {
+            len += store.read().await.iter_unexpired().count();
+        }
+        len
+    }
+
+    /// Returns whether the jar holds no unexpired cookies
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// assert!(CookieMiddleware::new().is_empty().await);
+    /// # }) }
+    /// ```
+    pub async fn is_empty(&self) -> bool {
+        for store in std::iter::once(&self.cookie_store).chain(&self.insecure_store) {
+            if store.read().await.iter_unexpired().next().is_some() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Stores `cookie` as if it had been received in a response from
+    /// `url`, such as a session token or CSRF cookie obtained out of
+    /// band, so that it is sent with the next matching request. Its
+    /// domain and path default to those of `url`, and a Domain
+    /// attribute that `url` could not have set is an error. If this
+    /// middleware is backed by a file, the cookie is persisted
+    /// immediately.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> { async_std::task::block_on(async {
+    /// use surf::Url;
+    /// use surf_cookie_middleware::{cookie::Cookie, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let url = Url::parse("https://example.com/")?;
+    /// middleware.set_cookie(Cookie::new("session", "abc123"), &url).await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn set_cookie(&self, cookie: RawCookie<'_>, url: &Url) -> io::Result<()> {
+        let url = self.cookie_url(url);
+        let cookie = Cookie::try_from_raw_cookie(&cookie, &url)
+            .map_err(formats::invalid_data)?
+            .into_owned();
+
+        self.store_cookie(self.store_for(&url), cookie, url).await?;
+        self.enforce_limits().await;
+        self.autosave().await
+    }
+
+    /// Inserts `cookie` as received from `url` into `store`, recording
+    /// it and telling listeners as a response would. The caller
+    /// enforces limits and saves, once per batch of cookies.
+    async fn store_cookie(
+        &self,
+        store: &RwLock<B>,
+        cookie: Cookie<'static>,
+        url: Url,
+    ) -> io::Result<()> {
+        let (action, change) = {
+            let mut cookie_store = store.write().await;
+            let domain = String::from(&cookie.domain);
+            let previous = cookie_store
+                .get(&domain, &cookie.path, cookie.name())
+                .map(|previous| (previous.expires.clone(), previous.value().to_string()));
+            let action = StoreOutcome::new(previous.is_some(), &cookie);
+            cookie_store
+                .insert(cookie.clone(), &url)
+                .map_err(formats::invalid_data)?;
+            (action, CookieDelta::default().record(previous, &cookie))
+        };
+        self.listeners.changed(change);
+        self.access_log.stored(&cookie);
+        self.metadata.stored(&cookie, vec![]);
+        self.listeners.stored(Stored {
+            cookie,
+            url,
+            action,
+        });
+        Ok(())
+    }
+
+    /// Stores `cookie` for `domain` and all of its subdomains, as if
+    /// it had been received with `Domain=.example.com`. Any Domain
+    /// attribute on `cookie` is replaced, and its path defaults to `/`.
+    /// If this middleware is backed by a file, the cookie is persisted
+    /// immediately.
+    ///
+    /// This returns an error if `domain` is an ip address or a public
+    /// suffix according to the list provided with
+    /// [`with_public_suffix_list`](Self::with_public_suffix_list). Without
+    /// a list, only single-label domains like `com` are refused.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::{cookie::Cookie, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// middleware
+    ///     .set_for_registrable_domain("example.com", Cookie::new("sso", "token"))
+    ///     .await?;
+    /// assert!(middleware
+    ///     .set_for_registrable_domain("com", Cookie::new("sso", "token"))
+    ///     .await
+    ///     .is_err());
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn set_for_registrable_domain(
+        &self,
+        domain: &str,
+        mut cookie: RawCookie<'_>,
+    ) -> io::Result<()> {
+        let domain = validation::registrable_domain(domain, self.public_suffix_list.as_deref())?;
+        let scheme = if cookie.secure() == Some(true) {
+            "https"
+        } else {
+            "http"
+        };
+        let url =
+            Url::parse(&format!("{}://{}/", scheme, domain)).map_err(formats::invalid_data)?;
+
+        cookie.set_domain(domain);
+        let cookie = Cookie::try_from_raw_cookie(&cookie, &url)
+            .map_err(formats::invalid_data)?
+            .into_owned();
+
+        self.store_cookie(self.store_for(&url), cookie, url).await?;
+        self.enforce_limits().await;
+        self.autosave().await
+    }
+
+    /// Imports cookies from `input`, which is parsed with `format`. If
+    /// this middleware is backed by a file, the imported cookies are
+    /// persisted immediately. Returns the number of cookies that were
+    /// stored.
+    ///
+    /// Imported cookies are passed to
+    /// [`on_cookie_set`](Self::on_cookie_set) callbacks and
+    /// [subscribers](Self::subscribe), and the jar is held to its
+    /// [limits](Self::with_limits) once the import is done.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::{formats::StorageState, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let imported = middleware
+    ///     .import(&StorageState, r#"{"cookies": [], "origins": []}"#)
+    ///     .await?;
+    /// assert_eq!(imported, 0);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn import(&self, format: &impl CookieFormat, input: &str) -> io::Result<usize> {
+        let report = self
+            .import_with(format, input, ImportConflicts::PreferImported)
+            .await?;
+        Ok(report.imported)
+    }
+
+    /// Imports cookies from `input` as with [`import`](Self::import),
+    /// resolving each imported cookie that is already in the jar
+    /// according to `conflicts`. Returns a report of the cookies that
+    /// were stored and the conflicts that were resolved. A cookie that
+    /// can't be stored, such as one whose domain is not a valid host,
+    /// is counted as [skipped](ImportReport::skipped) and the rest are
+    /// still imported.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::{formats::Json, CookieMiddleware, ImportConflicts};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let cookies = r#"[{ "name": "session", "value": "a", "domain": "example.com" }]"#;
+    /// middleware.import(&Json, cookies).await?;
+    ///
+    /// let report = middleware
+    ///     .import_with(&Json, cookies, ImportConflicts::PreferExisting)
+    ///     .await?;
+    /// assert_eq!(report.imported, 0);
+    /// assert!(!report.conflicts[0].replaced);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn import_with(
+        &self,
+        format: &impl CookieFormat,
+        input: &str,
+        conflicts: ImportConflicts,
+    ) -> io::Result<ImportReport> {
+        let cookies = self.parse_imported(format, input)?;
+        self.import_cookies(cookies, conflicts).await
+    }
+
+    /// Imports the cookies of a Firefox profile from its
+    /// `cookies.sqlite` database, replacing any cookie already in the
+    /// jar with the same domain, path, and name. Returns the number of
+    /// cookies that were imported.
+    ///
+    /// The database is opened read-only. While Firefox is running it
+    /// may hold a lock on the database, in which case import from a
+    /// copy of the file.
+    ///
+    /// Available with the `firefox` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// middleware
+    ///     .import_firefox("/home/me/.mozilla/firefox/abcd1234.default/cookies.sqlite")
+    ///     .await?;
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg(feature = "firefox")]
+    pub async fn import_firefox(&self, path: impl Into<PathBuf>) -> io::Result<usize> {
+        let path = path.into();
+        let cookies = runtime::spawn_blocking(move || firefox::read_cookies(&path))
+            .await?
+            .into_iter()
+            .map(|cookie| self.encode_imported(cookie))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let report = self
+            .import_cookies(cookies, ImportConflicts::PreferImported)
+            .await?;
+        Ok(report.imported)
+    }
+
+    async fn import_cookies(
+        &self,
+        cookies: Vec<Cookie<'static>>,
+        conflicts: ImportConflicts,
+    ) -> io::Result<ImportReport> {
+        let mut report = ImportReport::default();
+
+        for cookie in cookies {
+            let url = match formats::request_url(&cookie) {
+                Ok(url) => url,
+                Err(e) => {
+                    log::trace!("cookie import error: {:?}", e);
+                    report.skipped += 1;
+                    continue;
+                }
+            };
+
+            let domain = String::from(&cookie.domain);
+            let path = String::from(&cookie.path);
+            let replace = self
+                .cookie_store
+                .read()
+                .await
+                .get(&domain, &path, cookie.name())
+                .map(|existing| conflicts.prefers_imported(existing, &cookie));
+
+            if let Some(replaced) = replace {
+                log::trace!(
+                    "import conflict for {} at {}{}, replaced: {}",
+                    cookie.name(),
+                    domain,
+                    path,
+                    replaced
+                );
+                report.conflicts.push(ImportConflict {
+                    name: cookie.name().to_string(),
+                    domain,
+                    path,
+                    replaced,
+                });
+
+                if !replaced {
+                    continue;
+                }
+            }
+
+            match self.store_cookie(&self.cookie_store, cookie, url).await {
+                Ok(()) => report.imported += 1,
+                Err(e) => {
+                    log::trace!("cookie import error: {:?}", e);
+                    report.skipped += 1;
+                }
+            }
+        }
+
+        self.enforce_limits().await;
+        self.autosave().await?;
+        Ok(report)
+    }
+
+    /// Adds cookies from `input`, which is parsed with `format`, to
+    /// the jar without overwriting any unexpired cookie that is already
+    /// in it with the same domain, path, and name. Returns the number
+    /// of cookies that were added.
+    ///
+    /// See [`with_seed_file`](Self::with_seed_file) to seed the jar
+    /// from a file when building the middleware.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::{formats::Json, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let seed = r#"[{ "name": "consent", "value": "yes", "domain": "example.com" }]"#;
+    /// assert_eq!(middleware.seed(&Json, seed).await?, 1);
+    /// assert_eq!(middleware.seed(&Json, seed).await?, 0);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn seed(&self, format: &impl CookieFormat, input: &str) -> io::Result<usize> {
+        let report = self
+            .import_with(format, input, ImportConflicts::PreferExisting)
+            .await?;
+        Ok(report.imported)
+    }
+
+    /// Seeds the jar from the file at `path`, as with
+    /// [`seed`](Self::seed), so a deployment can ship required cookies
+    /// such as region selectors or consent flags as configuration.
+    /// Cookies already in the jar are never overwritten.
+    ///
+    /// The file is read in the readable [`Json`](formats::Json)
+    /// format, or `formats::Yaml` if the `yaml` feature is
+    /// enabled and the file name ends in `.yaml` or `.yml`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_seed_file("./seed-cookies.json")
+    ///     .await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn with_seed_file(self, path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let input = runtime::fs::read_to_string(path).await?;
+
+        #[cfg(feature = "yaml")]
+        {
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if matches!(extension, Some("yaml") | Some("yml")) {
+                self.seed(&formats::Yaml, &input).await?;
+                return Ok(self);
+            }
+        }
+
+        self.seed(&formats::Json, &input).await?;
+        Ok(self)
+    }
+
+    fn parse_imported(
+        &self,
+        format: &impl CookieFormat,
+        input: &str,
+    ) -> io::Result<Vec<Cookie<'static>>> {
+        format
+            .parse(input)?
+            .into_iter()
+            .map(|cookie| self.encode_imported(cookie))
+            .collect()
+    }
+
+    fn encode_imported(&self, cookie: Cookie<'static>) -> io::Result<Cookie<'static>> {
+        if self.percent_encode {
+            formats::map_value(cookie, encode_value)
+        } else {
+            Ok(cookie)
+        }
+    }
+
+    /// Exports all unexpired cookies, including session cookies, in
+    /// `format`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::{formats::StorageState, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let storage_state = middleware.export(&StorageState).await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn export(&self, format: &impl CookieFormat) -> io::Result<String> {
+        let cookie_store = self.cookie_store.read().await;
+
+        if self.percent_encode {
+            let cookies = cookie_store
+                .iter_unexpired()
+                .map(|cookie| formats::map_value(cookie.clone(), decode_value))
+                .collect::<io::Result<Vec<_>>>()?;
+            format.serialize(&cookies.iter().collect::<Vec<_>>())
+        } else {
+            format.serialize(&cookie_store.iter_unexpired().collect::<Vec<_>>())
+        }
+    }
+
+    /// Exports all unexpired cookies as a single pretty-printed JSON
+    /// document in the [`Json`](formats::Json) format, sorted by
+    /// domain, path, and name so that exports of the same jar are
+    /// identical and diff cleanly. Unlike the ndjson that jar files
+    /// are persisted in, this is meant to be checked in as a fixture or
+    /// edited by hand, and can be read back with
+    /// [`import_json`](Self::import_json).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// assert_eq!(middleware.export_json().await?, "[]");
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn export_json(&self) -> io::Result<String> {
+        let cookie_store = self.cookie_store.read().await;
+        let mut cookies = cookie_store
+            .iter_unexpired()
+            .map(|cookie| {
+                if self.percent_encode {
+                    formats::map_value(cookie.clone(), decode_value)
+                } else {
+                    Ok(cookie.clone())
+                }
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        cookies.sort_by_cached_key(metadata::cookie_key);
+        formats::Json.serialize(&cookies.iter().collect::<Vec<_>>())
+    }
+
+    /// Imports a JSON document produced by
+    /// [`export_json`](Self::export_json), as with
+    /// [`import`](Self::import). Returns the number of cookies that
+    /// were stored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let imported = middleware
+    ///     .import_json(r#"[{ "name": "session", "value": "abc123", "domain": "example.com" }]"#)
+    ///     .await?;
+    /// assert_eq!(imported, 1);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn import_json(&self, input: &str) -> io::Result<usize> {
+        self.import(&formats::Json, input).await
+    }
+
+    /// Copies the unexpired cookies in the jar, including session
+    /// cookies, into a [`CookieJarSnapshot`] that can be serialized and
+    /// later applied with [`restore`](Self::restore)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// assert!(middleware.snapshot().await.is_empty());
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn snapshot(&self) -> CookieJarSnapshot {
+        let unexpired =
+            |store: RwLockReadGuard<'_, B>| store.iter_unexpired().cloned().collect::<Vec<_>>();
+
+        CookieJarSnapshot {
+            cookies: unexpired(self.cookie_store.read().await),
+            insecure_cookies: match &self.insecure_store {
+                Some(store) => unexpired(store.read().await),
+                None => vec![],
+            },
+        }
+    }
+
+    /// Replaces every cookie in the jar with the cookies in `snapshot`,
+    /// taken with [`snapshot`](Self::snapshot), and persists the jar if
+    /// it is backed by a file. Cookies that have expired since the
+    /// snapshot was taken are left out.
+    ///
+    /// Restored cookies are passed to
+    /// [`on_cookie_set`](Self::on_cookie_set) callbacks and
+    /// [subscribers](Self::subscribe) and count toward the
+    /// [limits](Self::with_limits) as if they had been set by a
+    /// response, and cookies that are not in `snapshot` are reported
+    /// as removed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let snapshot = middleware.snapshot().await;
+    /// CookieMiddleware::new().restore(&snapshot).await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn restore(&self, snapshot: &CookieJarSnapshot) -> io::Result<()> {
+        let stores = match &self.insecure_store {
+            Some(insecure_store) => vec![
+                (&self.cookie_store, snapshot.cookies.iter().collect()),
+                (insecure_store, snapshot.insecure_cookies.iter().collect()),
+            ],
+            None => vec![(&self.cookie_store, snapshot.cookies().collect::<Vec<_>>())],
+        };
+
+        for (store, cookies) in stores {
+            let previous = store
+                .read()
+                .await
+                .iter_any()
+                .map(metadata::cookie_key)
+                .collect::<Vec<_>>();
+
+            let mut restored = HashSet::new();
+            for cookie in cookies.into_iter().filter(|cookie| !cookie.is_expired()) {
+                let url = formats::request_url(cookie)?;
+                restored.insert(metadata::cookie_key(cookie));
+                self.store_cookie(store, cookie.clone(), url).await?;
+            }
+
+            let (gone, removed): (Vec<_>, Vec<_>) = {
+                let mut store = store.write().await;
+                previous
+                    .into_iter()
+                    .filter(|key| !restored.contains(key))
+                    .filter_map(|key| {
+                        let cookie = store.remove(&key.0, &key.1, &key.2)?;
+                        Some((key, cookie))
+                    })
+                    .unzip()
+            };
+            self.access_log.forget(&gone);
+            self.metadata.forget(&gone);
+            self.listeners
+                .changed(removed.iter().map(JarChange::removal));
+        }
+
+        self.enforce_limits().await;
+        self.autosave().await
+    }
+
+    /// Removes idle cookies now, as configured with
+    /// [`with_pruning`](Self::with_pruning), and persists the jar if it
+    /// is backed by a file. Returns how many cookies were removed, which
+    /// is always zero without a pruning policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use std::time::Duration;
+    /// use surf_cookie_middleware::{CookieMiddleware, PruningPolicy};
+    ///
+    /// let middleware = CookieMiddleware::new()
+    ///     .with_pruning(Some(PruningPolicy::new(Duration::from_secs(3600))));
+    /// assert_eq!(middleware.prune().await?, 0);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn prune(&self) -> io::Result<usize> {
+        let pruned = self.prune_stores().await;
+        if pruned > 0 {
+            self.autosave().await?;
+        }
+        Ok(pruned)
+    }
+
+    async fn prune_stores(&self) -> usize {
+        let policy = match &self.pruning {
+            Some(policy) => policy,
+            None => return 0,
+        };
+
+        let mut pruned = 0;
+        for store in std::iter::once(&self.cookie_store).chain(&self.insecure_store) {
+            let removed = self.access_log.prune(&mut *store.write().await, policy);
+            let keys = removed.iter().map(metadata::cookie_key).collect::<Vec<_>>();
+            self.metadata.forget(&keys);
+            self.listeners
+                .changed(removed.iter().map(JarChange::removal));
+            pruned += removed.len();
+        }
+
+        if pruned > 0 {
+            log::debug!("pruned {} idle cookies", pruned);
+        }
+        pruned
+    }
+
+    /// Removes the cookie with this `domain`, `path`, and `name`, such
+    /// as a session cookie when logging out of a site, returning
+    /// whether there was one. If this middleware is backed by a file,
+    /// the removal is persisted immediately.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let removed = middleware
+    ///     .remove_cookie("example.com", "/", "session")
+    ///     .await?;
+    /// assert!(!removed);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn remove_cookie(&self, domain: &str, path: &str, name: &str) -> io::Result<bool> {
+        let key = (domain.to_string(), path.to_string(), name.to_string());
+        let removed = self
+            .remove_where(|cookie| metadata::cookie_key(cookie) == key)
+            .await;
+
+        if removed > 0 {
+            self.autosave().await?;
+        }
+        Ok(removed > 0)
+    }
+
+    /// Removes every cookie from the jar, including session cookies.
+    /// If this middleware is backed by a file, the file is emptied
+    /// immediately.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson").await?;
+    /// middleware.clear().await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn clear(&self) -> io::Result<()> {
+        self.remove_where(|_| true).await;
+        self.autosave().await
+    }
+
+    /// Removes every cookie for `domain` and its subdomains, the usual
+    /// "forget this site" operation, returning how many were removed.
+    /// If this middleware is backed by a file, the removal is persisted
+    /// immediately.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// assert_eq!(middleware.clear_domain("example.com").await?, 0);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn clear_domain(&self, domain: &str) -> io::Result<usize> {
+        let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+        let subdomains = format!(".{}", domain);
+        let removed = self
+            .remove_where(|cookie| {
+                let cookie_domain = String::from(&cookie.domain).to_ascii_lowercase();
+                cookie_domain == domain || cookie_domain.ends_with(&subdomains)
+            })
+            .await;
+
+        if removed > 0 {
+            self.autosave().await?;
+        }
+        Ok(removed)
+    }
+
+    /// Removes cookies that have expired from memory, returning how
+    /// many were removed. Expired cookies are never sent or persisted,
+    /// and are not loaded from a jar file, but they stay in memory
+    /// until they are purged. This happens automatically about once a
+    /// minute as responses are processed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// assert_eq!(CookieMiddleware::new().purge_expired().await, 0);
+    /// # }) }
+    /// ```
+    pub async fn purge_expired(&self) -> usize {
+        let purged = self.remove_where(|cookie| cookie.is_expired()).await;
+        if purged > 0 {
+            log::debug!("purged {} expired cookies", purged);
+        }
+        purged
+    }
+
+    /// Removes the cookies in either jar for which `remove` returns
+    /// true, along with what is known about them, returning how many
+    /// were removed
+    async fn remove_where(&self, remove: impl Fn(&Cookie<'static>) -> bool) -> usize {
+        let mut removed = 0;
+        for store in std::iter::once(&self.cookie_store).chain(&self.insecure_store) {
+            let mut store = store.write().await;
+            let keys = store
+                .iter_any()
+                .filter(|cookie| remove(cookie))
+                .map(metadata::cookie_key)
+                .collect::<Vec<_>>();
+
+            let cookies = keys
+                .iter()
+                .filter_map(|(domain, path, name)| store.remove(domain, path, name))
+                .collect::<Vec<_>>();
+            drop(store);
+
+            self.access_log.forget(&keys);
+            self.metadata.forget(&keys);
+            self.listeners
+                .changed(cookies.iter().map(JarChange::removal));
+            removed += keys.len();
+        }
+        removed
+    }
+
+    /// Evicts cookies from each jar until it is within the configured
+    /// limits, if any
+    async fn enforce_limits(&self) {
+        let limits = match &self.limits {
+            Some(limits) => limits,
+            None => return,
+        };
+
+        for store in std::iter::once(&self.cookie_store).chain(&self.insecure_store) {
+            let evicted = limits.enforce(
+                &mut *store.write().await,
+                &self.access_log,
+                self.public_suffix_list.as_deref(),
+            );
+            if !evicted.is_empty() {
+                log::debug!("evicted {} cookies over the jar limits", evicted.len());
+            }
+            let keys = evicted.iter().map(metadata::cookie_key).collect::<Vec<_>>();
+            self.access_log.forget(&keys);
+            self.metadata.forget(&keys);
+            self.listeners
+                .changed(evicted.iter().map(JarChange::removal));
+        }
+    }
+
+    /// Rewrites the jar file as a minimal snapshot of the unexpired
+    /// persistent cookies, dropping any cookies that have expired since
+    /// the jar was last written, and any lines superseded since an
+    /// [append-only](FileStorage::append_only) jar was last compacted.
+    /// This does nothing if the middleware is not backed by a file.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson").await?;
+    /// middleware.compact().await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn compact(&self) -> io::Result<()> {
+        self.save_jar(true).await
+    }
+
+    /// Persists the unexpired persistent cookies in the jar, if it is
+    /// backed by a file or other [`CookieStorage`]. If that fails and
+    /// the jar is quarantined (see
+    /// [`with_persistence_quarantine`](Self::with_persistence_quarantine)),
+    /// the failure is counted instead of returned until too many saves
+    /// in a row have failed.
+    ///
+    /// The jar is saved automatically unless
+    /// [`with_manual_persistence`](Self::with_manual_persistence) is
+    /// enabled.
+    pub async fn save(&self) -> io::Result<()> {
+        self.save_jar(false).await
+    }
+
+    async fn save_jar(&self, compact: bool) -> io::Result<()> {
+        match self.write_jar(compact).await {
+            Ok(()) => {
+                if self.reload_on_change {
+                    self.record_modified().await?;
+                }
+                let recovered = self.counters.saved();
+                if recovered > 0 {
+                    log::info!(
+                        "persisted cookie jar changes held back by {} failed saves",
+                        recovered
+                    );
+                }
+                Ok(())
+            }
+
+            Err(e) => match self.max_failed_saves {
+                Some(max_failed_saves)
+                    if self.counters.snapshot().unsaved < max_failed_saves as u64 =>
+                {
+                    let failed_saves = self.counters.unsaved();
+                    log::warn!(
+                        "could not persist cookie jar, holding changes in memory after {} failed saves: {}",
+                        failed_saves,
+                        e
+                    );
+                    Ok(())
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// Replaces the persistent cookies in the jar with the cookies
+    /// persisted in its file or other [`CookieStorage`], discarding
+    /// changes that have not been saved. Session cookies are kept,
+    /// since they are never persisted. This does nothing if the jar is
+    /// not backed by storage.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_manual_persistence(true);
+    /// middleware.reload().await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn reload(&self) -> io::Result<()> {
+        let storage = match &self.storage {
+            Some(storage) => storage,
+            None => return Ok(()),
+        };
+        let modified = storage.modified().await?;
+        let cookies = self.load_persisted(&**storage).await?;
+
+        {
+            let mut cookie_store = self.cookie_store.write().await;
+            let persistent = cookie_store
+                .iter_any()
+                .filter(|cookie| cookie.is_persistent())
+                .map(metadata::cookie_key)
+                .collect::<Vec<_>>();
+            for (domain, path, name) in &persistent {
+                cookie_store.remove(domain, path, name);
+            }
+
+            storage::insert_loaded(&mut *cookie_store, cookies)?;
+
+            let gone = persistent
+                .into_iter()
+                .filter(|(domain, path, name)| !cookie_store.contains(domain, path, name))
+                .collect::<Vec<_>>();
+            self.metadata.forget(&gone);
+        }
+
+        if let Some(path) = &self.path {
+            self.load_annotations(path).await?;
+        }
+
+        self.counters.saved();
+        *self.last_modified.lock().unwrap() = modified;
+        Ok(())
+    }
+
+    /// Loads the cookies persisted in `storage`, leaving out session
+    /// cookies if the builder was asked to
+    /// [discard them](CookieMiddlewareBuilder::discard_session_cookies)
+    pub(crate) async fn load_persisted(
+        &self,
+        storage: &dyn CookieStorage,
+    ) -> io::Result<Vec<Cookie<'static>>> {
+        let mut cookies = storage::load(storage, &self.listeners).await?;
+        if self.discard_session_cookies {
+            cookies.retain(|cookie| cookie.is_persistent());
+        }
+        Ok(cookies)
+    }
+
+    /// Reloads the jar if its storage changed since it was last loaded
+    /// or saved by this middleware
+    async fn reload_if_changed(&self) -> io::Result<()> {
+        let storage = match &self.storage {
+            Some(storage) if self.reload_on_change => storage,
+            _ => return Ok(()),
+        };
+
+        let modified = storage.modified().await?;
+        if modified.is_some() && modified != *self.last_modified.lock().unwrap() {
+            log::debug!("cookie jar changed since it was loaded, reloading");
+            self.reload().await?;
+        }
+        Ok(())
+    }
+
+    /// Records when the storage was last changed, so that this
+    /// middleware's own saves are not mistaken for outside changes
+    pub(crate) async fn record_modified(&self) -> io::Result<()> {
+        if let Some(storage) = &self.storage {
+            *self.last_modified.lock().unwrap() = storage.modified().await?;
+        }
+        Ok(())
+    }
+
+    async fn autosave(&self) -> io::Result<()> {
+        // every change to the jar ends up here
+        self.start_autosave();
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("surf_cookie_jar_size").set(self.len().await as f64);
+
+        if self.storage.is_none() {
+            return Ok(());
+        }
+
+        self.dirty.store(true, Ordering::SeqCst);
+        if self.manual_persistence {
+            return Ok(());
+        }
+
+        let interval = match self.save_debounce {
+            Some(interval) => interval,
+            None => return self.save().await,
+        };
+
+        match self.debounce.schedule(interval) {
+            Schedule::Now => self.save().await,
+            Schedule::Scheduled => Ok(()),
+            Schedule::Later(delay) => {
+                let middleware = self.clone();
+                let spawned = runtime::spawn(async move {
+                    runtime::sleep(delay).await;
+                    middleware.debounce.trailing_save();
+                    if let Err(e) = middleware.save().await {
+                        log::warn!("could not persist cookie jar: {}", e);
+                    }
+                });
+                if spawned {
+                    Ok(())
+                } else {
+                    self.debounce.trailing_save();
+                    self.save().await
+                }
+            }
+        }
+    }
+
+    /// Persists the jar now, as with [`save`](Self::save), including
+    /// any changes held back by
+    /// [`with_save_debounce`](Self::with_save_debounce) or
+    /// [`with_manual_persistence`](Self::with_manual_persistence).
+    /// Unlike `save`, a failure is always returned, even if the jar is
+    /// [quarantined](Self::with_persistence_quarantine). Call this
+    /// before shutting down to make sure every change is on disk.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use std::time::Duration;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_save_debounce(Some(Duration::from_secs(5)));
+    /// // ... requests ...
+    /// middleware.flush().await?;
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn flush(&self) -> io::Result<()> {
+        self.write_jar(false).await?;
+        self.counters.saved();
+        if self.reload_on_change {
+            self.record_modified().await?;
+        }
+        Ok(())
+    }
+
+    /// Persist the jar when the last clone of this middleware is
+    /// dropped, such as when the last client using it goes away. This
+    /// blocks the dropping thread until the jar is written, and errors
+    /// are logged. It applies to the clones made after it is enabled,
+    /// so enable it while building the middleware.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_manual_persistence(true)
+    ///     .with_save_on_drop(true);
+    /// let client = surf::Client::new().with(middleware);
+    /// # Ok(()) }) }
+    /// ```
+    pub fn with_save_on_drop(mut self, save_on_drop: bool) -> Self {
+        self.save_on_drop = None;
+        if save_on_drop && self.storage.is_some() {
+            let cookie_store = self.cookie_store.clone();
+            let storage = self.storage.clone();
+            let path = self.path.clone();
+            let metadata = self.metadata.clone();
+            let access_log = self.access_log.clone();
+            let persist_session_cookies = self.persist_session_cookies;
+            self.save_on_drop = Some(Arc::new(SaveOnDrop::new(async move {
+                write_jar(
+                    &cookie_store,
+                    storage.as_deref(),
+                    path.as_deref(),
+                    &metadata,
+                    &access_log,
+                    persist_session_cookies,
+                    false,
+                )
+                .await
+                .map(drop)
+            })));
+        }
+        self
+    }
+
+    /// Save the jar every `interval` in a background task if it has
+    /// changed since it was last saved, even when no responses arrive.
+    /// This is useful with
+    /// [`with_manual_persistence`](Self::with_manual_persistence), or
+    /// when cookies are changed through this middleware rather than by
+    /// responses. The task starts with the first request or change to
+    /// the jar, so it saves with options configured after this one,
+    /// and stops once every clone of the middleware is dropped. Errors
+    /// are logged. This does nothing if the jar is not backed by a file
+    /// or other [`CookieStorage`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use std::time::Duration;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::from_path("./cookies.ndjson")
+    ///     .await?
+    ///     .with_manual_persistence(true)
+    ///     .with_autosave_interval(Some(Duration::from_secs(30)));
+    /// # Ok(()) }) }
+    /// ```
+    pub fn with_autosave_interval(mut self, interval: Option<Duration>) -> Self {
+        self.autosave_task =
+            interval.map(|interval| std::sync::Arc::new(AutosaveTask::new(interval)));
+        self
+    }
+
+    fn start_autosave(&self) {
+        let task = match &self.autosave_task {
+            Some(task) if self.storage.is_some() && task.start() => task,
+            _ => return,
+        };
+
+        // the task's clone must not keep the middleware alive
+        let running = std::sync::Arc::downgrade(task);
+        let interval = task.interval();
+        let middleware = Self {
+            save_on_drop: None,
+            autosave_task: None,
+            ..self.clone()
+        };
+
+        let spawned = runtime::spawn(async move {
+            loop {
+                runtime::sleep(interval).await;
+                if running.upgrade().is_none() {
+                    break;
+                }
+
+                if middleware.dirty.load(Ordering::SeqCst) {
+                    if let Err(e) = middleware.save().await {
+                        log::warn!("could not persist cookie jar: {}", e);
+                    }
+                }
+            }
+        });
+
+        if !spawned {
+            log::debug!("no runtime to save the cookie jar in the background yet");
+            task.not_started();
+        }
+    }
+
+    async fn write_jar(&self, compact: bool) -> io::Result<()> {
+        if self.purge_on_save {
+            self.purge_expired().await;
+        }
+
+        self.dirty.store(false, Ordering::SeqCst);
+        let started = Instant::now();
+        let result = write_jar(
+            &self.cookie_store,
+            self.storage.as_deref(),
+            self.path.as_deref(),
+            &self.metadata,
+            &self.access_log,
+            self.persist_session_cookies,
+            compact,
+        )
+        .await;
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("surf_cookie_jar_save_duration_seconds")
+            .record(started.elapsed().as_secs_f64());
+
+        if let Some(storage) = &self.storage {
+            if self.listeners.observes_saves() {
+                let summary =
+                    PersistenceSummary::new(&**storage, result.as_ref().copied(), started).await;
+                self.listeners.saved(&summary);
+            }
+        }
+
+        if result.is_err() {
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        result.map(drop)
+    }
+
+    pub(crate) async fn load_annotations(&self, path: &std::path::Path) -> io::Result<()> {
+        let records = match runtime::fs::read(annotations_path(path)).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let cookie_store = self.cookie_store.read().await;
+        self.metadata
+            .load_annotations(records, &self.access_log, |(domain, path, name)| {
+                cookie_store.contains(domain, path, name)
+            });
+        Ok(())
+    }
+
+    /// The unexpired cookie identified by `key`, in either jar
+    async fn find_cookie(&self, key: &CookieKey) -> Option<Cookie<'static>> {
+        for store in std::iter::once(&self.cookie_store).chain(&self.insecure_store) {
+            let cookie = store
+                .read()
+                .await
+                .iter_unexpired()
+                .find(|cookie| metadata::cookie_key(cookie) == *key)
+                .cloned();
+            if cookie.is_some() {
+                return cookie;
+            }
+        }
+        None
+    }
+
+    /// Tags the cookie with this `domain`, `path`, and `name` with an
+    /// arbitrary string, such as `"account=alice"` or
+    /// `"source=import"`. A cookie can have any number of annotations,
+    /// which are kept when the server updates the cookie and dropped
+    /// when it is removed.
+    ///
+    /// For a middleware built [`from_path`](Self::from_path),
+    /// annotations are persisted in a file next to the jar, named with
+    /// an added `.annotations.json` extension. Otherwise they are kept
+    /// in memory only.
+    ///
+    /// Returns an error of kind [`NotFound`](io::ErrorKind::NotFound)
+    /// if the jar has no such cookie.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::{cookie::Cookie, CookieMiddleware};
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// middleware
+    ///     .set_for_registrable_domain("example.com", Cookie::new("session", "abc"))
+    ///     .await?;
+    /// middleware
+    ///     .annotate("example.com", "/", "session", "account=alice")
+    ///     .await?;
+    ///
+    /// let alice = middleware.find_annotated("account=alice").await;
+    /// assert_eq!(alice[0].value(), "abc");
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn annotate(
+        &self,
+        domain: &str,
+        path: &str,
+        name: &str,
+        annotation: impl Into<String>,
+    ) -> io::Result<()> {
+        let key = (domain.to_string(), path.to_string(), name.to_string());
+        if self.find_cookie(&key).await.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no cookie {} for {}{}", name, domain, path),
+            ));
+        }
+
+        if self.metadata.annotate(key, annotation.into()) {
+            self.autosave().await?;
+        }
+        Ok(())
+    }
+
+    /// Removes an annotation added with [`annotate`](Self::annotate),
+    /// returning whether the cookie had it
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new();
+    /// let removed = middleware
+    ///     .remove_annotation("example.com", "/", "session", "account=alice")
+    ///     .await?;
+    /// assert!(!removed);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn remove_annotation(
+        &self,
+        domain: &str,
+        path: &str,
+        name: &str,
+        annotation: &str,
+    ) -> io::Result<bool> {
+        let key = (domain.to_string(), path.to_string(), name.to_string());
+        let removed = self.metadata.remove_annotation(&key, annotation);
+        if removed {
+            self.autosave().await?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns the annotations of the cookie with this `domain`,
+    /// `path`, and `name`, in sorted order
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new();
+    /// assert!(middleware.annotations("example.com", "/", "session").is_empty());
+    /// ```
+    pub fn annotations(&self, domain: &str, path: &str, name: &str) -> Vec<String> {
+        self.metadata
+            .annotations(&(domain.to_string(), path.to_string(), name.to_string()))
+    }
+
+    /// Returns the unexpired cookies that have `annotation`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    /// let middleware = CookieMiddleware::new();
+    /// assert!(middleware.find_annotated("account=alice").await.is_empty());
+    /// # }) }
+    /// ```
+    pub async fn find_annotated(&self, annotation: &str) -> Vec<Cookie<'static>> {
+        let mut cookies = vec![];
+        for key in self.metadata.annotated(annotation) {
+            cookies.extend(self.find_cookie(&key).await);
+        }
+        cookies
+    }
+
+    /// The url that cookies for `url` are matched against and stored
+    /// for, after applying host aliases and secure hosts
+    fn cookie_url(&self, url: &Url) -> Url {
+        self.secure_hosts.apply(&self.host_aliases.apply(url))
+    }
+
+    /// The jar that holds cookies for `url`
+    fn store_for(&self, url: &Url) -> &Arc<RwLock<B>> {
+        match &self.insecure_store {
+            Some(insecure_store) if url.scheme() != "https" => insecure_store,
+            _ => &self.cookie_store,
+        }
+    }
+
+    /// Sets the Cookie header on `req` to the cookies in this jar that
+    /// match its url (see [`with_target_url`](Self::with_target_url)).
+    /// A Cookie header already set by the application is handled as
+    /// configured [`with_existing_cookie_header`](Self::with_existing_cookie_header).
+    /// For hosts configured
+    /// [`with_cookie_header_name`](Self::with_cookie_header_name), that
+    /// header is set instead. SameSite cookies are withheld from
+    /// cross-site requests as described for [`SiteForCookies`].
+    ///
+    /// This is what the middleware does before each request is sent.
+    /// It is public so that an outer middleware that sends several
+    /// requests of its own, such as a custom redirect middleware, can
+    /// apply cookies to each of them explicitly. Pair it with
+    /// [`process_response`](Self::process_response).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> { async_std::task::block_on(async {
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let mut req = surf::get("https://example.com/next-hop").build();
+    /// middleware.prepare_request(&mut req).await;
+    /// # Ok(()) }) }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(url = %req.url()))
+    )]
+    pub async fn prepare_request(&self, req: &mut Request) {
+        let target = self.target_url.resolve(req);
+        let site_for_cookies = req
+            .ext::<SiteForCookies>()
+            .map(|site| &site.0)
+            .or(self.site_for_cookies.as_ref());
+        let context = SameSiteContext::new(
+            &target,
+            site_for_cookies,
+            req.method(),
+            self.public_suffix_list.as_deref(),
+        );
+        let header_name = self.cookie_header_names.for_url(&target);
+        let existing = req.header(&header_name).map(|values| {
+            values
+                .iter()
+                .map(|value| value.as_str())
+                .collect::<Vec<_>>()
+                .join("; ")
+        });
+        let existing = match (self.existing_cookie_header, existing) {
+            (ExistingCookieHeader::Skip, Some(_)) => return,
+            (ExistingCookieHeader::Replace, _) => None,
+            (_, existing) => existing,
+        };
+        let values = self
+            .cookie_header(&target, context, existing.as_deref())
+            .await;
+        req.insert_header(header_name, values);
+    }
+
+    /// Returns the Cookie header value to send with a websocket
+    /// handshake to `url`, or None if no cookies match. `ws://` and
+    /// `wss://` urls are matched as `http://` and `https://`
+    /// respectively, so the handshake shares the session held by this
+    /// jar, including Secure cookies over `wss://`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> { async_std::task::block_on(async {
+    /// use surf::Url;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let url = Url::parse("wss://example.com/socket")?;
+    /// if let Some(cookie) = middleware.websocket_cookie_header(&url).await {
+    ///     // add ("Cookie", cookie) to the handshake request, for
+    ///     // example with async-tungstenite
+    /// }
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn websocket_cookie_header(&self, url: &Url) -> Option<String> {
+        Some(
+            self.cookie_header(
+                &websocket_handshake_url(url),
+                SameSiteContext::default(),
+                None,
+            )
+            .await,
+        )
+        .filter(|values| !values.is_empty())
+    }
+
+    /// Returns the cookies that would be sent with a request to `url`,
+    /// in the order they would be sent, without counting them as sent.
+    /// This is useful for debugging, and for sending the same cookies
+    /// with requests that are not made through surf. `ws://` and
+    /// `wss://` urls are matched as in
+    /// [`websocket_cookie_header`](Self::websocket_cookie_header).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> { async_std::task::block_on(async {
+    /// use surf::Url;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let url = Url::parse("https://example.com/account")?;
+    /// for cookie in middleware.matches_for_url(&url).await {
+    ///     println!("{}={}", cookie.name(), cookie.value());
+    /// }
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn matches_for_url(&self, url: &Url) -> Vec<Cookie<'static>> {
+        let url = self.cookie_url(&websocket_handshake_url(url));
+        let cookie_store = self.store_for(&url).read().await;
+        let (pairs, _) = self.header_pairs(&*cookie_store, &url, SameSiteContext::default(), &[]);
+        pairs
+            .into_iter()
+            .filter(|(cookie, _)| self.readable(cookie))
+            .map(|(cookie, _)| cookie.clone())
+            .collect()
+    }
+
+    /// Returns the exact `name=value; name2=value2` Cookie header that
+    /// would be sent with a same-site request to `url`, or an empty
+    /// string if no cookies match, without counting the cookies as
+    /// sent. Paste it into `curl -b` or other tools to check that they
+    /// send what surf does. Unlike
+    /// [`matches_for_url`](Self::matches_for_url), HttpOnly cookies are
+    /// always included, since they are sent. `ws://` and `wss://` urls
+    /// are matched as in
+    /// [`websocket_cookie_header`](Self::websocket_cookie_header).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> { async_std::task::block_on(async {
+    /// use surf::Url;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let url = Url::parse("https://example.com/account")?;
+    /// let cookies = middleware.cookie_string_for(&url).await;
+    /// println!("curl -b '{}' {}", cookies, url);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn cookie_string_for(&self, url: &Url) -> String {
+        let url = self.cookie_url(&websocket_handshake_url(url));
+        let cookie_store = self.store_for(&url).read().await;
+        let (pairs, _) = self.header_pairs(&*cookie_store, &url, SameSiteContext::default(), &[]);
+        pairs
+            .iter()
+            .map(|(_, pair)| pair.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Whether `cookie` is returned by the methods that read cookies,
+    /// as opposed to only being sent
+    fn readable(&self, cookie: &Cookie<'_>) -> bool {
+        !(self.hide_http_only && cookie.http_only() == Some(true))
+    }
+
+    /// Returns the value of the cookie named `name` that would be sent
+    /// with a request to `url`, such as a session id to confirm that a
+    /// login succeeded. If several cookies have that name, the one
+    /// with the longest path wins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> { async_std::task::block_on(async {
+    /// use surf::Url;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let url = Url::parse("https://example.com/")?;
+    /// assert_eq!(middleware.get(&url, "session_id").await, None);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn get(&self, url: &Url, name: &str) -> Option<String> {
+        self.matches_for_url(url)
+            .await
+            .into_iter()
+            .find(|cookie| cookie.name() == name)
+            .map(|cookie| cookie.value().to_string())
+    }
+
+    /// The cookies in `cookie_store` that `context` allows sending to
+    /// `url`, other than those named in `overridden`, in the order they
+    /// are sent, with their `name=value` pairs, and any that were left
+    /// out to fit the header budget
+    fn header_pairs<'a>(
+        &self,
+        cookie_store: &'a B,
+        url: &Url,
+        context: SameSiteContext,
+        overridden: &[&str],
+    ) -> (Vec<(&'a Cookie<'static>, String)>, Option<Truncation>) {
+        let mut matches = cookie_store.matches(url);
+        matches.retain(|cookie| {
+            context.allows(cookie)
+                && !overridden.contains(&cookie.name())
+                && self
+                    .policy
+                    .as_ref()
+                    .is_none_or(|policy| policy.allow_send(cookie, url))
+        });
+
+        // clients "SHOULD" sort by path length, then by creation time
+        matches.sort_by_cached_key(|cookie| {
+            (
+                Reverse(cookie.path.len()),
+                self.access_log.created(cookie),
+                metadata::cookie_key(cookie),
+            )
+        });
+
+        let mut pairs = matches
+            .into_iter()
+            .map(|cookie| {
+                let pair = if self.percent_encode {
+                    format!("{}={}", cookie.name(), encode_value(cookie.value()))
+                } else {
+                    format!("{}={}", cookie.name(), cookie.value())
+                };
+                (cookie, pair)
+            })
+            .collect::<Vec<_>>();
+
+        let truncation = self.header_budget.and_then(|(max_bytes, strategy)| {
+            let dropped = budget::truncate(&mut pairs, max_bytes, strategy, &self.access_log);
+            (!dropped.is_empty()).then(|| Truncation {
+                url: url.clone(),
+                max_bytes,
+                dropped,
+            })
+        });
+
+        (pairs, truncation)
+    }
+
+    /// The Cookie header to send to `url`, after the `name=value` pairs
+    /// in `existing`, if any
+    async fn cookie_header(
+        &self,
+        url: &Url,
+        context: SameSiteContext,
+        existing: Option<&str>,
+    ) -> String {
+        let url = self.cookie_url(url);
+        let existing = existing
+            .into_iter()
+            .flat_map(|existing| existing.split(';'))
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .collect::<Vec<_>>();
+        let overridden = existing
+            .iter()
+            .map(|pair| pair.split('=').next().unwrap_or_default().trim())
+            .collect::<Vec<_>>();
+
+        let cookie_store = self.store_for(&url).read().await;
+        let (pairs, truncation) = self.header_pairs(&*cookie_store, &url, context, &overridden);
+
+        let matches = pairs.iter().map(|(cookie, _)| *cookie).collect::<Vec<_>>();
+        let values = existing
+            .iter()
+            .copied()
+            .chain(pairs.iter().map(|(_, pair)| pair.as_str()))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        self.counters.sent(matches.len());
+        self.access_log.touch(matches.iter().copied());
+        #[cfg(feature = "tracing")]
+        for cookie in &matches {
+            tracing::trace!(
+                name = cookie.name(),
+                domain = %String::from(&cookie.domain),
+                action = "send",
+            );
+        }
+
+        let leaks = if self.detect_leaks {
+            leaks::detect(&*cookie_store, &matches, &url)
+        } else {
+            vec![]
+        };
+
+        // emitted after releasing the lock so listeners can use the middleware
+        drop(cookie_store);
+        if let Some(truncation) = truncation {
+            log::debug!(
+                "left {} cookies out of the request to {}",
+                truncation.dropped.len(),
+                truncation.url
+            );
+            self.listeners.emit(CookieEvent::Truncated(truncation));
+        }
+
+        for leak in leaks {
+            log::warn!(
+                "cookie {} sent to {} has the same value as {} for {}",
+                leak.name,
+                leak.url,
+                leak.source_name,
+                leak.source_domain
+            );
+            self.listeners.emit(CookieEvent::PossibleLeak(leak));
+        }
+
+        values
+    }
+
+    fn parse_set_cookie(
+        &self,
+        header: &str,
+        request_url: &Url,
+    ) -> std::result::Result<Cookie<'static>, RejectionReason> {
+        let mut raw = if self.preserve_quotes {
+            parse_preserving_quotes(header)?
+        } else {
+            RawCookie::parse(header).map_err(|_| CookieError::Parse)?
+        };
+
+        if let Some(canonical) = raw
+            .domain()
+            .and_then(|domain| self.host_aliases.canonical(domain))
+        {
+            let canonical = canonical.to_string();
+            raw.set_domain(canonical);
+        }
+
+        // the cookie crate only understands a few fixed date formats
+        if raw.max_age().is_none() && raw.expires().is_none() {
+            if let Some(expires) =
+                dates::expires_attribute(header).and_then(dates::parse_cookie_date)
+            {
+                raw.set_expires(expires);
+            }
+        }
+        if let Some(max_lifetime) = self.max_lifetime {
+            dates::cap_lifetime(&mut raw, max_lifetime);
+        }
+        dates::clamp_expiry(&mut raw);
+
+        Ok(Cookie::try_from_raw_cookie(&raw, request_url)?.into_owned())
+    }
+
+    /// Stores the cookies set by `res`, the response to a request for
+    /// `request_url`, and persists the jar if it is backed by a file.
+    /// Rejected and duplicate cookies are reported to event listeners.
+    ///
+    /// This is what the middleware does after each response is
+    /// received. Like [`prepare_request`](Self::prepare_request), it
+    /// is public so that an outer middleware can process intermediate
+    /// responses that never reach this one, such as each hop of a
+    /// redirect.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> { async_std::task::block_on(async {
+    /// use surf::{http::headers::SET_COOKIE, Response, Url};
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let url = Url::parse("https://example.com/login")?;
+    /// let mut res = surf::http::Response::new(302);
+    /// res.insert_header(SET_COOKIE, "session=abc; Path=/");
+    /// middleware.process_response(&url, &Response::from(res)).await?;
+    /// assert_eq!(middleware.stats().stored, 1);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn process_response(&self, request_url: &Url, res: &Response) -> Result<()> {
+        self.store_cookies(request_url, res, request_url).await?;
+        Ok(())
+    }
+
+    /// Stores the cookies of a batch of raw Set-Cookie header values,
+    /// each with the url of the request it was received from, as if
+    /// each was set by a response to that request. Headers are stored
+    /// in order, so a later header replaces an earlier cookie with the
+    /// same name, domain, and path, and headers that are rejected are
+    /// reported to [event listeners](Self::with_event_listener) as
+    /// usual. The jar is persisted once, after the whole batch is
+    /// stored. Returns the number of headers that added, replaced, or
+    /// removed a cookie.
+    ///
+    /// This seeds a jar from logged traffic or from the examples in api
+    /// documentation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> surf::Result<()> { async_std::task::block_on(async {
+    /// use surf::Url;
+    /// use surf_cookie_middleware::CookieMiddleware;
+    ///
+    /// let middleware = CookieMiddleware::new();
+    /// let url = Url::parse("https://example.com/login")?;
+    /// let stored = middleware
+    ///     .import_set_cookie_headers([
+    ///         ("session=abc123; Path=/; Secure; HttpOnly", &url),
+    ///         ("theme=dark; Path=/", &url),
+    ///     ])
+    ///     .await?;
+    /// assert_eq!(stored, 2);
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn import_set_cookie_headers<'a>(
+        &self,
+        headers: impl IntoIterator<Item = (&'a str, &'a Url)>,
+    ) -> io::Result<usize> {
+        let mut stored = 0;
+        for (header, url) in headers {
+            let url = self.cookie_url(url);
+            let accepted =
+                self.accept_policy
+                    .accepts(&url, &url, self.public_suffix_list.as_deref());
+            let (delta, _) = self
+                .store_set_cookies(&url, &[header], StatusCode::Ok, accepted)
+                .await;
+            stored += delta.added.len() + delta.updated.len() + delta.deleted.len();
+        }
+        self.tidy().await;
+
+        self.autosave().await?;
+        Ok(stored)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(url = %request_url))
+    )]
+    async fn store_cookies(
+        &self,
+        request_url: &Url,
+        res: &Response,
+        first_party: &Url,
+    ) -> Result<CookieDelta> {
+        let request_url = &self.cookie_url(request_url);
+        let accepted = self.accept_policy.accepts(
+            request_url,
+            &self.cookie_url(first_party),
+            self.public_suffix_list.as_deref(),
+        );
+        let headers = res
+            .header(SET_COOKIE)
+            .into_iter()
+            .flatten()
+            .map(|header| header.as_str())
+            .collect::<Vec<_>>();
+        let (delta, malformed) = self
+            .store_set_cookies(request_url, &headers, res.status(), accepted)
+            .await;
+        self.tidy().await;
+
+        self.persistence_failure.handle(self.autosave().await)?;
+
+        if !malformed.is_empty() {
+            return Err(surf::Error::new(
+                StatusCode::BadGateway,
+                events::MalformedSetCookie {
+                    rejections: malformed,
+                },
+            ));
+        }
+
+        Ok(delta)
+    }
+
+    /// Stores the cookies in `headers`, the Set-Cookie headers of one
+    /// response with `status` to a request for `request_url`, without
+    /// persisting the jar. Returns what changed, and the malformed
+    /// headers if parsing is strict.
+    async fn store_set_cookies(
+        &self,
+        request_url: &Url,
+        headers: &[&str],
+        status: StatusCode,
+        accepted: bool,
+    ) -> (CookieDelta, Vec<Rejection>) {
+        let mut events = vec![];
+        let mut stored = vec![];
+        let mut changes = vec![];
+        let mut malformed = vec![];
+        let mut delta = CookieDelta::default();
+
+        if !headers.is_empty() {
+            let mut cookie_store = self.store_for(request_url).write().await;
+            let mut seen = HashMap::new();
+            for &header in headers {
+                let result = validation::validate_header(header)
+                    .and(if self.status_filter.allows(status) {
+                        Ok(())
+                    } else {
+                        Err(RejectionReason::IgnoredStatus(status))
+                    })
+                    .and(if accepted {
+                        Ok(())
+                    } else {
+                        Err(RejectionReason::NotAccepted)
+                    })
+                    .and_then(|_| self.parse_set_cookie(header, request_url))
+                    .and_then(|cookie| {
+                        let size = cookie.name().len() + cookie.value().len();
+                        match self.max_cookie_size {
+                            Some(max_bytes) if size > max_bytes => {
+                                Err(RejectionReason::TooLarge { size, max_bytes })
+                            }
+                            _ => Ok(cookie),
+                        }
+                    })
+                    .and_then(|cookie| {
+                        validation::validate_prefix(&cookie, request_url)?;
+                        Ok(cookie)
+                    })
+                    .and_then(|cookie| {
+                        if self.strict_secure
+                            && cookie.secure() == Some(true)
+                            && !validation::is_secure_context(request_url)
+                        {
+                            Err(RejectionReason::InsecureOrigin)
+                        } else {
+                            Ok(cookie)
+                        }
+                    })
+                    .and_then(|cookie| {
+                        if self.domain_filter.allows(&String::from(&cookie.domain)) {
+                            Ok(cookie)
+                        } else {
+                            Err(RejectionReason::DomainNotAllowed)
+                        }
+                    })
+                    .and_then(|cookie| {
+                        if self
+                            .policy
+                            .as_ref()
+                            .is_none_or(|policy| policy.allow_store(&cookie, request_url))
+                        {
+                            Ok(cookie)
+                        } else {
+                            Err(RejectionReason::Policy)
+                        }
+                    })
+                    .and_then(|cookie| {
+                        if self.reject_blank_values
+                            && validation::is_blank(cookie.value())
+                            && !cookie.is_expired()
+                        {
+                            Err(RejectionReason::BlankValue)
+                        } else {
+                            Ok(cookie)
+                        }
+                    })
+                    .and_then(|cookie| {
+                        let key = (
+                            cookie.name().to_string(),
+                            String::from(&cookie.domain),
+                            String::from(&cookie.path),
+                        );
+
+                        if let Some(&previous) = seen.get(&key) {
+                            let first_wins = self.duplicate_cookies == DuplicateCookies::FirstWins;
+                            let (kept, discarded) = if first_wins {
+                                (previous, header)
+                            } else {
+                                (header, previous)
+                            };
+
+                            events.push(CookieEvent::Duplicate(Duplicate {
+                                name: key.0.clone(),
+                                domain: key.1.clone(),
+                                path: key.2.clone(),
+                                url: request_url.clone(),
+                                kept: kept.to_string(),
+                                discarded: discarded.to_string(),
+                            }));
+
+                            if first_wins {
+                                return Ok(false);
+                            }
+                        }
+
+                        let previous = cookie_store.get(&key.1, &key.2, &key.0).map(|previous| {
+                            (previous.expires.clone(), previous.value().to_string())
+                        });
+                        let action = StoreOutcome::new(previous.is_some(), &cookie);
+                        let cookie_for_delta = cookie.clone();
+                        cookie_store.insert(cookie, request_url)?;
+                        if cookie_for_delta.is_expired() {
+                            // nothing is left to purge later
+                            cookie_store.remove(&key.1, &key.2, &key.0);
+                        }
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(
+                            name = %key.0,
+                            domain = %key.1,
+                            action = if cookie_for_delta.is_expired() {
+                                "remove"
+                            } else {
+                                "store"
+                            },
+                        );
+                        changes.extend(delta.record(previous, &cookie_for_delta));
+                        self.access_log.stored(&cookie_for_delta);
+                        self.metadata
+                            .stored(&cookie_for_delta, metadata::extension_attributes(header));
+                        stored.push(Stored {
+                            cookie: cookie_for_delta,
+                            url: request_url.clone(),
+                            action,
+                        });
+                        seen.insert(key, header);
+                        Ok(true)
+                    });
+
+                match result {
+                    Ok(true) => self.counters.stored(),
+
+                    Ok(false) => {
+                        log::trace!("ignoring duplicate cookie: {}", header);
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(header, action = "ignore_duplicate");
+                    }
+
+                    // an expired cookie for which there is nothing to expire
+                    Err(RejectionReason::CookieStore(CookieError::Expired)) => {
+                        log::trace!("ignoring expired cookie: {}", header);
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(header, action = "ignore_expired");
+                    }
+
+                    Err(reason) => {
+                        log::trace!("cookie rejected ({}): {}", reason, header);
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(header, %reason, action = "reject");
+                        self.counters.rejected();
+                        let rejection = Rejection {
+                            header: header.to_string(),
+                            url: request_url.clone(),
+                            reason,
+                        };
+                        if self.strict_parsing && rejection.reason.is_malformed() {
+                            malformed.push(rejection.clone());
+                        }
+                        events.push(CookieEvent::Rejected(rejection));
+                    }
+                }
+            }
+        }
+
+        // emitted after releasing the lock so listeners can use the middleware
+        self.listeners.changed(changes);
+        for stored in stored {
+            self.listeners.stored(stored);
+        }
+        for event in events {
+            self.listeners.emit(event);
+        }
+
+        (delta, malformed)
+    }
+
+    /// Purges, evicts, and prunes cookies as configured, after cookies
+    /// have been stored
+    async fn tidy(&self) {
+        if self.access_log.purge_due() {
+            self.purge_expired().await;
+        }
+        self.enforce_limits().await;
+
+        if self
+            .pruning
+            .is_some_and(|policy| self.access_log.due(&policy))
+        {
+            self.prune_stores().await;
+        }
+    }
+}
+
+/// Writes the unexpired persistent cookies in `cookie_store`, and
+/// session cookies if `persist_session_cookies` is set, to `storage`,
+/// and annotations and use times next to the jar file at `path` if
+/// they changed
+async fn write_jar<B: CookieJarBackend>(
+    cookie_store: &RwLock<B>,
+    storage: Option<&dyn CookieStorage>,
+    path: Option<&std::path::Path>,
+    metadata: &MetadataTable,
+    access_log: &AccessLog,
+    persist_session_cookies: bool,
+    compact: bool,
+) -> io::Result<usize> {
+    let mut persisted = 0;
+    if let Some(storage) = storage {
+        let cookies = cookie_store
+            .read()
+            .await
+            .iter_unexpired()
+            .filter(|cookie| persist_session_cookies || cookie.is_persistent())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if compact {
+            storage.compact(&cookies).await?;
+        } else {
+            storage.persist(&cookies).await?;
+        }
+        persisted = cookies.len();
+    }
+
+    if let Some(path) = path {
+        let annotations_changed = metadata.take_annotations_changed();
+        if access_log.take_changed() || annotations_changed {
+            let records = metadata.annotation_records(access_log);
+            let bytes =
+                serde_json::to_vec_pretty(&records).map_err(|e| SerializeError::wrap(None, e))?;
+            runtime::fs::write(annotations_path(path), bytes).await?;
+        }
+    }
+
+    Ok(persisted)
+}
+
+/// `url` with a `ws://` or `wss://` scheme replaced by `http://` or
+/// `https://`, the schemes cookies are matched against
+fn websocket_handshake_url(url: &Url) -> Url {
+    let mut url = url.clone();
+    let scheme = match url.scheme() {
+        "ws" => Some("http"),
+        "wss" => Some("https"),
+        _ => None,
+    };
+    if let Some(scheme) = scheme {
+        let _ = url.set_scheme(scheme);
+    }
+    url
+}
+
+/// The file that annotations for the jar at `path` are persisted in
+fn annotations_path(path: &std::path::Path) -> PathBuf {
+    let mut annotations_path = path.as_os_str().to_owned();
+    annotations_path.push(".annotations.json");
+    PathBuf::from(annotations_path)
+}
+
+const REDIRECT_CODES: &[StatusCode] = &[
+    StatusCode::MovedPermanently,
+    StatusCode::Found,
+    StatusCode::SeeOther,
+    StatusCode::TemporaryRedirect,
+    StatusCode::PermanentRedirect,
+];
+
+/// Where a redirect response points, resolved against the url of the
+/// request
+fn redirect_location(url: &Url, res: &Response) -> Result<Option<Url>> {
+    if !REDIRECT_CODES.contains(&res.status()) {
+        return Ok(None);
+    }
+
+    match res.header(LOCATION) {
+        Some(location) => Ok(Some(url.join(location.last().as_str())?)),
+        None => Ok(None),
+    }
+}
+
+/// Which cookie to keep when one response sets the same cookie more
+/// than once. See
+/// [`CookieMiddleware::with_duplicate_cookies`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateCookies {
+    /// Keep the first Set-Cookie header for the cookie and ignore the
+    /// rest
+    FirstWins,
+
+    /// Each Set-Cookie header for the cookie replaces the one before
+    /// it, as browsers do
+    #[default]
+    LastWins,
+}
+
+/// What to do with a Cookie header that the application set on a
+/// request itself. See
+/// [`CookieMiddleware::with_existing_cookie_header`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExistingCookieHeader {
+    /// Discard it and send only the cookies in the jar
+    Replace,
+
+    /// Send its cookies followed by the matching cookies in the jar.
+    /// Its cookies take precedence over jar cookies with the same
+    /// name.
+    #[default]
+    Append,
+
+    /// Send it unchanged, without any cookies from the jar
+    Skip,
+}
+
+/// Parses a Set-Cookie header, restoring the double quotes that the
+/// cookie crate strips from quoted values
+fn parse_preserving_quotes(header: &str) -> std::result::Result<RawCookie<'_>, CookieError> {
+    let mut raw = RawCookie::parse(header).map_err(|_| CookieError::Parse)?;
+
+    let quoted_value = header
+        .split(';')
+        .next()
+        .and_then(|pair| pair.split_once('='))
+        .map(|(_, value)| value.trim())
+        .filter(|value| value.len() >= 2 && value.starts_with('"') && value.ends_with('"'));
+
+    if let Some(quoted_value) = quoted_value {
+        raw.set_value(quoted_value);
+    }
+
+    Ok(raw)
 }
@@ -0,0 +1,128 @@
+//! Capping the number of cookies in the jar
+use crate::{acceptance::domain_site, metadata::cookie_key, pruning::AccessLog, CookieJarBackend};
+use cookie_store::Cookie;
+use publicsuffix::List;
+use std::collections::{HashMap, HashSet};
+
+/// # How many cookies the jar may hold
+///
+/// A client that visits many hosts, such as a crawler, can otherwise
+/// grow the jar without bound. When a response takes the jar over a
+/// limit, cookies are evicted until it is back within it, in the order
+/// given by [`Eviction`]. Browsers allow about 180 cookies per site
+/// and 3000 in total.
+///
+/// Sites are registrable domains, according to the list provided with
+/// [`with_public_suffix_list`](crate::CookieMiddleware::with_public_suffix_list)
+/// if there is one, and otherwise the last two labels of the domain.
+///
+/// ```rust
+/// use surf_cookie_middleware::{CookieLimits, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new()
+///     .with_limits(Some(CookieLimits::new().max_per_site(180).max_total(3000)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CookieLimits {
+    max_per_site: Option<usize>,
+    max_total: Option<usize>,
+    eviction: Eviction,
+}
+
+/// Which cookies are evicted first when the jar is over a
+/// [limit](CookieLimits)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eviction {
+    /// The cookies that were sent or set least recently
+    #[default]
+    LeastRecentlyUsed,
+
+    /// The cookies that were first set longest ago
+    Oldest,
+}
+
+impl CookieLimits {
+    /// No limits, to be set with the other methods
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Holds at most `max` cookies for each site
+    pub fn max_per_site(mut self, max: usize) -> Self {
+        self.max_per_site = Some(max);
+        self
+    }
+
+    /// Holds at most `max` cookies in total
+    pub fn max_total(mut self, max: usize) -> Self {
+        self.max_total = Some(max);
+        self
+    }
+
+    /// Sets which cookies are evicted first
+    pub fn eviction(mut self, eviction: Eviction) -> Self {
+        self.eviction = eviction;
+        self
+    }
+
+    /// Removes cookies from `cookie_store` until it is within these
+    /// limits, returning the ones that were removed. Expired cookies
+    /// don't count toward the limits.
+    pub(crate) fn enforce(
+        &self,
+        cookie_store: &mut impl CookieJarBackend,
+        access_log: &AccessLog,
+        list: Option<&List>,
+    ) -> Vec<Cookie<'static>> {
+        if self.max_per_site.is_none() && self.max_total.is_none() {
+            return vec![];
+        }
+
+        // least deserving first
+        let mut cookies = cookie_store
+            .iter_unexpired()
+            .map(|cookie| {
+                let rank = match self.eviction {
+                    Eviction::LeastRecentlyUsed => access_log.last_used(cookie),
+                    Eviction::Oldest => access_log.created(cookie),
+                };
+                let domain = String::from(&cookie.domain);
+                (rank, cookie_key(cookie), domain_site(&domain, list))
+            })
+            .collect::<Vec<_>>();
+        cookies.sort();
+
+        let mut evicted = HashSet::new();
+        if let Some(max) = self.max_per_site {
+            let mut per_site = HashMap::<&str, usize>::new();
+            for (_, _, site) in &cookies {
+                *per_site.entry(site).or_default() += 1;
+            }
+
+            for (_, key, site) in &cookies {
+                let count = per_site.get_mut(site.as_str()).unwrap();
+                if *count > max {
+                    *count -= 1;
+                    evicted.insert(key.clone());
+                }
+            }
+        }
+
+        if let Some(max) = self.max_total {
+            let mut excess = (cookies.len() - evicted.len()).saturating_sub(max);
+            for (_, key, _) in &cookies {
+                if excess == 0 {
+                    break;
+                }
+                if evicted.insert(key.clone()) {
+                    excess -= 1;
+                }
+            }
+        }
+
+        evicted
+            .into_iter()
+            .filter_map(|(domain, path, name)| cookie_store.remove(&domain, &path, &name))
+            .collect()
+    }
+}
@@ -0,0 +1,224 @@
+//! What is kept about each cookie beyond what the cookie store holds
+use crate::{
+    pruning::{Access, AccessLog},
+    validation::ATTRIBUTE_NAMES,
+};
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+/// The domain, path, and name that identify a cookie
+pub(crate) type CookieKey = (String, String, String);
+
+pub(crate) fn cookie_key(cookie: &Cookie<'_>) -> CookieKey {
+    (
+        String::from(&cookie.domain),
+        String::from(&cookie.path),
+        cookie.name().to_string(),
+    )
+}
+
+/// The attributes of a Set-Cookie header that the cookie crate does
+/// not recognize, such as `Priority=High`, in the order received
+pub(crate) fn extension_attributes(header: &str) -> Vec<(String, Option<String>)> {
+    header
+        .split(';')
+        .skip(1)
+        .map(|attribute| match attribute.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (attribute.trim(), None),
+        })
+        .filter(|(name, _)| {
+            !name.is_empty()
+                && !ATTRIBUTE_NAMES
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(name))
+        })
+        .map(|(name, value)| (name.to_string(), value.map(String::from)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+struct Metadata {
+    extensions: Vec<(String, Option<String>)>,
+    annotations: BTreeSet<String>,
+}
+
+/// The annotations and use times of one cookie, as persisted next to
+/// the jar
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct AnnotationRecord {
+    domain: String,
+    path: String,
+    name: String,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    annotations: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    access: Option<Access>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MetadataTable {
+    table: Mutex<HashMap<CookieKey, Metadata>>,
+    annotations_changed: AtomicBool,
+}
+
+impl MetadataTable {
+    /// Records the extension attributes `cookie` was set with,
+    /// replacing those of any cookie it replaced. An expired cookie is
+    /// forgotten.
+    pub(crate) fn stored(&self, cookie: &Cookie<'_>, extensions: Vec<(String, Option<String>)>) {
+        let key = cookie_key(cookie);
+        if cookie.is_expired() {
+            self.forget(Some(&key));
+        } else {
+            self.table
+                .lock()
+                .unwrap()
+                .entry(key)
+                .or_default()
+                .extensions = extensions;
+        }
+    }
+
+    pub(crate) fn forget<'a>(&self, keys: impl IntoIterator<Item = &'a CookieKey>) {
+        let mut table = self.table.lock().unwrap();
+        for key in keys {
+            if let Some(metadata) = table.remove(key) {
+                if !metadata.annotations.is_empty() {
+                    self.annotations_changed.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn extensions(&self, key: &CookieKey) -> Vec<(String, Option<String>)> {
+        self.table
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|metadata| metadata.extensions.clone())
+            .unwrap_or_default()
+    }
+
+    /// Adds `annotation` to a cookie, returning false if it already had
+    /// it
+    pub(crate) fn annotate(&self, key: CookieKey, annotation: String) -> bool {
+        let added = self
+            .table
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_default()
+            .annotations
+            .insert(annotation);
+        if added {
+            self.annotations_changed.store(true, Ordering::Relaxed);
+        }
+        added
+    }
+
+    /// Removes `annotation` from a cookie, returning false if it did
+    /// not have it
+    pub(crate) fn remove_annotation(&self, key: &CookieKey, annotation: &str) -> bool {
+        let removed = self
+            .table
+            .lock()
+            .unwrap()
+            .get_mut(key)
+            .is_some_and(|metadata| metadata.annotations.remove(annotation));
+        if removed {
+            self.annotations_changed.store(true, Ordering::Relaxed);
+        }
+        removed
+    }
+
+    pub(crate) fn annotations(&self, key: &CookieKey) -> Vec<String> {
+        self.table
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|metadata| metadata.annotations.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The cookies that have `annotation`, sorted by domain, path, and
+    /// name
+    pub(crate) fn annotated(&self, annotation: &str) -> Vec<CookieKey> {
+        let mut keys = self
+            .table
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, metadata)| metadata.annotations.contains(annotation))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    /// Whether annotations have changed since this was last called
+    pub(crate) fn take_annotations_changed(&self) -> bool {
+        self.annotations_changed.swap(false, Ordering::Relaxed)
+    }
+
+    /// The annotations of every cookie, with the use times in
+    /// `access_log`, sorted by domain, path, and name
+    pub(crate) fn annotation_records(&self, access_log: &AccessLog) -> Vec<AnnotationRecord> {
+        let mut records = BTreeMap::<CookieKey, AnnotationRecord>::new();
+        let record = |(domain, path, name): &CookieKey| AnnotationRecord {
+            domain: domain.clone(),
+            path: path.clone(),
+            name: name.clone(),
+            annotations: BTreeSet::new(),
+            access: None,
+        };
+
+        for (key, metadata) in self.table.lock().unwrap().iter() {
+            if !metadata.annotations.is_empty() {
+                records
+                    .entry(key.clone())
+                    .or_insert_with(|| record(key))
+                    .annotations = metadata.annotations.clone();
+            }
+        }
+        for (key, access) in access_log.accesses() {
+            records
+                .entry(key.clone())
+                .or_insert_with(|| record(&key))
+                .access = Some(access);
+        }
+        records.into_values().collect()
+    }
+
+    /// Restores persisted annotations and use times for the cookies
+    /// that `exists`
+    pub(crate) fn load_annotations(
+        &self,
+        records: Vec<AnnotationRecord>,
+        access_log: &AccessLog,
+        exists: impl Fn(&CookieKey) -> bool,
+    ) {
+        let mut table = self.table.lock().unwrap();
+        let mut accesses = vec![];
+        for record in records {
+            let key = (record.domain, record.path, record.name);
+            if !exists(&key) {
+                continue;
+            }
+            if let Some(access) = record.access {
+                accesses.push((key.clone(), access));
+            }
+            if !record.annotations.is_empty() {
+                table.entry(key).or_default().annotations = record.annotations;
+            }
+        }
+        access_log.load(accesses);
+    }
+}
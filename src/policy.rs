@@ -0,0 +1,49 @@
+//! Application rules for which cookies are stored and sent
+use cookie_store::Cookie;
+use std::fmt::Debug;
+use surf::Url;
+
+/// # Custom rules for storing and sending cookies
+///
+/// Implement this trait for business rules that the built-in options
+/// don't cover, such as dropping analytics cookies or keeping only
+/// session cookies, and attach it with
+/// [`CookieMiddleware::with_policy`](crate::CookieMiddleware::with_policy).
+/// Both methods allow every cookie by default. The policy is consulted
+/// after the middleware's own checks, so it only sees cookies that
+/// would otherwise be stored or sent.
+///
+/// Cookies refused by [`allow_store`](Self::allow_store) are reported
+/// as [`RejectionReason::Policy`](crate::events::RejectionReason::Policy).
+/// Cookies set with [`set_cookie`](crate::CookieMiddleware::set_cookie)
+/// or imported are not checked, but are still subject to
+/// [`allow_send`](Self::allow_send).
+///
+/// ```rust
+/// use surf::Url;
+/// use surf_cookie_middleware::{cookie_store::Cookie, CookieMiddleware, CookiePolicy};
+///
+/// #[derive(Debug)]
+/// struct SessionsOnly;
+///
+/// impl CookiePolicy for SessionsOnly {
+///     fn allow_store(&self, cookie: &Cookie<'_>, _url: &Url) -> bool {
+///         cookie.name().starts_with("session")
+///     }
+/// }
+///
+/// let middleware = CookieMiddleware::new().with_policy(SessionsOnly);
+/// ```
+pub trait CookiePolicy: Debug + Send + Sync + 'static {
+    /// Whether `cookie`, set by a response to `url`, is stored
+    fn allow_store(&self, cookie: &Cookie<'_>, url: &Url) -> bool {
+        let _ = (cookie, url);
+        true
+    }
+
+    /// Whether `cookie` is sent with a request to `url`
+    fn allow_send(&self, cookie: &Cookie<'_>, url: &Url) -> bool {
+        let _ = (cookie, url);
+        true
+    }
+}
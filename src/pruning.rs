@@ -0,0 +1,236 @@
+//! Dropping cookies that have not been used for a while
+use crate::metadata::{cookie_key as key, CookieKey};
+use crate::CookieJarBackend;
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+/// # When to drop cookies that have gone unused
+///
+/// A long-running client, such as a crawler, can collect cookies that
+/// are never sent again but have not expired. With a pruning policy,
+/// cookies that have been neither sent nor set for `max_idle` are
+/// removed from the jar. The jar is checked at most once per
+/// `interval` (an hour by default) when a response is processed, and
+/// whenever [`CookieMiddleware::prune`](crate::CookieMiddleware::prune)
+/// is called.
+///
+/// Use times of a jar built with a path, such as with
+/// [`from_path`](crate::CookieMiddleware::from_path), are persisted
+/// next to it with its annotations each time it is saved, so idle
+/// cookies are pruned across restarts. To prune as the jar is loaded,
+/// set the policy with
+/// [`CookieMiddlewareBuilder::pruning`](crate::CookieMiddlewareBuilder::pruning).
+/// With other storage, use times are kept in memory, and a loaded
+/// cookie counts as used when the middleware was built.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use surf_cookie_middleware::{CookieMiddleware, PruningPolicy};
+///
+/// let thirty_days = Duration::from_secs(30 * 24 * 60 * 60);
+/// let middleware = CookieMiddleware::new().with_pruning(Some(PruningPolicy::new(thirty_days)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PruningPolicy {
+    max_idle: Duration,
+    interval: Duration,
+}
+
+impl PruningPolicy {
+    /// Drops cookies that have not been used for `max_idle`
+    pub fn new(max_idle: Duration) -> Self {
+        Self {
+            max_idle,
+            interval: Duration::from_secs(60 * 60),
+        }
+    }
+
+    /// Sets how often the jar is checked for idle cookies
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// How often expired cookies are purged from the jar as responses
+/// are processed
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// When a cookie was first set and last sent or set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Access {
+    pub(crate) created: SystemTime,
+    pub(crate) last: SystemTime,
+}
+
+/// When each cookie was first set and last sent or set. Cookies this
+/// log has not seen, such as those loaded from a file without
+/// persisted use times, count as created and used when the log was.
+#[derive(Debug)]
+pub(crate) struct AccessLog {
+    created: SystemTime,
+    accesses: Mutex<HashMap<CookieKey, Access>>,
+    changed: AtomicBool,
+    last_pruned: Mutex<Instant>,
+    last_purged: Mutex<Instant>,
+}
+
+impl Default for AccessLog {
+    fn default() -> Self {
+        Self {
+            created: SystemTime::now(),
+            accesses: Mutex::default(),
+            changed: AtomicBool::new(false),
+            last_pruned: Mutex::new(Instant::now()),
+            last_purged: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl AccessLog {
+    /// Records that `cookies` were sent
+    pub(crate) fn touch<'a>(&self, cookies: impl IntoIterator<Item = &'a Cookie<'a>>) {
+        let now = SystemTime::now();
+        let mut accesses = self.accesses.lock().unwrap();
+        for cookie in cookies {
+            self.changed.store(true, Ordering::Relaxed);
+            accesses
+                .entry(key(cookie))
+                .or_insert(Access {
+                    created: self.created,
+                    last: now,
+                })
+                .last = now;
+        }
+    }
+
+    /// Records that `cookie` was set. A replaced cookie keeps the
+    /// creation time of the cookie it replaced, and an expired cookie
+    /// is forgotten.
+    pub(crate) fn stored(&self, cookie: &Cookie<'_>) {
+        let mut accesses = self.accesses.lock().unwrap();
+        self.changed.store(true, Ordering::Relaxed);
+        if cookie.is_expired() {
+            accesses.remove(&key(cookie));
+        } else {
+            let now = SystemTime::now();
+            accesses
+                .entry(key(cookie))
+                .or_insert(Access {
+                    created: now,
+                    last: now,
+                })
+                .last = now;
+        }
+    }
+
+    /// Forgets cookies that were removed from the jar
+    pub(crate) fn forget<'a>(&self, keys: impl IntoIterator<Item = &'a CookieKey>) {
+        let mut accesses = self.accesses.lock().unwrap();
+        for key in keys {
+            if accesses.remove(key).is_some() {
+                self.changed.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// When each cookie this log has seen was first set and last used
+    pub(crate) fn accesses(&self) -> Vec<(CookieKey, Access)> {
+        self.accesses
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, access)| (key.clone(), *access))
+            .collect()
+    }
+
+    /// Restores use times persisted by an earlier run
+    pub(crate) fn load(&self, accesses: impl IntoIterator<Item = (CookieKey, Access)>) {
+        self.accesses.lock().unwrap().extend(accesses);
+    }
+
+    /// Whether use times have changed since this was last called
+    pub(crate) fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+
+    /// When `cookie` was first set
+    pub(crate) fn created(&self, cookie: &Cookie<'_>) -> SystemTime {
+        self.accesses
+            .lock()
+            .unwrap()
+            .get(&key(cookie))
+            .map_or(self.created, |access| access.created)
+    }
+
+    /// When `cookie` was last sent or set
+    pub(crate) fn last_used(&self, cookie: &Cookie<'_>) -> SystemTime {
+        self.accesses
+            .lock()
+            .unwrap()
+            .get(&key(cookie))
+            .map_or(self.created, |access| access.last)
+    }
+
+    /// Whether `policy` calls for a check now, in which case the next
+    /// check is due after another interval
+    pub(crate) fn due(&self, policy: &PruningPolicy) -> bool {
+        let mut last_pruned = self.last_pruned.lock().unwrap();
+        if last_pruned.elapsed() >= policy.interval {
+            *last_pruned = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether expired cookies are due to be purged, in which case the
+    /// next purge is due after another interval
+    pub(crate) fn purge_due(&self) -> bool {
+        let mut last_purged = self.last_purged.lock().unwrap();
+        if last_purged.elapsed() >= PURGE_INTERVAL {
+            *last_purged = Instant::now();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes cookies from `cookie_store` that have been idle for
+    /// longer than `max_idle`, returning the ones that were removed
+    pub(crate) fn prune(
+        &self,
+        cookie_store: &mut impl CookieJarBackend,
+        policy: &PruningPolicy,
+    ) -> Vec<Cookie<'static>> {
+        let mut accesses = self.accesses.lock().unwrap();
+        let idle = cookie_store
+            .iter_any()
+            .map(key)
+            .filter(|key| {
+                let last = accesses.get(key).map_or(self.created, |access| access.last);
+                // a last use in the future, after the clock was turned
+                // back, counts as just now
+                last.elapsed().unwrap_or_default() >= policy.max_idle
+            })
+            .collect::<Vec<_>>();
+
+        idle.iter()
+            .filter_map(|key| {
+                if accesses.remove(key).is_some() {
+                    self.changed.store(true, Ordering::Relaxed);
+                }
+                let (domain, path, name) = key;
+                cookie_store.remove(domain, path, name)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,94 @@
+use crate::CookieMiddleware;
+use std::{fmt, sync::Arc};
+use surf::{
+    middleware::{Middleware, Next},
+    utils::async_trait,
+    Client, Request, Response, Result,
+};
+
+type Predicate = Arc<dyn Fn(&Request) -> bool + Send + Sync + 'static>;
+
+/// # A middleware that routes each request to one of several cookie jars
+///
+/// Each route pairs a predicate over the request with a
+/// [`CookieMiddleware`]. The first route whose predicate matches
+/// handles the request, and requests that match no route use the
+/// fallback jar. This lets one client keep a separate identity per
+/// tenant or group of hosts.
+///
+/// The jars are ordinary `CookieMiddleware`s, so each can have its own
+/// options and persistence, and a clone kept outside of the router
+/// refers to the same cookies.
+///
+/// # Example
+///
+/// ```rust
+/// use surf_cookie_middleware::{CookieMiddleware, JarRouter};
+///
+/// let router = JarRouter::new(CookieMiddleware::new())
+///     .with_route(
+///         |req| req.url().host_str() == Some("tenant-a.example.com"),
+///         CookieMiddleware::new(),
+///     )
+///     .with_route(
+///         |req| req.header("x-tenant").is_some_and(|tenant| tenant == "b"),
+///         CookieMiddleware::new(),
+///     );
+///
+/// let client = surf::Client::new().with(router);
+/// ```
+#[derive(Clone)]
+pub struct JarRouter {
+    routes: Vec<(Predicate, CookieMiddleware)>,
+    fallback: CookieMiddleware,
+}
+
+impl JarRouter {
+    /// Builds a router that sends every request to `fallback` until
+    /// routes are added
+    pub fn new(fallback: CookieMiddleware) -> Self {
+        Self {
+            routes: vec![],
+            fallback,
+        }
+    }
+
+    /// Adds a route that uses `jar` for requests matching `predicate`.
+    /// Routes are tried in the order they were added.
+    pub fn with_route(
+        mut self,
+        predicate: impl Fn(&Request) -> bool + Send + Sync + 'static,
+        jar: CookieMiddleware,
+    ) -> Self {
+        self.routes.push((Arc::new(predicate), jar));
+        self
+    }
+
+    /// The jar that handles `req`
+    pub fn jar_for(&self, req: &Request) -> &CookieMiddleware {
+        self.routes
+            .iter()
+            .find(|(predicate, _)| predicate(req))
+            .map_or(&self.fallback, |(_, jar)| jar)
+    }
+}
+
+impl fmt::Debug for JarRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JarRouter")
+            .field(
+                "routes",
+                &self.routes.iter().map(|(_, jar)| jar).collect::<Vec<_>>(),
+            )
+            .field("fallback", &self.fallback)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Middleware for JarRouter {
+    async fn handle(&self, req: Request, client: Client, next: Next<'_>) -> Result<Response> {
+        let jar = self.jar_for(&req).clone();
+        jar.handle(req, client, next).await
+    }
+}
@@ -0,0 +1,95 @@
+//! The async runtime that jar files are read and written on, and that
+//! background saves run on: async-std by default, or tokio when the
+//! crate is built with only the `tokio` feature
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+compile_error!("surf-cookie-middleware needs either the `async-std` or the `tokio` feature");
+
+#[cfg(feature = "async-std")]
+mod imp {
+    pub(crate) use async_std::{fs, fs::File};
+
+    pub(crate) mod io {
+        pub(crate) use async_std::io::{
+            prelude::{ReadExt, SeekExt, WriteExt},
+            SeekFrom,
+        };
+    }
+
+    pub(crate) fn spawn(future: impl std::future::Future<Output = ()> + Send + 'static) -> bool {
+        async_std::task::spawn(future);
+        true
+    }
+
+    pub(crate) async fn sleep(duration: std::time::Duration) {
+        async_std::task::sleep(duration).await
+    }
+
+    pub(crate) async fn spawn_blocking<T: Send + 'static>(
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> T {
+        async_std::task::spawn_blocking(f).await
+    }
+
+    pub(crate) fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+        async_std::task::block_on(future)
+    }
+}
+
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+mod imp {
+    pub(crate) use tokio::{fs, fs::File};
+
+    pub(crate) mod io {
+        pub(crate) use std::io::SeekFrom;
+        pub(crate) use tokio::io::{
+            AsyncReadExt as ReadExt, AsyncSeekExt as SeekExt, AsyncWriteExt as WriteExt,
+        };
+    }
+
+    /// Spawns `future` on the current runtime, returning false if
+    /// there is none to spawn it on
+    pub(crate) fn spawn(future: impl std::future::Future<Output = ()> + Send + 'static) -> bool {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(future);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub(crate) async fn sleep(duration: std::time::Duration) {
+        tokio::time::sleep(duration).await
+    }
+
+    pub(crate) async fn spawn_blocking<T: Send + 'static>(
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> T {
+        match tokio::task::spawn_blocking(f).await {
+            Ok(output) => output,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+
+    /// Runs `future` to completion from synchronous code, such as a
+    /// drop. Inside a runtime, the future runs on another thread with
+    /// a handle to that runtime, since the current thread may be one
+    /// of its workers. Outside of one, a runtime is started for it.
+    pub(crate) fn block_on<T: Send>(future: impl std::future::Future<Output = T> + Send) -> T {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => std::thread::scope(|scope| {
+                scope
+                    .spawn(|| handle.block_on(future))
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            }),
+            Err(_) => tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("could not start a tokio runtime")
+                .block_on(future),
+        }
+    }
+}
+
+pub(crate) use imp::*;
@@ -0,0 +1,71 @@
+//! Withholding SameSite cookies from cross-site requests
+use crate::acceptance::site;
+use cookie::SameSite;
+use cookie_store::Cookie;
+use publicsuffix::List;
+use surf::{http::Method, Url};
+
+/// # The site a request is made on behalf of
+///
+/// Browsers withhold SameSite cookies from requests that a page on
+/// another site makes, such as an embedded iframe or a cross-site form
+/// post. To reproduce this, declare the "site for cookies" (the url of
+/// the top-level page) for a client with
+/// [`CookieMiddleware::with_site_for_cookies`](crate::CookieMiddleware::with_site_for_cookies),
+/// or for a single request by adding this as an extension, which takes
+/// precedence.
+///
+/// When the request is to a different site (registrable domain) than
+/// the site for cookies, `SameSite=Strict` cookies are not sent, and
+/// `SameSite=Lax` cookies are only sent with GET and HEAD requests, as
+/// for a top-level navigation. Cookies with `SameSite=None` or without
+/// a SameSite attribute are always sent. Without a site for cookies,
+/// every request is treated as same-site.
+///
+/// ```rust
+/// # fn main() -> surf::Result<()> {
+/// use surf::Url;
+/// use surf_cookie_middleware::SiteForCookies;
+///
+/// let mut req = surf::post("https://api.example.com/like").build();
+/// req.set_ext(SiteForCookies(Url::parse("https://blog.example.net/")?));
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SiteForCookies(pub Url);
+
+/// Whether a request is cross-site, and so which SameSite cookies it
+/// may carry
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SameSiteContext {
+    cross_site: bool,
+    safe_method: bool,
+}
+
+impl SameSiteContext {
+    pub(crate) fn new(
+        url: &Url,
+        site_for_cookies: Option<&Url>,
+        method: Method,
+        list: Option<&List>,
+    ) -> Self {
+        Self {
+            cross_site: site_for_cookies
+                .is_some_and(|site_for_cookies| site(url, list) != site(site_for_cookies, list)),
+            safe_method: matches!(method, Method::Get | Method::Head),
+        }
+    }
+
+    /// Whether `cookie` may be sent with the request
+    pub(crate) fn allows(&self, cookie: &Cookie<'_>) -> bool {
+        if !self.cross_site {
+            return true;
+        }
+
+        match cookie.same_site() {
+            Some(SameSite::Strict) => false,
+            Some(SameSite::Lax) => self.safe_method,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! Persisting the jar when the last clone of a middleware is dropped
+use std::{fmt, future::Future, io, pin::Pin, sync::Mutex};
+
+type Save = Pin<Box<dyn Future<Output = io::Result<()>> + Send + 'static>>;
+
+/// Holds a save of the jar that runs when the last clone of the
+/// middleware, and with it this guard, is dropped
+pub(crate) struct SaveOnDrop(Mutex<Option<Save>>);
+
+impl SaveOnDrop {
+    pub(crate) fn new(save: impl Future<Output = io::Result<()>> + Send + 'static) -> Self {
+        Self(Mutex::new(Some(Box::pin(save))))
+    }
+}
+
+impl Drop for SaveOnDrop {
+    fn drop(&mut self) {
+        let save = self.0.get_mut().ok().and_then(Option::take);
+        if let Some(save) = save {
+            if let Err(e) = crate::runtime::block_on(save) {
+                log::warn!("could not persist cookie jar on drop: {}", e);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SaveOnDrop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SaveOnDrop")
+    }
+}
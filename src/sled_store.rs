@@ -0,0 +1,108 @@
+//! Persisting the jar to a sled database
+use crate::{
+    metadata::{cookie_key, CookieKey},
+    CookieStorage,
+};
+use cookie_store::Cookie;
+use std::{collections::HashMap, io, path::Path, sync::Mutex};
+use surf::utils::async_trait;
+
+/// # A cookie jar in a [sled](https://docs.rs/sled) database
+///
+/// Each cookie is an entry keyed by its domain, path, and name. When
+/// the jar is persisted, only the cookies that were added, changed, or
+/// removed since the last write are written, as one atomic batch, so a
+/// large jar is not rewritten after every response.
+///
+/// Available with the `sled` feature.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{CookieMiddleware, SledCookieStore};
+///
+/// let middleware = CookieMiddleware::builder()
+///     .storage(SledCookieStore::open("./cookies.sled")?)
+///     .build()
+///     .await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug)]
+pub struct SledCookieStore {
+    tree: sled::Tree,
+    persisted: Mutex<HashMap<CookieKey, String>>,
+}
+
+impl SledCookieStore {
+    /// Opens the database at `path`, creating it if needed. sled
+    /// locks the database while it is open, so open it once and share
+    /// the middleware rather than opening it again.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let db = sled::open(path)?;
+        Ok(Self::with_tree(db.open_tree("cookies")?))
+    }
+
+    /// Uses a tree of an open database, such as one the application
+    /// also uses for other data. The tree should hold nothing but
+    /// cookies.
+    pub fn with_tree(tree: sled::Tree) -> Self {
+        Self {
+            tree,
+            persisted: Mutex::default(),
+        }
+    }
+}
+
+fn entry_key(key: &CookieKey) -> io::Result<Vec<u8>> {
+    Ok(serde_json::to_vec(key)?)
+}
+
+#[async_trait]
+impl CookieStorage for SledCookieStore {
+    async fn load(&self) -> io::Result<Vec<Cookie<'static>>> {
+        let mut persisted = HashMap::new();
+        let mut cookies = vec![];
+
+        for entry in self.tree.iter() {
+            let (_, value) = entry?;
+            let cookie: Cookie<'static> = serde_json::from_slice(&value)?;
+            persisted.insert(
+                cookie_key(&cookie),
+                String::from_utf8_lossy(&value).into_owned(),
+            );
+            cookies.push(cookie);
+        }
+
+        *self.persisted.lock().unwrap() = persisted;
+        Ok(cookies)
+    }
+
+    async fn persist(&self, cookies: &[Cookie<'static>]) -> io::Result<()> {
+        let rows = cookies
+            .iter()
+            .map(|cookie| Ok((cookie_key(cookie), serde_json::to_string(cookie)?)))
+            .collect::<io::Result<HashMap<_, _>>>()?;
+
+        {
+            let mut persisted = self.persisted.lock().unwrap();
+            let mut batch = sled::Batch::default();
+
+            for (key, cookie) in &rows {
+                if persisted.get(key) != Some(cookie) {
+                    batch.insert(entry_key(key)?, cookie.as_bytes());
+                }
+            }
+
+            for key in persisted.keys() {
+                if !rows.contains_key(key) {
+                    batch.remove(entry_key(key)?);
+                }
+            }
+
+            self.tree.apply_batch(batch)?;
+            *persisted = rows;
+        }
+
+        self.tree.flush_async().await?;
+        Ok(())
+    }
+}
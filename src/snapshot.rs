@@ -0,0 +1,48 @@
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+
+/// # A copy of the cookies in a jar
+///
+/// Taken with [`CookieMiddleware::snapshot`](crate::CookieMiddleware::snapshot)
+/// and applied with [`restore`](crate::CookieMiddleware::restore). A
+/// snapshot can be serialized with any serde format, so that
+/// applications can keep a jar in their own database or session store
+/// instead of a jar file. Unlike a jar file, a snapshot includes
+/// session cookies.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{CookieJarSnapshot, CookieMiddleware};
+///
+/// let middleware = CookieMiddleware::new();
+/// let blob = serde_json::to_string(&middleware.snapshot().await)?;
+///
+/// let snapshot: CookieJarSnapshot = serde_json::from_str(&blob)?;
+/// CookieMiddleware::new().restore(&snapshot).await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CookieJarSnapshot {
+    pub(crate) cookies: Vec<Cookie<'static>>,
+
+    // only used by jars that keep http cookies apart
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) insecure_cookies: Vec<Cookie<'static>>,
+}
+
+impl CookieJarSnapshot {
+    /// The cookies that were in the jar when the snapshot was taken
+    pub fn cookies(&self) -> impl Iterator<Item = &Cookie<'static>> {
+        self.cookies.iter().chain(&self.insecure_cookies)
+    }
+
+    /// The number of cookies in the snapshot
+    pub fn len(&self) -> usize {
+        self.cookies.len() + self.insecure_cookies.len()
+    }
+
+    /// Whether the snapshot has no cookies
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
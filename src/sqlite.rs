@@ -0,0 +1,145 @@
+//! Persisting the jar to a SQLite database
+use crate::runtime;
+use crate::{
+    metadata::{cookie_key, CookieKey},
+    CookieStorage,
+};
+use cookie_store::Cookie;
+use rusqlite::{params, Connection};
+use std::{
+    collections::HashMap,
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use surf::utils::async_trait;
+
+/// # A cookie jar in a SQLite database
+///
+/// Each cookie is a row keyed by its domain, path, and name. When the
+/// jar is persisted, only the cookies that were added, changed, or
+/// removed since the last write are written, in a single transaction,
+/// so a large jar is not rewritten after every response.
+///
+/// Available with the `sqlite` feature.
+///
+/// ```rust
+/// # fn main() -> std::io::Result<()> { async_std::task::block_on(async {
+/// use surf_cookie_middleware::{CookieMiddleware, SqliteCookieStore};
+///
+/// let middleware = CookieMiddleware::builder()
+///     .storage(SqliteCookieStore::open("./cookies.sqlite")?)
+///     .build()
+///     .await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SqliteCookieStore(Arc<Mutex<Database>>);
+
+#[derive(Debug)]
+struct Database {
+    connection: Connection,
+    persisted: HashMap<CookieKey, String>,
+}
+
+impl SqliteCookieStore {
+    /// Opens the database at `path`, creating it and its cookie table
+    /// if needed
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::with_connection(Connection::open(path).map_err(io::Error::other)?)
+    }
+
+    /// Uses an open connection, such as one to a database the
+    /// application also uses for other tables. A `cookies` table is
+    /// created if it does not exist.
+    pub fn with_connection(connection: Connection) -> io::Result<Self> {
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS cookies (
+                    domain TEXT NOT NULL,
+                    path TEXT NOT NULL,
+                    name TEXT NOT NULL,
+                    cookie TEXT NOT NULL,
+                    PRIMARY KEY (domain, path, name)
+                )",
+            )
+            .map_err(io::Error::other)?;
+
+        Ok(Self(Arc::new(Mutex::new(Database {
+            connection,
+            persisted: HashMap::new(),
+        }))))
+    }
+}
+
+impl Database {
+    fn load(&mut self) -> rusqlite::Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT domain, path, name, cookie FROM cookies")?;
+        let rows = statement
+            .query_map(params![], |row| {
+                Ok(((row.get(0)?, row.get(1)?, row.get(2)?), row.get(3)?))
+            })?
+            .collect::<rusqlite::Result<HashMap<CookieKey, String>>>()?;
+
+        self.persisted = rows;
+        Ok(self.persisted.values().cloned().collect())
+    }
+
+    fn persist(&mut self, rows: HashMap<CookieKey, String>) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut upsert = transaction.prepare_cached(
+                "INSERT OR REPLACE INTO cookies (domain, path, name, cookie)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (key, cookie) in &rows {
+                if self.persisted.get(key) != Some(cookie) {
+                    let (domain, path, name) = key;
+                    upsert.execute(params![domain, path, name, cookie])?;
+                }
+            }
+
+            let mut delete = transaction.prepare_cached(
+                "DELETE FROM cookies WHERE domain = ?1 AND path = ?2 AND name = ?3",
+            )?;
+            for key in self.persisted.keys() {
+                if !rows.contains_key(key) {
+                    let (domain, path, name) = key;
+                    delete.execute(params![domain, path, name])?;
+                }
+            }
+        }
+        transaction.commit()?;
+
+        self.persisted = rows;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CookieStorage for SqliteCookieStore {
+    async fn load(&self) -> io::Result<Vec<Cookie<'static>>> {
+        let database = self.0.clone();
+        let rows = runtime::spawn_blocking(move || database.lock().unwrap().load())
+            .await
+            .map_err(io::Error::other)?;
+
+        rows.iter()
+            .map(|row| serde_json::from_str(row).map_err(io::Error::from))
+            .collect()
+    }
+
+    async fn persist(&self, cookies: &[Cookie<'static>]) -> io::Result<()> {
+        let rows = cookies
+            .iter()
+            .map(|cookie| Ok((cookie_key(cookie), serde_json::to_string(cookie)?)))
+            .collect::<io::Result<HashMap<_, _>>>()?;
+
+        let database = self.0.clone();
+        runtime::spawn_blocking(move || database.lock().unwrap().persist(rows))
+            .await
+            .map_err(io::Error::other)
+    }
+}
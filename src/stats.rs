@@ -0,0 +1,70 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counts of cookie activity, shared by all clones of a
+/// [`CookieMiddleware`](crate::CookieMiddleware)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CookieStats {
+    /// Cookies attached to outgoing requests
+    pub sent: u64,
+
+    /// Cookies stored or updated from Set-Cookie headers
+    pub stored: u64,
+
+    /// Set-Cookie headers that were not stored, each also reported as
+    /// a [`CookieEvent::Rejected`](crate::events::CookieEvent::Rejected)
+    pub rejected: u64,
+
+    /// Saves in a row that failed, whose changes are held in memory
+    /// until a save succeeds. Only counted with
+    /// [`with_persistence_quarantine`](crate::CookieMiddleware::with_persistence_quarantine).
+    pub unsaved: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    sent: AtomicU64,
+    stored: AtomicU64,
+    rejected: AtomicU64,
+    unsaved: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn sent(&self, count: usize) {
+        self.sent.fetch_add(count as u64, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("surf_cookies_sent_total").increment(count as u64);
+    }
+
+    pub(crate) fn stored(&self) {
+        self.stored.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("surf_cookies_stored_total").increment(1);
+    }
+
+    pub(crate) fn rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("surf_cookies_rejected_total").increment(1);
+    }
+
+    /// Counts a save that failed, returning the number of failures
+    /// since the last successful save
+    pub(crate) fn unsaved(&self) -> u64 {
+        self.unsaved.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Clears the failures after a successful save, returning how many
+    /// there were
+    pub(crate) fn saved(&self) -> u64 {
+        self.unsaved.swap(0, Ordering::Relaxed)
+    }
+
+    pub(crate) fn snapshot(&self) -> CookieStats {
+        CookieStats {
+            sent: self.sent.load(Ordering::Relaxed),
+            stored: self.stored.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            unsaved: self.unsaved.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,614 @@
+//! Where the persistent cookies of a jar are kept between runs
+use crate::runtime::{
+    self,
+    fs::{self, OpenOptions},
+    io::WriteExt,
+    File,
+};
+use crate::{
+    append_log::AppendLog,
+    events::Listeners,
+    formats::{invalid_data, request_url},
+    CookieFormat, CookieJarBackend, JarIo,
+};
+use async_lock::Mutex;
+use cookie_store::{Cookie, CookieStore};
+use fs2::FileExt;
+use std::{
+    fmt::{self, Debug},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use surf::utils::async_trait;
+use tempfile::NamedTempFile;
+
+/// # Persistence for the cookies held by a [`CookieMiddleware`](crate::CookieMiddleware)
+///
+/// [`FileStorage`] implements this trait and is what
+/// [`from_path`](crate::CookieMiddleware::from_path) and
+/// [`from_file`](crate::CookieMiddleware::from_file) use. Implement it
+/// to keep the jar in a database, an encrypted store, or other
+/// infrastructure, and build the middleware with
+/// [`CookieMiddlewareBuilder::storage`](crate::CookieMiddlewareBuilder::storage).
+///
+/// Only persistent cookies are persisted, unless the middleware is
+/// built with
+/// [`persist_session_cookies`](crate::CookieMiddlewareBuilder::persist_session_cookies).
+/// Expired cookies are never passed to [`persist`](Self::persist).
+#[async_trait]
+pub trait CookieStorage: Debug + Send + Sync + 'static {
+    /// Reads the cookies persisted by an earlier run, when the
+    /// middleware is built
+    async fn load(&self) -> io::Result<Vec<Cookie<'static>>>;
+
+    /// Replaces the persisted cookies with `cookies`, the unexpired
+    /// persistent cookies currently in the jar. This is called after
+    /// every response that changes the jar.
+    async fn persist(&self, cookies: &[Cookie<'static>]) -> io::Result<()>;
+
+    /// When the persisted cookies last changed, if the storage can
+    /// tell, so that
+    /// [`with_reload_on_change`](crate::CookieMiddleware::with_reload_on_change)
+    /// can pick up changes made by other processes. The default
+    /// returns `None`, which never triggers a reload.
+    async fn modified(&self) -> io::Result<Option<SystemTime>> {
+        Ok(None)
+    }
+
+    /// The size of the persisted jar in bytes, if the storage can
+    /// tell, for the [`PersistenceSummary`] passed to
+    /// [`on_save`](crate::CookieMiddleware::on_save) and
+    /// [`on_load`](crate::CookieMiddleware::on_load) callbacks. The
+    /// default returns `None`.
+    async fn size(&self) -> io::Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Rewrites the persisted cookies as compactly as possible, for
+    /// [`CookieMiddleware::compact`](crate::CookieMiddleware::compact).
+    /// The default calls [`persist`](Self::persist), which suits
+    /// storage that is always rewritten in full.
+    async fn compact(&self, cookies: &[Cookie<'static>]) -> io::Result<()> {
+        self.persist(cookies).await
+    }
+}
+
+/// # What happened when the jar was saved or loaded
+///
+/// Passed to callbacks registered with
+/// [`CookieMiddleware::on_save`](crate::CookieMiddleware::on_save) and
+/// [`on_load`](crate::CookieMiddleware::on_load), so that applications
+/// can alert on persistence failures or drive their own backups.
+#[derive(Debug)]
+pub struct PersistenceSummary {
+    /// The number of cookies written or read, or zero if it failed
+    pub cookies: usize,
+
+    /// The size of the persisted jar in bytes afterward, if the
+    /// storage reports it with [`CookieStorage::size`]
+    pub bytes: Option<u64>,
+
+    /// How long saving or loading took
+    pub duration: Duration,
+
+    /// Why saving or loading failed, if it did
+    pub error: Option<io::Error>,
+}
+
+impl PersistenceSummary {
+    pub(crate) async fn new(
+        storage: &dyn CookieStorage,
+        result: Result<usize, &io::Error>,
+        started: Instant,
+    ) -> Self {
+        let duration = started.elapsed();
+        match result {
+            Ok(cookies) => Self {
+                cookies,
+                bytes: storage.size().await.ok().flatten(),
+                duration,
+                error: None,
+            },
+
+            Err(e) => Self {
+                cookies: 0,
+                bytes: None,
+                duration,
+                error: Some(io::Error::new(e.kind(), e.to_string())),
+            },
+        }
+    }
+}
+
+/// # What a request does when the jar can't be saved
+///
+/// Set with
+/// [`CookieMiddleware::with_persistence_failure_policy`](crate::CookieMiddleware::with_persistence_failure_policy).
+/// The response has already been received when the jar is saved, so
+/// with any policy but `FailRequest` it is returned as usual and jar
+/// problems can be handled out of band.
+#[derive(Clone, Default)]
+pub enum PersistenceFailurePolicy {
+    /// The request fails with the error. This is the default.
+    #[default]
+    FailRequest,
+
+    /// The error is logged and the response is returned
+    LogAndContinue,
+
+    /// The error is passed to this callback and the response is
+    /// returned. See [`callback`](Self::callback).
+    Callback(Arc<dyn Fn(&io::Error) + Send + Sync>),
+}
+
+impl PersistenceFailurePolicy {
+    /// Passes errors to `callback` instead of failing the request
+    pub fn callback(callback: impl Fn(&io::Error) + Send + Sync + 'static) -> Self {
+        Self::Callback(Arc::new(callback))
+    }
+
+    /// Applies this policy to the result of saving the jar
+    pub(crate) fn handle(&self, result: io::Result<()>) -> io::Result<()> {
+        match (self, result) {
+            (Self::FailRequest, result) | (_, result @ Ok(())) => result,
+            (Self::LogAndContinue, Err(e)) => {
+                log::warn!("could not persist cookie jar: {}", e);
+                Ok(())
+            }
+            (Self::Callback(callback), Err(e)) => {
+                callback(&e);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Debug for PersistenceFailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FailRequest => f.write_str("FailRequest"),
+            Self::LogAndContinue => f.write_str("LogAndContinue"),
+            Self::Callback(_) => f.write_str("Callback(..)"),
+        }
+    }
+}
+
+/// # A cookie jar that could not be serialized
+///
+/// Carried inside the [`io::Error`] returned by
+/// [`CookieMiddleware::save`](crate::CookieMiddleware::save) and by
+/// requests that save the jar, so that serialization failures can be
+/// told apart from failures to write the serialized jar:
+///
+/// ```rust
+/// # use std::io;
+/// use surf_cookie_middleware::SerializeError;
+///
+/// fn is_serialization_failure(e: &io::Error) -> bool {
+///     e.get_ref()
+///         .is_some_and(|source| source.is::<SerializeError>())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SerializeError {
+    cookie: Option<String>,
+    source: Box<dyn std::error::Error + Send + Sync>,
+}
+
+impl SerializeError {
+    /// An [`io::Error`] wrapping the failure to serialize `cookie`, or
+    /// the whole jar if `None`. The error kind of an `io::Error` source
+    /// is kept, and is otherwise [`io::ErrorKind::InvalidData`].
+    pub(crate) fn wrap(
+        cookie: Option<&Cookie<'_>>,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> io::Error {
+        let source = source.into();
+        let kind = source
+            .downcast_ref::<io::Error>()
+            .map_or(io::ErrorKind::InvalidData, io::Error::kind);
+        let cookie = cookie.map(|cookie| cookie.name().to_string());
+        io::Error::new(kind, Self { cookie, source })
+    }
+
+    /// The name of the cookie that could not be serialized, if the
+    /// failure was specific to one cookie
+    pub fn cookie_name(&self) -> Option<&str> {
+        self.cookie.as_deref()
+    }
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.cookie {
+            Some(name) => write!(f, "could not serialize cookie {}: {}", name, self.source),
+            None => write!(f, "could not serialize cookie jar: {}", self.source),
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+/// # A cookie jar file
+///
+/// By default the file is in [ndjson](http://ndjson.org/) format, and a
+/// file that can't be read or parsed loads as an empty jar. The whole
+/// file is rewritten every time the jar is persisted, in place unless
+/// the storage is [`atomic`](Self::atomic), or only the changes are
+/// appended to it if the storage is [`append_only`](Self::append_only).
+pub struct FileStorage {
+    target: Target,
+    format: Option<Box<dyn CookieFormat + Send + Sync>>,
+    lock_file: Option<PathBuf>,
+}
+
+enum Target {
+    Io(Mutex<Box<dyn JarIo>>),
+    // the lock keeps saves from overtaking each other
+    Atomic(Mutex<PathBuf>),
+    Lazy(Mutex<PathBuf>),
+    AppendOnly(Mutex<AppendLog>),
+}
+
+impl FileStorage {
+    /// Persists to `file`, either an `async_std::fs::File` (a
+    /// `tokio::fs::File` with the `tokio` feature) or a
+    /// [`std::fs::File`], which must be open for reading and writing
+    pub fn new(file: impl Into<File>) -> Self {
+        Self::from_io(file.into())
+    }
+
+    /// Persists to `io`, such as an [`AsyncIo`](crate::AsyncIo) around
+    /// a file of any runtime
+    pub fn from_io(io: impl JarIo) -> Self {
+        Self {
+            target: Target::Io(Mutex::new(Box::new(io))),
+            format: None,
+            lock_file: None,
+        }
+    }
+
+    /// Persists to `file` in `format`, such as
+    /// [`Netscape`](crate::formats::Netscape). Unlike the default
+    /// format, a file that can't be parsed is an error, so that a file
+    /// shared with another tool is never replaced by an empty jar.
+    pub fn with_format(
+        file: impl Into<File>,
+        format: impl CookieFormat + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            format: Some(Box::new(format)),
+            ..Self::new(file)
+        }
+    }
+
+    /// Persists to the file at `path` with blocking [`std::fs`] calls,
+    /// made in place on the task that loads or saves the jar instead
+    /// of through the runtime's async files. Each save briefly blocks
+    /// the executor thread, which suits command line tools that make a
+    /// handful of requests. The file is created if it does not exist.
+    pub fn blocking(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(path)?;
+        Ok(Self::from_io(file))
+    }
+
+    /// Persists to `io`, as with [`from_io`](Self::from_io), in
+    /// `format`, as with [`with_format`](Self::with_format)
+    pub fn from_io_with_format(
+        io: impl JarIo,
+        format: impl CookieFormat + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            format: Some(Box::new(format)),
+            ..Self::from_io(io)
+        }
+    }
+
+    /// Persists to the file at `path` by writing a temporary file in
+    /// the same directory and renaming it over `path`, so the jar is
+    /// never left half-written if the process dies while saving. A
+    /// missing file loads as an empty jar.
+    pub fn atomic(path: impl Into<PathBuf>) -> Self {
+        Self {
+            target: Target::Atomic(Mutex::new(path.into())),
+            format: None,
+            lock_file: None,
+        }
+    }
+
+    /// Persists to the file at `path`, which is not created until the
+    /// first save that has a persistent cookie to write, so that a
+    /// program that never receives one leaves no empty jar behind. A
+    /// missing file loads as an empty jar.
+    pub fn lazy(path: impl Into<PathBuf>) -> Self {
+        Self {
+            target: Target::Lazy(Mutex::new(path.into())),
+            format: None,
+            lock_file: None,
+        }
+    }
+
+    /// Persists to the file at `path` by appending one ndjson line for
+    /// each cookie that was set, changed or removed since the last
+    /// save, so a save writes only what changed rather than the whole
+    /// jar. Loading replays the lines in order. Once superseded lines
+    /// outnumber the cookies in the jar, and at least 64 of them have
+    /// built up, the next save compacts the file by atomically
+    /// replacing it with one line per cookie, as does
+    /// [`CookieMiddleware::compact`](crate::CookieMiddleware::compact).
+    ///
+    /// A line left partly written by a crash is skipped on load, and
+    /// the file is compacted by the next save. Files written by the
+    /// other constructors load as they are, but files with removal
+    /// lines can only be read by append-only storage. A missing file
+    /// loads as an empty jar.
+    pub fn append_only(path: impl Into<PathBuf>) -> Self {
+        Self {
+            target: Target::AppendOnly(Mutex::new(AppendLog::new(path.into()))),
+            format: None,
+            lock_file: None,
+        }
+    }
+
+    /// Persists atomically to the file at `path`, as with
+    /// [`atomic`](Self::atomic), in `format`, as with
+    /// [`with_format`](Self::with_format)
+    pub fn atomic_with_format(
+        path: impl Into<PathBuf>,
+        format: impl CookieFormat + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            format: Some(Box::new(format)),
+            ..Self::atomic(path)
+        }
+    }
+
+    /// Holds an advisory lock (`flock` on unix, `LockFileEx` on
+    /// windows) on the file at `lock_file` while loading and saving,
+    /// shared while loading and exclusive while saving, so that
+    /// processes sharing a jar never read it half-written or write it
+    /// at the same time. The lock file is created if needed. Every
+    /// process must use the same lock file, and the last process to
+    /// save wins when two processes change the jar.
+    pub fn with_lock_file(mut self, lock_file: impl Into<PathBuf>) -> Self {
+        self.lock_file = Some(lock_file.into());
+        self
+    }
+
+    /// Takes the advisory lock, if any, which is held until the
+    /// returned file is dropped
+    async fn lock(&self, exclusive: bool) -> io::Result<Option<std::fs::File>> {
+        let lock_file = match &self.lock_file {
+            Some(lock_file) => lock_file.clone(),
+            None => return Ok(None),
+        };
+
+        runtime::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(lock_file)?;
+            if exclusive {
+                file.lock_exclusive()?;
+            } else {
+                file.lock_shared()?;
+            }
+            Ok(Some(file))
+        })
+        .await
+    }
+
+    async fn read(&self) -> io::Result<Vec<u8>> {
+        let _lock = self.lock(false).await?;
+        match &self.target {
+            Target::Io(io) => io.lock().await.read().await,
+            Target::Atomic(path) | Target::Lazy(path) => read_existing(&path.lock().await).await,
+            Target::AppendOnly(log) => read_existing(log.lock().await.path()).await,
+        }
+    }
+}
+
+impl Debug for FileStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("FileStorage");
+        match &self.target {
+            Target::Io(io) => debug.field("io", io),
+            Target::Atomic(path) => debug.field("atomic", path),
+            Target::Lazy(path) => debug.field("lazy", path),
+            Target::AppendOnly(log) => debug.field("append_only", log),
+        };
+        debug
+            .field("format", &self.format.is_some())
+            .field("lock_file", &self.lock_file)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl CookieStorage for FileStorage {
+    async fn load(&self) -> io::Result<Vec<Cookie<'static>>> {
+        if let Target::AppendOnly(log) = &self.target {
+            let _lock = self.lock(false).await?;
+            return log.lock().await.load().await;
+        }
+
+        if let Some(format) = &self.format {
+            let input = String::from_utf8(self.read().await?).map_err(invalid_data)?;
+            return format.parse(&input);
+        }
+
+        let bytes = self.read().await.unwrap_or_default();
+        Ok(CookieStore::load_json(&bytes[..])
+            .map(|cookie_store| cookie_store.iter_unexpired().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn persist(&self, cookies: &[Cookie<'static>]) -> io::Result<()> {
+        if let Target::AppendOnly(log) = &self.target {
+            let _lock = self.lock(true).await?;
+            return log.lock().await.persist(cookies, false).await;
+        }
+
+        let bytes = match &self.format {
+            Some(format) => format
+                .serialize(&cookies.iter().collect::<Vec<_>>())
+                .map_err(|e| SerializeError::wrap(None, e))?
+                .into_bytes(),
+            None => {
+                let mut bytes = Vec::new();
+                for cookie in cookies {
+                    serde_json::to_writer(&mut bytes, cookie)
+                        .map_err(|e| SerializeError::wrap(Some(cookie), e))?;
+                    bytes.push(b'\n');
+                }
+                bytes
+            }
+        };
+
+        let _lock = self.lock(true).await?;
+        match &self.target {
+            Target::Io(io) => io.lock().await.replace(&bytes).await,
+
+            Target::Atomic(path) => {
+                let path = path.lock().await;
+                let target = path.clone();
+                runtime::spawn_blocking(move || replace_atomically(&target, &bytes)).await
+            }
+
+            Target::Lazy(path) => {
+                let path = path.lock().await;
+                if cookies.is_empty() && !path.exists() {
+                    return Ok(());
+                }
+
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .truncate(true)
+                    .open(&*path)
+                    .await?;
+                file.write_all(&bytes).await?;
+                file.sync_all().await
+            }
+
+            Target::AppendOnly(_) => unreachable!("append-only jars are persisted above"),
+        }
+    }
+
+    async fn compact(&self, cookies: &[Cookie<'static>]) -> io::Result<()> {
+        match &self.target {
+            Target::AppendOnly(log) => {
+                let _lock = self.lock(true).await?;
+                log.lock().await.persist(cookies, true).await
+            }
+            _ => self.persist(cookies).await,
+        }
+    }
+
+    async fn modified(&self) -> io::Result<Option<SystemTime>> {
+        let metadata = match &self.target {
+            Target::Io(io) => return io.lock().await.modified().await,
+            Target::Atomic(path) | Target::Lazy(path) => {
+                existing_metadata(&path.lock().await).await?
+            }
+            Target::AppendOnly(log) => existing_metadata(log.lock().await.path()).await?,
+        };
+        match metadata {
+            Some(metadata) => Ok(Some(metadata.modified()?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn size(&self) -> io::Result<Option<u64>> {
+        let metadata = match &self.target {
+            Target::Io(io) => return io.lock().await.size().await,
+            Target::Atomic(path) | Target::Lazy(path) => {
+                existing_metadata(&path.lock().await).await?
+            }
+            Target::AppendOnly(log) => existing_metadata(log.lock().await.path()).await?,
+        };
+        match metadata {
+            Some(metadata) => Ok(Some(metadata.len())),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The contents of the file at `path`, which are empty if it doesn't
+/// exist yet
+async fn read_existing(path: &Path) -> io::Result<Vec<u8>> {
+    match fs::read(path).await {
+        Ok(contents) => Ok(contents),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+/// The metadata of the file at `path`, if it exists yet
+async fn existing_metadata(path: &Path) -> io::Result<Option<std::fs::Metadata>> {
+    match fs::metadata(path).await {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) fn replace_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut temp = NamedTempFile::new_in(dir)?;
+    temp.write_all(bytes)?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Loads the cookies persisted in `storage`, reporting the outcome to
+/// any [`on_load`](crate::CookieMiddleware::on_load) callbacks
+pub(crate) async fn load(
+    storage: &dyn CookieStorage,
+    listeners: &Listeners,
+) -> io::Result<Vec<Cookie<'static>>> {
+    let started = Instant::now();
+    let result = storage.load().await;
+    if listeners.observes_loads() {
+        let summary =
+            PersistenceSummary::new(storage, result.as_ref().map(Vec::len), started).await;
+        listeners.loaded(&summary);
+    }
+    result
+}
+
+/// Adds cookies loaded from storage to `cookie_store`, skipping expired
+/// cookies and any cookie it already holds
+pub(crate) fn insert_loaded(
+    cookie_store: &mut impl CookieJarBackend,
+    cookies: Vec<Cookie<'static>>,
+) -> io::Result<()> {
+    for cookie in cookies {
+        let domain = cookie.domain.as_cow().unwrap_or_default().into_owned();
+        if cookie.is_expired() || cookie_store.contains(&domain, &cookie.path, cookie.name()) {
+            continue;
+        }
+        let url = request_url(&cookie)?;
+        cookie_store
+            .insert(cookie, &url)
+            .map_err(io::Error::other)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! Which url a request's cookies belong to
+use std::{collections::HashMap, fmt, sync::Arc};
+use surf::{
+    http::headers::{HeaderName, COOKIE, HOST},
+    Request, Url,
+};
+
+type Resolver = Arc<dyn Fn(&Request) -> Url + Send + Sync + 'static>;
+
+#[derive(Clone, Default)]
+pub(crate) struct TargetUrl(Option<Resolver>);
+
+impl TargetUrl {
+    pub(crate) fn new(resolver: impl Fn(&Request) -> Url + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(resolver)))
+    }
+
+    pub(crate) fn resolve(&self, req: &Request) -> Url {
+        match &self.0 {
+            Some(resolver) => resolver(req),
+            None => from_host_header(req),
+        }
+    }
+}
+
+impl fmt::Debug for TargetUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.0 {
+            Some(_) => "TargetUrl(custom)",
+            None => "TargetUrl(default)",
+        })
+    }
+}
+
+/// The request url, with its host replaced by the Host header if
+/// there is one. A request addressed to a proxy carries the host it is
+/// meant for in that header.
+fn from_host_header(req: &Request) -> Url {
+    let mut url = req.url().clone();
+
+    let host = req
+        .header(HOST)
+        .and_then(|host| Url::parse(&format!("{}://{}", url.scheme(), host.last())).ok());
+
+    if let Some(host) = host {
+        if url.set_host(host.host_str()).is_ok() {
+            let _ = url.set_port(host.port());
+        }
+    }
+
+    url
+}
+
+/// Groups of hosts that share cookies, mapping each host to the first
+/// host of its group
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HostAliases(HashMap<String, String>);
+
+impl HostAliases {
+    pub(crate) fn add_group(&mut self, hosts: impl IntoIterator<Item = String>) {
+        let mut hosts = hosts.into_iter().map(|host| host.to_ascii_lowercase());
+        if let Some(canonical) = hosts.next() {
+            for host in hosts {
+                self.0.insert(host, canonical.clone());
+            }
+        }
+    }
+
+    /// The host that cookies for `host` are kept under
+    pub(crate) fn canonical(&self, host: &str) -> Option<&str> {
+        self.0.get(&host.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// Replaces an aliased host in `url` with its canonical host
+    pub(crate) fn apply(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+        if let Some(canonical) = url.host_str().and_then(|host| self.canonical(host)) {
+            let canonical = canonical.to_string();
+            let _ = url.set_host(Some(&canonical));
+        }
+        url
+    }
+}
+
+/// Host patterns that are treated as secure contexts even over plain
+/// http, such as hosts behind a TLS-terminating proxy
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SecureHosts(Vec<String>);
+
+impl SecureHosts {
+    /// Adds comma- or whitespace-separated patterns
+    pub(crate) fn parse(&mut self, patterns: &str) {
+        self.extend(
+            patterns
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|pattern| !pattern.is_empty())
+                .map(String::from),
+        );
+    }
+
+    pub(crate) fn extend(&mut self, patterns: impl IntoIterator<Item = String>) {
+        self.0.extend(
+            patterns
+                .into_iter()
+                .map(|pattern| normalize_pattern(&pattern)),
+        );
+    }
+
+    fn matches(&self, host: &str) -> bool {
+        self.0.iter().any(|pattern| host_matches(pattern, host))
+    }
+
+    /// Treats a matching http url as https
+    pub(crate) fn apply(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+        if url.scheme() == "http" && url.host_str().is_some_and(|host| self.matches(host)) {
+            let _ = url.set_scheme("https");
+        }
+        url
+    }
+}
+
+/// Header names that cookies are sent in for matching hosts, in place
+/// of Cookie
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CookieHeaderNames(Vec<(String, HeaderName)>);
+
+impl CookieHeaderNames {
+    pub(crate) fn push(&mut self, pattern: &str, header_name: HeaderName) {
+        self.0.push((normalize_pattern(pattern), header_name));
+    }
+
+    /// The header to send cookies for `url` in. The first matching
+    /// pattern wins.
+    pub(crate) fn for_url(&self, url: &Url) -> HeaderName {
+        url.host_str()
+            .and_then(|host| {
+                self.0
+                    .iter()
+                    .find(|(pattern, _)| host_matches(pattern, host))
+            })
+            .map_or(COOKIE, |(_, header_name)| header_name.clone())
+    }
+}
+
+/// Lowercases a host pattern, reading `*.example.com` as
+/// `.example.com`
+pub(crate) fn normalize_pattern(pattern: &str) -> String {
+    pattern.trim_start_matches('*').to_ascii_lowercase()
+}
+
+/// Whether `host` is `pattern`, or a subdomain of it if the pattern
+/// starts with a dot
+pub(crate) fn host_matches(pattern: &str, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    match pattern.strip_prefix('.') {
+        // .internal matches example.internal but not internal
+        Some(suffix) => host.len() > suffix.len() && host.ends_with(pattern),
+        None => host == pattern,
+    }
+}
@@ -0,0 +1,120 @@
+//! Checks applied to Set-Cookie headers before they reach the cookie
+//! store
+use crate::events::RejectionReason;
+use cookie_store::{Cookie, CookieDomain};
+use publicsuffix::List;
+use std::{io, net::IpAddr};
+use surf::{http::url::Host, Url};
+
+pub(crate) const ATTRIBUTE_NAMES: &[&str] = &[
+    "expires", "max-age", "domain", "path", "secure", "httponly", "samesite",
+];
+
+/// Checks the name-value pair of a Set-Cookie header
+pub(crate) fn validate_header(header: &str) -> Result<(), RejectionReason> {
+    let first = header.split(';').next().unwrap_or_default();
+
+    match first.split_once('=') {
+        // `=value` has a pair, just not a name
+        Some((name, _)) if name.trim().is_empty() => Err(RejectionReason::EmptyName),
+        Some(_) => Ok(()),
+        None if is_attribute_only(header, first.trim()) => Err(RejectionReason::AttributeOnly),
+        None if first.trim().is_empty() => Err(RejectionReason::EmptyName),
+        None => Ok(()),
+    }
+}
+
+/// Whether a Set-Cookie header whose first pair `first` has no `=` only
+/// has attributes, like `HttpOnly; Path=/`. The cookie crate would
+/// otherwise store this as a cookie named `HttpOnly`. A first pair
+/// with a value is a cookie even when it is named like an attribute,
+/// as in `Secure=1`.
+fn is_attribute_only(header: &str, first: &str) -> bool {
+    if first.is_empty() {
+        header.contains(';')
+    } else {
+        ATTRIBUTE_NAMES
+            .iter()
+            .any(|attribute| attribute.eq_ignore_ascii_case(first))
+    }
+}
+
+/// Checks the RFC 6265bis name prefixes: a `__Secure-` cookie must be
+/// Secure and set from a secure context, and a `__Host-` cookie must
+/// also have no Domain attribute and a path of `/`
+pub(crate) fn validate_prefix(cookie: &Cookie<'_>, url: &Url) -> Result<(), RejectionReason> {
+    let name = cookie.name().as_bytes();
+    let has_prefix = |prefix: &str| {
+        name.len() >= prefix.len() && name[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+    };
+    let secure = cookie.secure() == Some(true) && is_secure_context(url);
+
+    let valid = if has_prefix("__Host-") {
+        secure
+            && matches!(
+                cookie.domain,
+                CookieDomain::HostOnly(_) | CookieDomain::NotPresent
+            )
+            && String::from(&cookie.path) == "/"
+    } else if has_prefix("__Secure-") {
+        secure
+    } else {
+        true
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(RejectionReason::InvalidPrefix)
+    }
+}
+
+/// Whether a cookie value is empty or whitespace, ignoring quotes
+pub(crate) fn is_blank(value: &str) -> bool {
+    value.trim_matches('"').trim().is_empty()
+}
+
+/// Normalizes `domain` and checks that a cookie may be scoped to it: it
+/// must not be an ip address or a public suffix such as `com` or
+/// `co.uk`. Without a public suffix list, only single-label domains
+/// are refused.
+pub(crate) fn registrable_domain(domain: &str, list: Option<&List>) -> io::Result<String> {
+    let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+    let invalid = |reason| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("cannot set a cookie for {:?}: {}", domain, reason),
+        )
+    };
+
+    if domain.parse::<IpAddr>().is_ok() {
+        return Err(invalid("ip addresses have no subdomains"));
+    }
+
+    let is_public_suffix = match list {
+        Some(list) => list
+            .parse_domain(&domain)
+            .map_err(|_| invalid("not a valid domain"))?
+            .root()
+            .is_none(),
+        None => !domain.contains('.'),
+    };
+
+    if is_public_suffix {
+        Err(invalid("public suffix"))
+    } else {
+        Ok(domain)
+    }
+}
+
+/// Whether `url` is a secure context that Secure cookies may be set
+/// from and sent to: https, or a loopback host, as in the cookie store
+pub(crate) fn is_secure_context(url: &Url) -> bool {
+    url.scheme() == "https"
+        || match url.host() {
+            Some(Host::Domain(domain)) => domain == "localhost",
+            Some(Host::Ipv4(ip)) => ip.is_loopback(),
+            Some(Host::Ipv6(ip)) => ip.is_loopback(),
+            None => false,
+        }
+}
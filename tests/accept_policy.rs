@@ -0,0 +1,90 @@
+use async_std::prelude::*;
+use http::headers::{LOCATION, SET_COOKIE};
+use surf::{http, Client};
+use surf_cookie_middleware::{
+    events::{CookieEvent, RejectionReason},
+    CookieAcceptPolicy, CookieMiddleware,
+};
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/start").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(302);
+        res.insert_header(LOCATION, "http://tracker.net/bounce");
+        res.insert_header(SET_COOKIE, "first=1");
+        Ok(res)
+    });
+    server.at("/bounce").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(302);
+        res.insert_header(LOCATION, "http://www.example.com/end");
+        res.insert_header(SET_COOKIE, "tracking=1");
+        Ok(res)
+    });
+    server.at("/end").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.insert_header(SET_COOKIE, "last=1");
+        Ok(res)
+    });
+    server
+}
+
+async fn stored_names(policy: CookieAcceptPolicy) -> surf::Result<Vec<String>> {
+    let middleware = CookieMiddleware::new()
+        .with_redirects(5)
+        .with_accept_policy(policy);
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    client.get("http://example.com/start").await?;
+
+    let mut names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+#[async_std::test]
+async fn accepts_all_by_default() -> surf::Result<()> {
+    assert_eq!(
+        stored_names(CookieAcceptPolicy::default()).await?,
+        ["first", "last", "tracking"]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn accepts_none() -> surf::Result<()> {
+    assert!(stored_names(CookieAcceptPolicy::AcceptNone)
+        .await?
+        .is_empty());
+    Ok(())
+}
+
+#[async_std::test]
+async fn accepts_first_party_only() -> surf::Result<()> {
+    assert_eq!(
+        stored_names(CookieAcceptPolicy::FirstPartyOnly).await?,
+        ["first", "last"]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn reports_refused_cookies() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_accept_policy(CookieAcceptPolicy::AcceptNone);
+    let mut events = middleware.events();
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    client.get("http://www.example.com/end").await?;
+
+    match events.next().await {
+        Some(CookieEvent::Rejected(rejection)) => {
+            assert_eq!(rejection.reason, RejectionReason::NotAccepted);
+        }
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+    assert_eq!(middleware.stats().rejected, 1);
+    Ok(())
+}
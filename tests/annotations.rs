@@ -0,0 +1,90 @@
+use std::io;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn annotations_are_queryable_and_follow_the_cookie() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = client(&middleware);
+    client
+        .get("http://a.example/set?session=1;Path=/&theme=dark;Path=/")
+        .await?;
+    client.get("http://b.example/set?session=2;Path=/").await?;
+
+    middleware
+        .annotate("a.example", "/", "session", "account=alice")
+        .await?;
+    middleware
+        .annotate("a.example", "/", "session", "source=login")
+        .await?;
+    middleware
+        .annotate("b.example", "/", "session", "account=alice")
+        .await?;
+
+    assert_eq!(
+        middleware.annotations("a.example", "/", "session"),
+        ["account=alice", "source=login"]
+    );
+    let alice = middleware.find_annotated("account=alice").await;
+    assert_eq!(
+        alice
+            .iter()
+            .map(|cookie| cookie.value())
+            .collect::<Vec<_>>(),
+        ["1", "2"]
+    );
+
+    // kept when the server rotates the value
+    client.get("http://a.example/set?session=3;Path=/").await?;
+    assert_eq!(
+        middleware.find_annotated("source=login").await[0].value(),
+        "3"
+    );
+
+    // dropped with the cookie
+    client
+        .get("http://b.example/set?session=;Path=/;Max-Age=0")
+        .await?;
+    assert_eq!(middleware.find_annotated("account=alice").await.len(), 1);
+
+    assert!(
+        middleware
+            .remove_annotation("a.example", "/", "session", "source=login")
+            .await?
+    );
+    assert!(middleware.find_annotated("source=login").await.is_empty());
+
+    let missing = middleware
+        .annotate("a.example", "/", "missing", "account=alice")
+        .await
+        .unwrap_err();
+    assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn annotations_persist_next_to_the_jar() -> surf::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cookies.ndjson");
+
+    let middleware = CookieMiddleware::from_path(&path).await?;
+    let client = client(&middleware);
+    client
+        .get("http://example.com/set?session=1;Path=/;Max-Age=3600")
+        .await?;
+    middleware
+        .annotate("example.com", "/", "session", "account=alice")
+        .await?;
+    assert!(dir.path().join("cookies.ndjson.annotations.json").exists());
+
+    let reloaded = CookieMiddleware::from_path(&path).await?;
+    assert_eq!(
+        reloaded.annotations("example.com", "/", "session"),
+        ["account=alice"]
+    );
+    assert_eq!(reloaded.find_annotated("account=alice").await.len(), 1);
+    Ok(())
+}
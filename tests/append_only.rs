@@ -0,0 +1,109 @@
+use std::{fs, path::Path};
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::TempDir;
+
+mod common;
+use common::client;
+
+async fn append_only(path: &Path) -> std::io::Result<CookieMiddleware> {
+    CookieMiddleware::builder()
+        .path(path)
+        .append_only(true)
+        .build()
+        .await
+}
+
+fn lines(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[async_std::test]
+async fn changes_are_appended_and_replayed() -> surf::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("cookies.ndjson");
+    let middleware = append_only(&path).await?;
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/?a=1;Max-Age=3600&b=2;Max-Age=3600")
+        .await?;
+    assert_eq!(lines(&path).len(), 2);
+
+    client.get("http://example.com/?a=3;Max-Age=3600").await?;
+    client.get("http://example.com/?b=;Max-Age=0").await?;
+    let log = lines(&path);
+    assert_eq!(log.len(), 4);
+    assert!(log[2].contains("a=3"));
+    assert!(log[3].contains("\"removed\""));
+
+    // a response that changes nothing appends nothing
+    client.get("http://example.com/").await?;
+    assert_eq!(lines(&path).len(), 4);
+
+    let reloaded = append_only(&path).await?;
+    let cookies = self::client(&reloaded)
+        .get("http://example.com/")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "a=3");
+
+    middleware.compact().await?;
+    let log = lines(&path);
+    assert_eq!(log.len(), 1);
+    assert!(log[0].contains("a=3"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn superseded_lines_are_compacted() -> surf::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("cookies.ndjson");
+    let middleware = append_only(&path).await?;
+    let client = client(&middleware);
+
+    for value in 0..100 {
+        client
+            .get(format!("http://example.com/?a={};Max-Age=3600", value))
+            .await?;
+        assert!(lines(&path).len() <= 65);
+    }
+
+    let reloaded = append_only(&path).await?;
+    let cookies = self::client(&reloaded)
+        .get("http://example.com/")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "a=99");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn partial_lines_are_skipped_and_compacted() -> surf::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("cookies.ndjson");
+    client(&append_only(&path).await?)
+        .get("http://example.com/?a=1;Max-Age=3600")
+        .await?;
+
+    let mut contents = fs::read_to_string(&path)?;
+    contents.push_str("{\"raw_cookie\":\"b=2");
+    fs::write(&path, contents)?;
+
+    let middleware = append_only(&path).await?;
+    let client = client(&middleware);
+    let cookies = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(cookies, "a=1");
+
+    client.get("http://example.com/?c=3;Max-Age=3600").await?;
+    let log = lines(&path);
+    assert_eq!(log.len(), 2);
+    assert!(log.iter().all(|line| line.ends_with('}')));
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use async_std::fs;
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::build_app;
+
+async fn build_client(path: &std::path::Path) -> surf::Result<Client> {
+    let middleware = CookieMiddleware::builder()
+        .path(path)
+        .atomic_writes(true)
+        .build()
+        .await?;
+    Ok(Client::with_http_client(build_app()).with(middleware))
+}
+
+#[async_std::test]
+async fn replaces_the_jar_by_renaming() -> surf::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cookies.ndjson");
+
+    let client = build_client(&path).await?;
+    assert!(!path.exists());
+
+    client.get("http://example.com/?a=1;Max-Age=3600").await?;
+    client.get("http://example.com/?b=2;Max-Age=3600").await?;
+    assert_eq!(fs::read_to_string(&path).await?.lines().count(), 2);
+    // no temporary files are left behind, only the jar and the use
+    // times persisted next to it
+    let mut files = std::fs::read_dir(dir.path())?
+        .map(|entry| Ok(entry?.file_name().into_string().unwrap()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    files.sort();
+    assert_eq!(files, ["cookies.ndjson", "cookies.ndjson.annotations.json"]);
+
+    let client = build_client(&path).await?;
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "a=1; b=2"
+    );
+
+    Ok(())
+}
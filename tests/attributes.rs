@@ -0,0 +1,97 @@
+use http::headers::SET_COOKIE;
+use serde_json::Value;
+use surf::{http, Client};
+use surf_cookie_middleware::{formats::Selenium, CookieMiddleware};
+use tide::Request;
+
+const CASINGS: &[fn(&str) -> String] = &[
+    |attribute| attribute.to_lowercase(),
+    |attribute| attribute.to_uppercase(),
+    |attribute| attribute.to_string(),
+    |attribute| {
+        attribute
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                if i % 2 == 0 {
+                    c.to_ascii_lowercase()
+                } else {
+                    c.to_ascii_uppercase()
+                }
+            })
+            .collect()
+    },
+];
+
+/// Sends each Set-Cookie header in its own response and returns the
+/// cookie as exported by the Selenium format
+async fn store(header: String) -> surf::Result<Value> {
+    let mut server = tide::new();
+    server.at("*").get(move |_: Request<_>| {
+        let header = header.clone();
+        async move {
+            let mut res = tide::Response::new(200);
+            res.insert_header(SET_COOKIE, header);
+            Ok(res)
+        }
+    });
+
+    let middleware = CookieMiddleware::new();
+    let client = Client::with_http_client(server).with(middleware.clone());
+    client.get("https://www.example.com/a/b").await?;
+
+    let mut exported: Value = serde_json::from_str(&middleware.export(&Selenium).await?)?;
+    Ok(exported[0].take())
+}
+
+#[async_std::test]
+async fn attribute_names_are_case_insensitive() -> surf::Result<()> {
+    for casing in CASINGS {
+        let attributes = [
+            ("Secure", "secure", Value::from(true)),
+            ("HttpOnly", "httpOnly", Value::from(true)),
+            ("Max-Age=100", "expiry", Value::Null),
+            (
+                "Expires=Wed, 21 Oct 2037 07:28:00 GMT",
+                "expiry",
+                2139722880.into(),
+            ),
+            ("Domain=example.com", "domain", ".example.com".into()),
+            ("Path=/a", "path", "/a".into()),
+            ("SameSite=Strict", "sameSite", "Strict".into()),
+        ];
+
+        for (attribute, field, expected) in attributes {
+            let attribute = match attribute.split_once('=') {
+                Some((name, value)) => format!("{}={}", casing(name), value),
+                None => casing(attribute),
+            };
+
+            let cookie = store(format!("name=value; {}", attribute)).await?;
+            let actual = &cookie[field];
+            if expected.is_null() {
+                assert!(!actual.is_null(), "{} was ignored", attribute);
+            } else {
+                assert_eq!(actual, &expected, "{} was ignored", attribute);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn attribute_values_are_case_insensitive() -> surf::Result<()> {
+    for (same_site, expected) in [("strict", "Strict"), ("LAX", "Lax"), ("nOnE", "None")] {
+        let cookie = store(format!("name=value; SameSite={}; Secure", same_site)).await?;
+        assert_eq!(cookie["sameSite"], expected);
+    }
+
+    let cookie = store(String::from(
+        "name=value; expires=wed, 21-OCT-2037 07:28:00 gmt",
+    ))
+    .await?;
+    assert_eq!(cookie["expiry"], 2139722880);
+
+    Ok(())
+}
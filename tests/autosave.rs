@@ -0,0 +1,79 @@
+use async_std::{fs, task};
+use std::time::Duration;
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::{build_app, client};
+
+#[async_std::test]
+async fn saves_changes_in_the_background() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_manual_persistence(true)
+        .with_autosave_interval(Some(Duration::from_millis(50)));
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+
+    client
+        .get("http://example.com/?saved=1;Max-Age=3600&session=1")
+        .await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?, "");
+
+    task::sleep(Duration::from_millis(200)).await;
+    let persisted = fs::read_to_string(jar.path()).await?;
+    assert_eq!(persisted.lines().count(), 1);
+    assert!(persisted.contains("saved=1"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn stops_when_the_middleware_is_dropped() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_manual_persistence(true)
+        .with_autosave_interval(Some(Duration::from_millis(50)));
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+
+    client
+        .get("http://example.com/?saved=1;Max-Age=3600")
+        .await?;
+    drop(client);
+
+    task::sleep(Duration::from_millis(200)).await;
+    assert_eq!(fs::read_to_string(jar.path()).await?, "");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn saves_with_options_configured_afterwards() -> surf::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cookies.ndjson");
+    std::fs::write(&path, "")?;
+    let file = std::fs::File::open(&path)?;
+    let middleware = CookieMiddleware::from_file(file)
+        .await?
+        .with_autosave_interval(Some(Duration::from_millis(50)))
+        .with_manual_persistence(true)
+        .with_persistence_quarantine(Some(10));
+
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/?saved=1;Max-Age=3600")
+        .await?;
+    assert_eq!(middleware.stats().unsaved, 0);
+
+    // the jar can't be written, and the failed background save is held
+    // back by the quarantine configured after the interval
+    task::sleep(Duration::from_millis(200)).await;
+    assert_ne!(middleware.stats().unsaved, 0);
+
+    Ok(())
+}
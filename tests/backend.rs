@@ -0,0 +1,77 @@
+use surf::Url;
+use surf_cookie_middleware::{
+    cookie_store::{Cookie, CookieError},
+    CookieJarBackend, CookieMiddleware,
+};
+
+mod common;
+use common::client;
+
+/// A minimal backend that keeps cookies in a list
+#[derive(Debug, Default)]
+struct ListJar(Vec<Cookie<'static>>);
+
+impl ListJar {
+    fn position(&self, domain: &str, path: &str, name: &str) -> Option<usize> {
+        self.0.iter().position(|cookie| {
+            String::from(&cookie.domain) == domain
+                && String::from(&cookie.path) == path
+                && cookie.name() == name
+        })
+    }
+}
+
+impl CookieJarBackend for ListJar {
+    fn matches(&self, url: &Url) -> Vec<&Cookie<'static>> {
+        self.0
+            .iter()
+            .filter(|cookie| !cookie.is_expired() && cookie.matches(url))
+            .collect()
+    }
+
+    fn insert(&mut self, cookie: Cookie<'static>, _url: &Url) -> Result<(), CookieError> {
+        let domain = String::from(&cookie.domain);
+        let path = String::from(&cookie.path);
+        self.remove(&domain, &path, cookie.name());
+        if !cookie.is_expired() {
+            self.0.push(cookie);
+        }
+        Ok(())
+    }
+
+    fn get(&self, domain: &str, path: &str, name: &str) -> Option<&Cookie<'_>> {
+        self.position(domain, path, name)
+            .map(|index| &self.0[index])
+            .filter(|cookie| !cookie.is_expired())
+    }
+
+    fn remove(&mut self, domain: &str, path: &str, name: &str) -> Option<Cookie<'static>> {
+        self.position(domain, path, name)
+            .map(|index| self.0.remove(index))
+    }
+
+    fn iter_unexpired(&self) -> Box<dyn Iterator<Item = &Cookie<'static>> + '_> {
+        Box::new(self.0.iter().filter(|cookie| !cookie.is_expired()))
+    }
+}
+
+#[async_std::test]
+async fn custom_backend() -> surf::Result<()> {
+    let middleware = CookieMiddleware::with_cookie_store(ListJar::default());
+    let client = client(&middleware);
+
+    client.get("http://example.com/set?a=1;Path=/").await?;
+    client.get("http://example.com/set?b=2;Path=/b").await?;
+    assert_eq!(
+        client.get("http://example.com/b/c").recv_string().await?,
+        "b=2; a=1"
+    );
+
+    client
+        .get("http://example.com/set?a=;Path=/;Max-Age=0")
+        .await?;
+    assert_eq!(client.get("http://example.com/x").recv_string().await?, "");
+
+    assert_eq!(middleware.find("b").await.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use surf_cookie_middleware::{CookieMiddleware, FileStorage};
+use tempfile::TempDir;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn builder_persists_with_std_fs() -> surf::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("cookies.ndjson");
+    let middleware = CookieMiddleware::builder()
+        .path(&path)
+        .blocking_io(true)
+        .build()
+        .await?;
+    assert!(path.exists());
+
+    let client = client(&middleware);
+    client
+        .get("http://example.com/?a=1;Max-Age=3600&b=2;Max-Age=3600")
+        .await?;
+    assert_eq!(std::fs::read_to_string(&path)?.lines().count(), 2);
+
+    client.get("http://example.com/?a=;Max-Age=0").await?;
+    let persisted = std::fs::read_to_string(&path)?;
+    assert_eq!(persisted.lines().count(), 1);
+    assert!(persisted.contains("b=2"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn blocking_storage_reloads() -> surf::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("cookies.ndjson");
+    let middleware = CookieMiddleware::builder()
+        .storage(FileStorage::blocking(&path)?)
+        .build()
+        .await?;
+    client(&middleware)
+        .get("http://example.com/?a=1;Max-Age=3600")
+        .await?;
+
+    let reloaded = CookieMiddleware::builder()
+        .storage(FileStorage::blocking(&path)?)
+        .build()
+        .await?;
+    let cookies = client(&reloaded)
+        .get("http://example.com/")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "a=1");
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use async_std::fs;
+use surf::{Client, Url};
+use surf_cookie_middleware::{CookieMiddleware, CookieStore};
+use tempfile::NamedTempFile;
+
+mod common;
+use common::build_app;
+
+fn build_client(middleware: CookieMiddleware) -> Client {
+    Client::with_http_client(build_app()).with(middleware)
+}
+
+#[async_std::test]
+async fn existing_store_with_file_persistence() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let client = build_client(CookieMiddleware::from_path(jar.path()).await?);
+    client
+        .get("http://example.com/?region=file;Max-Age=3600&saved=yes;Max-Age=3600")
+        .await?;
+
+    let url = Url::parse("http://example.com/")?;
+    let mut cookie_store = CookieStore::default();
+    cookie_store.parse("region=store; Max-Age=3600", &url)?;
+
+    let middleware = CookieMiddleware::builder()
+        .cookie_store(cookie_store)
+        .path(jar.path())
+        .build()
+        .await?;
+    let client = build_client(middleware);
+
+    let mut cookies = client
+        .get("http://example.com/?added=new;Max-Age=3600")
+        .recv_string()
+        .await?
+        .split("; ")
+        .map(String::from)
+        .collect::<Vec<_>>();
+    cookies.sort();
+    assert_eq!(cookies, ["region=store", "saved=yes"]);
+
+    let persisted = fs::read_to_string(jar.path()).await?;
+    assert_eq!(persisted.lines().count(), 3);
+    assert!(persisted.contains("region=store"));
+    assert!(!persisted.contains("region=file"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn builder_without_persistence() -> surf::Result<()> {
+    let client = build_client(CookieMiddleware::builder().build().await?);
+    client.get("http://example.com/?a=1").await?;
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "a=1"
+    );
+    Ok(())
+}
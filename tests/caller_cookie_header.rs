@@ -0,0 +1,76 @@
+use http::headers::COOKIE;
+use surf::{http, Client};
+use surf_cookie_middleware::{CookieMiddleware, ExistingCookieHeader};
+
+mod common;
+use common::build_app;
+
+fn build_client(middleware: CookieMiddleware) -> Client {
+    Client::with_http_client(build_app()).with(middleware)
+}
+
+#[async_std::test]
+async fn caller_cookies_are_merged_with_the_jar() -> surf::Result<()> {
+    let client = build_client(CookieMiddleware::new());
+    client
+        .get("http://example.com/?session=jar&theme=dark")
+        .await?;
+
+    let body = client
+        .get("http://example.com/")
+        .header(COOKIE, "session=caller; debug=1")
+        .recv_string()
+        .await?;
+    assert_eq!(body, "session=caller; debug=1; theme=dark");
+
+    let body = client.get("http://example.com/").recv_string().await?;
+    assert!(body.contains("session=jar"));
+    Ok(())
+}
+
+#[async_std::test]
+async fn caller_cookies_are_sent_with_an_empty_jar() -> surf::Result<()> {
+    let client = build_client(CookieMiddleware::new());
+    let body = client
+        .get("http://example.com/")
+        .header(COOKIE, "debug=1")
+        .recv_string()
+        .await?;
+    assert_eq!(body, "debug=1");
+    Ok(())
+}
+
+#[async_std::test]
+async fn replace() -> surf::Result<()> {
+    let client = build_client(
+        CookieMiddleware::new().with_existing_cookie_header(ExistingCookieHeader::Replace),
+    );
+    client.get("http://example.com/?session=jar").await?;
+
+    let body = client
+        .get("http://example.com/")
+        .header(COOKIE, "session=caller; debug=1")
+        .recv_string()
+        .await?;
+    assert_eq!(body, "session=jar");
+    Ok(())
+}
+
+#[async_std::test]
+async fn skip() -> surf::Result<()> {
+    let client = build_client(
+        CookieMiddleware::new().with_existing_cookie_header(ExistingCookieHeader::Skip),
+    );
+    client.get("http://example.com/?session=jar").await?;
+
+    let body = client
+        .get("http://example.com/")
+        .header(COOKIE, "debug=1")
+        .recv_string()
+        .await?;
+    assert_eq!(body, "debug=1");
+
+    let body = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(body, "session=jar");
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use async_std::fs;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn empties_the_jar_and_its_file() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_scheme_separation(true);
+
+    let client = client(&middleware);
+
+    client
+        .get("https://example.com/?tls=1;Max-Age=3600&session=1")
+        .await?;
+    client
+        .get("http://example.com/?insecure=1;Max-Age=3600")
+        .await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+
+    middleware.clear().await?;
+
+    assert_eq!(client.get("https://example.com/").recv_string().await?, "");
+    assert_eq!(client.get("http://example.com/").recv_string().await?, "");
+    assert_eq!(fs::read_to_string(jar.path()).await?, "");
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use async_std::fs;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn forgets_a_site_and_its_subdomains() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path()).await?;
+
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/?apex=1;Max-Age=3600")
+        .await?;
+    client
+        .get("http://www.example.com/?sub=1;Max-Age=3600&wide=1;Domain=example.com;Max-Age=3600")
+        .await?;
+    client
+        .get("http://notexample.com/?other=1;Max-Age=3600")
+        .await?;
+
+    assert_eq!(middleware.clear_domain("Example.com").await?, 3);
+    assert_eq!(middleware.clear_domain("example.com").await?, 0);
+
+    assert_eq!(
+        client.get("http://www.example.com/").recv_string().await?,
+        ""
+    );
+    assert_eq!(
+        client.get("http://notexample.com/").recv_string().await?,
+        "other=1"
+    );
+
+    let persisted = fs::read_to_string(jar.path()).await?;
+    assert_eq!(persisted.lines().count(), 1);
+    assert!(persisted.contains("other=1"));
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+//! Servers and clients shared by the integration tests. Not every test
+//! uses every helper.
+#![allow(dead_code)]
+use http::headers::{COOKIE, SET_COOKIE};
+use surf::{http, Client};
+use surf_cookie_middleware::{CookieJarBackend, CookieMiddleware};
+use tide::Request;
+
+/// Responds with a Set-Cookie header for each `&`-separated part of
+/// the query
+pub async fn set_cookies(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(query) = req.url().query() {
+        for set_cookie in query.split('&') {
+            res.append_header(SET_COOKIE, set_cookie);
+        }
+    }
+    Ok(res)
+}
+
+/// Sets cookies from the query as [`set_cookies`] does, and responds
+/// with the Cookie header that was sent
+pub async fn echo_cookies(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(query) = req.url().query() {
+        for set_cookie in query.split('&') {
+            res.append_header(SET_COOKIE, set_cookie);
+        }
+    }
+    res.set_body(
+        req.header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default(),
+    );
+    Ok(res)
+}
+
+/// A server that answers every path with [`echo_cookies`]
+pub fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/").get(echo_cookies);
+    server.at("*").get(echo_cookies);
+    server
+}
+
+/// A client for [`build_app`] that uses a clone of `middleware`
+pub fn client<B: CookieJarBackend>(middleware: &CookieMiddleware<B>) -> Client {
+    Client::with_http_client(build_app()).with(middleware.clone())
+}
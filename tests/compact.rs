@@ -0,0 +1,29 @@
+use std::time::Duration;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn compact_drops_cookies_that_expired_since_the_last_write() -> surf::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cookies.ndjson");
+
+    let middleware = CookieMiddleware::from_path(&path).await?;
+    let client = client(&middleware);
+    client
+        .get("http://example.com/set?short=1;Max-Age=1&long=1;Max-Age=3600")
+        .await?;
+    assert_eq!(
+        async_std::fs::read_to_string(&path).await?.lines().count(),
+        2
+    );
+
+    async_std::task::sleep(Duration::from_millis(1100)).await;
+    middleware.compact().await?;
+
+    let jar = async_std::fs::read_to_string(&path).await?;
+    assert_eq!(jar.lines().count(), 1);
+    assert!(jar.contains("long"));
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use surf::{http, Config, Url};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/set").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.insert_header(SET_COOKIE, "session=abc");
+        Ok(res)
+    });
+    server.at("/cookies").get(|req: Request<_>| async move {
+        Ok(req
+            .header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default())
+    });
+    server
+}
+
+#[async_std::test]
+async fn build_client_from_config() -> surf::Result<()> {
+    let config = Config::new()
+        .set_http_client(build_app())
+        .set_base_url(Url::parse("http://example.com/")?);
+    let (client, cookies) = CookieMiddleware::new().build_client(config)?;
+
+    client.get("set").await?;
+    assert_eq!(client.get("cookies").recv_string().await?, "session=abc");
+    assert_eq!(cookies.stats().stored, 1);
+    assert_eq!(cookies.find("session").await.len(), 1);
+    Ok(())
+}
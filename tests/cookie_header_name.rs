@@ -0,0 +1,52 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/set").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.insert_header(SET_COOKIE, "session=abc; Path=/");
+        Ok(res)
+    });
+    server.at("/headers").get(|req: Request<_>| async move {
+        let header = |name| {
+            req.header(name)
+                .map(|value| value.as_str().to_string())
+                .unwrap_or_default()
+        };
+        Ok(format!(
+            "{}|{}",
+            header(COOKIE.as_str()),
+            header("x-cookie")
+        ))
+    });
+    server
+}
+
+#[async_std::test]
+async fn sends_cookies_in_the_configured_header_for_matching_hosts() -> surf::Result<()> {
+    let client = Client::with_http_client(build_app())
+        .with(CookieMiddleware::new().with_cookie_header_name("*.gateway.example", "X-Cookie"));
+
+    client.get("http://api.gateway.example/set").await?;
+    assert_eq!(
+        client
+            .get("http://api.gateway.example/headers")
+            .recv_string()
+            .await?,
+        "|session=abc"
+    );
+
+    client.get("http://example.com/set").await?;
+    assert_eq!(
+        client
+            .get("http://example.com/headers")
+            .recv_string()
+            .await?,
+        "session=abc|"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::build_app;
+
+fn build_client() -> Client {
+    Client::with_http_client(build_app()).with(CookieMiddleware::new())
+}
+
+#[async_std::test]
+async fn equal_paths_are_ordered_by_creation_time() -> surf::Result<()> {
+    let client = build_client();
+    for name in ["m", "z", "a", "q"] {
+        client
+            .get(format!("http://example.com/?{}=1;Path=/", name))
+            .await?;
+    }
+
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "m=1; z=1; a=1; q=1"
+    );
+
+    // a replaced cookie keeps its place
+    client.get("http://example.com/?z=2;Path=/").await?;
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "m=1; z=2; a=1; q=1"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn longer_paths_come_first() -> surf::Result<()> {
+    let client = build_client();
+    client.get("http://example.com/?a=1;Path=/").await?;
+    client.get("http://example.com/?b=1;Path=/account").await?;
+    assert_eq!(
+        client
+            .get("http://example.com/account/settings")
+            .recv_string()
+            .await?,
+        "b=1; a=1"
+    );
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+use surf::Url;
+use surf_cookie_middleware::{
+    cookie_store::Cookie,
+    events::{CookieEvent, RejectionReason},
+    CookieMiddleware, CookiePolicy,
+};
+
+mod common;
+use common::client;
+
+#[derive(Debug)]
+struct SessionsOnly;
+
+impl CookiePolicy for SessionsOnly {
+    fn allow_store(&self, cookie: &Cookie<'_>, _url: &Url) -> bool {
+        cookie.name().starts_with("session")
+    }
+}
+
+#[derive(Debug)]
+struct NotToApi;
+
+impl CookiePolicy for NotToApi {
+    fn allow_send(&self, cookie: &Cookie<'_>, url: &Url) -> bool {
+        cookie.name() != "tracking" || !url.path().starts_with("/api")
+    }
+}
+
+#[async_std::test]
+async fn allow_store() -> surf::Result<()> {
+    let rejections = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new()
+        .with_policy(SessionsOnly)
+        .with_event_listener({
+            let rejections = rejections.clone();
+            move |event| {
+                if let CookieEvent::Rejected(rejection) = event {
+                    rejections
+                        .lock()
+                        .unwrap()
+                        .push((rejection.header.clone(), rejection.reason.clone()));
+                }
+            }
+        });
+
+    let client = client(&middleware);
+    client.get("http://example.com/?session_id=1&_ga=2").await?;
+
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "session_id=1"
+    );
+    assert_eq!(
+        *rejections.lock().unwrap(),
+        vec![(String::from("_ga=2"), RejectionReason::Policy)]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn allow_send() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_policy(NotToApi);
+    let client = client(&middleware);
+    client.get("http://example.com/?tracking=1").await?;
+
+    assert_eq!(
+        client.get("http://example.com/page").recv_string().await?,
+        "tracking=1"
+    );
+    assert_eq!(
+        client.get("http://example.com/api").recv_string().await?,
+        ""
+    );
+
+    let url = Url::parse("http://example.com/api")?;
+    assert!(middleware.matches_for_url(&url).await.is_empty());
+    assert_eq!(middleware.len().await, 1);
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use surf::http;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn exposes_received_cookies() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/?session=abc&theme=dark;Max-Age=3600")
+        .await?;
+
+    let cookie_store = middleware.cookie_store().await;
+    let url = http::Url::parse("http://example.com/")?;
+    assert_eq!(
+        cookie_store
+            .get("example.com", "/", "session")
+            .map(|c| c.value()),
+        Some("abc")
+    );
+    assert_eq!(cookie_store.matches(&url).len(), 2);
+
+    Ok(())
+}
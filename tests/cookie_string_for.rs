@@ -0,0 +1,39 @@
+use surf::Url;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn matches_the_header_that_is_sent() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_http_only_hidden(true);
+
+    let client = client(&middleware);
+
+    client
+        .get("https://example.com/?root=1&account=2;Path=/account&session=3;HttpOnly&other=4;Path=/other")
+        .await?;
+
+    let url = Url::parse("https://example.com/account/settings")?;
+    let cookies = middleware.cookie_string_for(&url).await;
+    assert_eq!(cookies, "account=2; root=1; session=3");
+    assert_eq!(client.get(url).recv_string().await?, cookies);
+
+    let url = Url::parse("https://example.org/")?;
+    assert_eq!(middleware.cookie_string_for(&url).await, "");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn does_not_count_cookies_as_sent() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let url = Url::parse("https://example.com/")?;
+    middleware
+        .import_set_cookie_headers([("session=abc123", &url)])
+        .await?;
+
+    assert_eq!(middleware.cookie_string_for(&url).await, "session=abc123");
+    assert_eq!(middleware.stats().sent, 0);
+    Ok(())
+}
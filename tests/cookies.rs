@@ -0,0 +1,32 @@
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn lists_every_unexpired_cookie() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    assert!(middleware.is_empty().await);
+
+    let client = client(&middleware);
+
+    client
+        .get("http://www.example.com/?b=2&a=1;Max-Age=3600")
+        .await?;
+    client.get("http://example.com/?c=3").await?;
+
+    let cookies = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| format!("{}={}", cookie.name(), cookie.value()))
+        .collect::<Vec<_>>();
+    assert_eq!(cookies, ["c=3", "a=1", "b=2"]);
+    assert_eq!(middleware.len().await, 3);
+    assert!(!middleware.is_empty().await);
+
+    client.get("http://example.com/?c=3;Max-Age=0").await?;
+    assert_eq!(middleware.len().await, 2);
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use async_std::{fs, task};
+use http::headers::SET_COOKIE;
+use std::time::Duration;
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+use tide::Request;
+
+async fn set_cookie(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(set_cookie) = req.url().query() {
+        res.insert_header(SET_COOKIE, set_cookie);
+    }
+    Ok(res)
+}
+
+#[async_std::test]
+async fn coalesces_saves_within_the_interval() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_save_debounce(Some(Duration::from_millis(300)));
+
+    let mut server = tide::new();
+    server.at("/").get(set_cookie);
+    let client = Client::with_http_client(server).with(middleware);
+
+    client.get("http://example.com/?a=1;Max-Age=3600").await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+
+    client.get("http://example.com/?b=2;Max-Age=3600").await?;
+    client.get("http://example.com/?c=3;Max-Age=3600").await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+
+    task::sleep(Duration::from_millis(600)).await;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 3);
+
+    Ok(())
+}
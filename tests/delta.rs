@@ -0,0 +1,53 @@
+use surf::Client;
+use surf_cookie_middleware::{CookieChange, CookieDelta, CookieMiddleware};
+
+mod common;
+use common::build_app;
+
+async fn delta(client: &Client, query: &str) -> surf::Result<CookieDelta> {
+    let res = client
+        .get(format!("http://example.com/set?{}", query))
+        .await?;
+    Ok(res.ext::<CookieDelta>().cloned().unwrap_or_default())
+}
+
+fn names(changes: &[CookieChange]) -> Vec<&str> {
+    changes.iter().map(|change| change.name.as_str()).collect()
+}
+
+#[async_std::test]
+async fn reports_added_updated_and_deleted_cookies() -> surf::Result<()> {
+    let client = Client::with_http_client(build_app()).with(CookieMiddleware::new());
+
+    let added = delta(&client, "session=1&consent=yes;Max-Age=3600").await?;
+    assert_eq!(names(&added.added), ["session", "consent"]);
+    assert!(added.added.iter().all(|change| change.old_expiry.is_none()));
+    assert!(added.updated.is_empty() && added.deleted.is_empty());
+
+    let rotated = delta(&client, "session=2&consent=yes;Max-Age=7200").await?;
+    assert!(rotated.added.is_empty());
+    assert_eq!(names(&rotated.updated), ["session", "consent"]);
+    assert!(rotated.updated[0].value_changed);
+    assert!(!rotated.updated[1].value_changed);
+    assert_ne!(rotated.updated[1].old_expiry, rotated.updated[1].new_expiry);
+
+    let logout = delta(&client, "session=;Max-Age=0").await?;
+    assert_eq!(names(&logout.deleted), ["session"]);
+    assert!(logout.deleted[0].new_expiry.is_none());
+    assert!(logout.deleted[0].old_expiry.is_some());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn empty_without_set_cookie() -> surf::Result<()> {
+    let client = Client::with_http_client(build_app()).with(CookieMiddleware::new());
+    assert!(delta(&client, "").await?.is_empty());
+
+    let res = client.get("http://example.com/set").await?;
+    assert!(res.ext::<CookieDelta>().is_some_and(CookieDelta::is_empty));
+
+    let expiring_unknown = delta(&client, "gone=;Max-Age=0").await?;
+    assert!(expiring_unknown.is_empty());
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+async fn stored(middleware: CookieMiddleware) -> surf::Result<Vec<String>> {
+    let client = client(&middleware);
+
+    client
+        .get("http://app.mycompany.com/?app=1&site=1;Domain=mycompany.com")
+        .await?;
+    client.get("http://ads.mycompany.com/?ads=1").await?;
+    client.get("http://tracker.net/?tracker=1").await?;
+
+    let mut names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+#[async_std::test]
+async fn stores_only_allowed_domains() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_allowed_domains(vec!["*.mycompany.com"]);
+    assert_eq!(stored(middleware).await?, ["ads", "app"]);
+
+    let middleware =
+        CookieMiddleware::new().with_allowed_domains(vec!["mycompany.com", ".mycompany.com"]);
+    assert_eq!(stored(middleware).await?, ["ads", "app", "site"]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn blocked_domains_win() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new()
+        .with_allowed_domains(vec!["mycompany.com", "*.mycompany.com"])
+        .with_blocked_domains(vec!["ADS.mycompany.com"]);
+    assert_eq!(stored(middleware.clone()).await?, ["app", "site"]);
+    assert_eq!(middleware.stats().rejected, 2);
+
+    let middleware = CookieMiddleware::new().with_blocked_domains(vec!["tracker.net"]);
+    assert_eq!(stored(middleware).await?, ["ads", "app", "site"]);
+    Ok(())
+}
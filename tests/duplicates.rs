@@ -0,0 +1,84 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use std::sync::{Arc, Mutex};
+use surf::{http, Client};
+use surf_cookie_middleware::{events::CookieEvent, CookieMiddleware, DuplicateCookies};
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/set").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, "a=first; Path=/");
+        res.append_header(SET_COOKIE, "b=1; Path=/");
+        res.append_header(SET_COOKIE, "a=second; Path=/");
+        res.append_header(SET_COOKIE, "b=2; Path=/other");
+        Ok(res)
+    });
+    server.at("/cookies").get(|req: Request<_>| async move {
+        Ok(req
+            .header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default())
+    });
+    server
+}
+
+async fn collisions(
+    duplicate_cookies: DuplicateCookies,
+) -> surf::Result<(String, Vec<(String, String, String)>)> {
+    let events = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new()
+        .with_duplicate_cookies(duplicate_cookies)
+        .with_event_listener({
+            let events = events.clone();
+            move |event| {
+                if let CookieEvent::Duplicate(duplicate) = event {
+                    events.lock().unwrap().push((
+                        duplicate.name.clone(),
+                        duplicate.kept.clone(),
+                        duplicate.discarded.clone(),
+                    ));
+                }
+            }
+        });
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+    client.get("http://example.com/set").await?;
+    let cookies = client
+        .get("http://example.com/cookies")
+        .recv_string()
+        .await?;
+
+    let events = events.lock().unwrap().clone();
+    Ok((cookies, events))
+}
+
+#[async_std::test]
+async fn last_wins_by_default() -> surf::Result<()> {
+    let (cookies, events) = collisions(DuplicateCookies::default()).await?;
+    assert_eq!(cookies, "a=second; b=1");
+    assert_eq!(
+        events,
+        vec![(
+            String::from("a"),
+            String::from("a=second; Path=/"),
+            String::from("a=first; Path=/")
+        )]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn first_wins() -> surf::Result<()> {
+    let (cookies, events) = collisions(DuplicateCookies::FirstWins).await?;
+    assert_eq!(cookies, "a=first; b=1");
+    assert_eq!(
+        events,
+        vec![(
+            String::from("a"),
+            String::from("a=first; Path=/"),
+            String::from("a=second; Path=/")
+        )]
+    );
+    Ok(())
+}
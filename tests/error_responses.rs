@@ -0,0 +1,88 @@
+use http::headers::SET_COOKIE;
+use std::sync::{Arc, Mutex};
+use surf::{http, Client, StatusCode};
+use surf_cookie_middleware::{
+    events::{CookieEvent, RejectionReason},
+    CookieMiddleware,
+};
+use tide::Request;
+
+async fn respond(req: Request<()>) -> tide::Result {
+    let status: u16 = req.url().path().trim_start_matches('/').parse()?;
+    let mut res = tide::Response::new(status);
+    res.insert_header(SET_COOKIE, format!("status{}=1", status));
+    Ok(res)
+}
+
+async fn stored_names(middleware: CookieMiddleware) -> surf::Result<Vec<String>> {
+    let mut server = tide::new();
+    server.at("/:status").get(respond);
+    let client = Client::with_http_client(server).with(middleware.clone());
+    for status in [200, 302, 404, 500, 503] {
+        client.get(format!("http://example.com/{}", status)).await?;
+    }
+
+    let mut names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+#[async_std::test]
+async fn error_responses_are_stored_by_default() -> surf::Result<()> {
+    assert_eq!(
+        stored_names(CookieMiddleware::new()).await?,
+        vec![
+            "status200",
+            "status302",
+            "status404",
+            "status500",
+            "status503"
+        ]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn error_responses_ignored() -> surf::Result<()> {
+    let rejections = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new()
+        .with_error_responses_ignored(true)
+        .with_event_listener({
+            let rejections = rejections.clone();
+            move |event| {
+                if let CookieEvent::Rejected(rejection) = event {
+                    rejections.lock().unwrap().push(rejection.reason.clone());
+                }
+            }
+        });
+
+    assert_eq!(
+        stored_names(middleware).await?,
+        vec!["status200", "status302"]
+    );
+    assert_eq!(
+        *rejections.lock().unwrap(),
+        vec![
+            RejectionReason::IgnoredStatus(StatusCode::NotFound),
+            RejectionReason::IgnoredStatus(StatusCode::InternalServerError),
+            RejectionReason::IgnoredStatus(StatusCode::ServiceUnavailable),
+        ]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn status_filter() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new()
+        .with_status_filter(|status| status != StatusCode::ServiceUnavailable);
+    assert_eq!(
+        stored_names(middleware).await?,
+        vec!["status200", "status302", "status404", "status500"]
+    );
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use async_std::prelude::*;
+use surf_cookie_middleware::{events::CookieEvent, CookieMiddleware};
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn events_are_streamed_to_each_subscriber() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = client(&middleware);
+
+    client.get("http://example.com/set?Path=/").await?;
+
+    let mut first = middleware.events();
+    let second = middleware.events();
+    client.get("http://example.com/set?=value").await?;
+    drop(second);
+    client.get("http://example.com/set?HttpOnly").await?;
+
+    for header in ["=value", "HttpOnly"] {
+        match first.next().await {
+            Some(CookieEvent::Rejected(rejection)) => assert_eq!(rejection.header, header),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    drop(client);
+    drop(middleware);
+    assert!(first.next().await.is_none());
+    Ok(())
+}
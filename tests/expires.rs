@@ -0,0 +1,162 @@
+use http::headers::SET_COOKIE;
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use surf::{http, Client};
+use surf_cookie_middleware::{formats::Selenium, CookieMiddleware};
+use tempfile::NamedTempFile;
+use tide::Request;
+
+fn build_app(set_cookies: &'static [&'static str]) -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/").get(move |_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        for set_cookie in set_cookies {
+            res.append_header(SET_COOKIE, *set_cookie);
+        }
+        Ok(res)
+    });
+    server
+}
+
+async fn stored_expiries(
+    middleware: CookieMiddleware,
+    set_cookies: &'static [&'static str],
+) -> surf::Result<Value> {
+    let client = Client::with_http_client(build_app(set_cookies)).with(middleware.clone());
+    client.get("http://example.com/").await?;
+
+    let exported: Value = serde_json::from_str(&middleware.export(&Selenium).await?)?;
+    Ok(exported
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|cookie| {
+            (
+                cookie["name"].as_str().unwrap().to_string(),
+                cookie["expiry"].clone(),
+            )
+        })
+        .collect())
+}
+
+#[async_std::test]
+async fn two_digit_years() -> surf::Result<()> {
+    let expiries = stored_expiries(
+        CookieMiddleware::new(),
+        &[
+            "rfc850=1; Expires=Wednesday, 21-Oct-37 07:28:00 GMT",
+            "no_weekday=1; Expires=21 Oct 37 07:28:00 GMT",
+            "utc=1; Expires=Wed, 21-Oct-37 07:28:00 UTC",
+            "reordered=1; Expires=Oct 21 07:28:00 37",
+            "upper_bound=1; expires=Sun, 01 Jan 68 00:00:00 GMT",
+            "nineteen_sixty_nine=1; Expires=01 Jan 69 00:00:01 GMT",
+            "nineteen_ninety_nine=1; Expires=31-Dec-99 23:59:59 GMT",
+        ],
+    )
+    .await?;
+
+    assert_eq!(
+        expiries,
+        json!({
+            "rfc850": 2139722880,
+            "no_weekday": 2139722880,
+            "utc": 2139722880,
+            "reordered": 2139722880,
+            "upper_bound": 3092601600i64,
+        })
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn unparseable_dates_are_session_cookies() -> surf::Result<()> {
+    let expiries = stored_expiries(
+        CookieMiddleware::new(),
+        &[
+            "no_time=1; Expires=21 Oct 37 GMT",
+            "bad_day=1; Expires=31 Feb 37 07:28:00 GMT",
+            "too_early=1; Expires=21 Oct 1600 07:28:00 GMT",
+            "max_age_wins=1; Max-Age=0; Expires=21 Oct 37 07:28:00 UTC",
+        ],
+    )
+    .await?;
+
+    assert_eq!(
+        expiries,
+        json!({ "no_time": null, "bad_day": null, "too_early": null })
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn far_future_expiries_saturate() -> surf::Result<()> {
+    let expiries = stored_expiries(CookieMiddleware::new(), &[
+        "huge_max_age=1; Max-Age=9223372036854775807",
+        "year_9999=1; Expires=Fri, 31 Dec 9999 23:59:59 GMT",
+        "huge_max_age_and_expires=1; Max-Age=99999999999999; Expires=Fri, 31 Dec 9999 23:59:59 GMT",
+    ])
+    .await?;
+
+    assert_eq!(
+        expiries,
+        json!({
+            "huge_max_age": 253402300799i64,
+            "year_9999": 253402300799i64,
+            "huge_max_age_and_expires": 253402300799i64,
+        })
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn far_future_expiries_round_trip_through_a_file() -> surf::Result<()> {
+    let (file, path) = NamedTempFile::new()?.into_parts();
+    let middleware = CookieMiddleware::from_file(file).await?;
+    let client = Client::with_http_client(build_app(&[
+        "huge_max_age=1; Max-Age=9223372036854775807",
+        "year_9999=1; Expires=Fri, 31 Dec 9999 23:59:59 GMT",
+    ]))
+    .with(middleware);
+    client.get("http://example.com/").await?;
+
+    let reloaded = CookieMiddleware::from_path(&*path).await?;
+    let exported: Value = serde_json::from_str(&reloaded.export(&Selenium).await?)?;
+    assert_eq!(exported.as_array().unwrap().len(), 2);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn max_lifetime() -> surf::Result<()> {
+    let middleware =
+        CookieMiddleware::new().with_max_lifetime(Some(CookieMiddleware::BROWSER_MAX_LIFETIME));
+    let expiries = stored_expiries(
+        middleware,
+        &[
+            "max_age=1; Max-Age=63072000",
+            "far_future=1; Expires=Fri, 31 Dec 9999 23:59:59 GMT",
+            "short=1; Max-Age=3600",
+            "session=1",
+        ],
+    )
+    .await?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let cap = now + 400 * 24 * 60 * 60;
+    for name in ["max_age", "far_future"] {
+        let expiry = expiries[name].as_i64().unwrap();
+        assert!(
+            (cap - 5..=cap + 5).contains(&expiry),
+            "{}: {}",
+            name,
+            expiry
+        );
+    }
+    assert!(expiries["short"].as_i64().unwrap() <= now + 3605);
+    assert!(expiries["session"].is_null());
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn unknown_attributes_are_kept() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = client(&middleware);
+
+    client
+        .get(
+            "http://example.com/set?session=1;Path=/;Priority=High;Secure;Partitioned;X-Vendor=a=b",
+        )
+        .await?;
+
+    assert_eq!(
+        middleware.extension_attributes("example.com", "/", "session"),
+        vec![
+            ("Priority".to_string(), Some("High".to_string())),
+            ("Partitioned".to_string(), None),
+            ("X-Vendor".to_string(), Some("a=b".to_string())),
+        ]
+    );
+
+    client
+        .get("http://example.com/set?session=2;Path=/")
+        .await?;
+    assert!(middleware
+        .extension_attributes("example.com", "/", "session")
+        .is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn expired_cookies_lose_their_attributes() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/set?session=1;Path=/;Priority=Low")
+        .await?;
+    client
+        .get("http://example.com/set?session=;Path=/;Max-Age=0")
+        .await?;
+
+    assert!(middleware
+        .extension_attributes("example.com", "/", "session")
+        .is_empty());
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use async_std::{fs, task};
+use fs2::FileExt;
+use http::headers::SET_COOKIE;
+use std::time::Duration;
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+async fn set_cookie(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(set_cookie) = req.url().query() {
+        res.insert_header(SET_COOKIE, set_cookie);
+    }
+    Ok(res)
+}
+
+#[async_std::test]
+async fn saves_wait_for_the_lock() -> surf::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cookies.ndjson");
+    let middleware = CookieMiddleware::builder()
+        .path(&path)
+        .file_locking(true)
+        .build()
+        .await?;
+
+    let lock = std::fs::File::open(dir.path().join("cookies.ndjson.lock"))?;
+    lock.lock_exclusive()?;
+
+    let mut server = tide::new();
+    server.at("/").get(set_cookie);
+    let client = Client::with_http_client(server).with(middleware);
+    let request = task::spawn(async move {
+        client.get("http://example.com/?a=1;Max-Age=3600").await?;
+        surf::Result::Ok(())
+    });
+
+    task::sleep(Duration::from_millis(200)).await;
+    assert_eq!(fs::read_to_string(&path).await?, "");
+
+    lock.unlock()?;
+    request.await?;
+    assert_eq!(fs::read_to_string(&path).await?.lines().count(), 1);
+
+    Ok(())
+}
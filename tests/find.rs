@@ -0,0 +1,64 @@
+use surf_cookie_middleware::{CookieMiddleware, CookiePattern, CookieView};
+
+mod common;
+use common::client;
+
+async fn jar() -> surf::Result<CookieMiddleware> {
+    let middleware = CookieMiddleware::new();
+    let client = client(&middleware);
+    client
+        .get("http://www.example.com/set?session_id=abc123&theme=dark")
+        .await?;
+    client
+        .get("http://api.example.com/set?session_token=def456")
+        .await?;
+    client.get("http://other.test/set?tracker=abc123").await?;
+    Ok(middleware)
+}
+
+fn names(views: &[CookieView]) -> Vec<&str> {
+    views.iter().map(|view| view.name.as_str()).collect()
+}
+
+#[async_std::test]
+async fn glob_matches_domain_or_name() -> surf::Result<()> {
+    let middleware = jar().await?;
+
+    let by_domain = middleware.find("*.example.com").await;
+    assert_eq!(names(&by_domain), ["session_token", "session_id", "theme"]);
+    assert!(by_domain.iter().all(|view| view.value.is_none()));
+
+    assert_eq!(names(&middleware.find("session_??").await), ["session_id"]);
+    assert!(middleware.find("example.com").await.is_empty());
+    Ok(())
+}
+
+#[async_std::test]
+async fn values_are_matched_and_revealed_on_request() -> surf::Result<()> {
+    let middleware = jar().await?;
+
+    assert!(middleware.find("abc*").await.is_empty());
+
+    let found = middleware
+        .find(CookiePattern::glob("abc*").matching_values(true))
+        .await;
+    assert_eq!(names(&found), ["tracker", "session_id"]);
+    assert_eq!(found[0].value, None);
+
+    let revealed = middleware
+        .find(CookiePattern::glob("tracker").revealing_values(true))
+        .await;
+    assert_eq!(revealed[0].value.as_deref(), Some("abc123"));
+    Ok(())
+}
+
+#[cfg(feature = "regex")]
+#[async_std::test]
+async fn regex() -> surf::Result<()> {
+    let middleware = jar().await?;
+    let found = middleware
+        .find(CookiePattern::regex("^session_(id|token)$")?)
+        .await;
+    assert_eq!(names(&found), ["session_token", "session_id"]);
+    Ok(())
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "firefox")]
+use http::headers::COOKIE;
+use rusqlite::{params, Connection};
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+use tide::Request;
+
+// the columns of moz_cookies that matter here, as of Firefox 128
+const SCHEMA: &str = "CREATE TABLE moz_cookies (
+    id INTEGER PRIMARY KEY,
+    originAttributes TEXT NOT NULL DEFAULT '',
+    name TEXT,
+    value TEXT,
+    host TEXT,
+    path TEXT,
+    expiry INTEGER,
+    lastAccessed INTEGER,
+    creationTime INTEGER,
+    isSecure INTEGER,
+    isHttpOnly INTEGER,
+    inBrowserElement INTEGER DEFAULT 0,
+    sameSite INTEGER DEFAULT 0,
+    rawSameSite INTEGER DEFAULT 0,
+    schemeMap INTEGER DEFAULT 0
+)";
+
+#[async_std::test]
+async fn imports_firefox_profile_cookies() -> surf::Result<()> {
+    let database = NamedTempFile::new()?;
+    {
+        let connection = Connection::open(database.path())?;
+        connection.execute_batch(SCHEMA)?;
+        let mut insert = connection.prepare(
+            "INSERT INTO moz_cookies (name, value, host, path, expiry, isSecure, isHttpOnly, sameSite)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        insert.execute(params![
+            "session",
+            "abc",
+            ".example.com",
+            "/",
+            4102444800i64,
+            0,
+            1,
+            1
+        ])?;
+        insert.execute(params![
+            "milliseconds",
+            "1",
+            "example.com",
+            "/",
+            4102444800000i64,
+            0,
+            0,
+            0
+        ])?;
+        insert.execute(params![
+            "secure",
+            "2",
+            "example.com",
+            "/",
+            4102444800i64,
+            1,
+            0,
+            2
+        ])?;
+        insert.execute(params!["expired", "3", "example.com", "/", 1i64, 0, 0, 0])?;
+    }
+
+    let middleware = CookieMiddleware::new();
+    assert_eq!(middleware.import_firefox(database.path()).await?, 3);
+
+    let mut server = tide::new();
+    server.at("/").get(|req: Request<()>| async move {
+        Ok(req
+            .header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default())
+    });
+    let client = Client::with_http_client(server).with(middleware);
+
+    assert_eq!(
+        client.get("http://www.example.com/").recv_string().await?,
+        "session=abc"
+    );
+
+    let mut cookies = client
+        .get("https://example.com/")
+        .recv_string()
+        .await?
+        .split("; ")
+        .map(String::from)
+        .collect::<Vec<_>>();
+    cookies.sort();
+    assert_eq!(cookies, ["milliseconds=1", "secure=2", "session=abc"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn missing_moz_cookies_table_is_an_error() -> surf::Result<()> {
+    let database = NamedTempFile::new()?;
+    Connection::open(database.path())?.execute_batch("CREATE TABLE other (id INTEGER)")?;
+    assert!(CookieMiddleware::new()
+        .import_firefox(database.path())
+        .await
+        .is_err());
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use async_std::fs;
+use http::headers::SET_COOKIE;
+use std::time::Duration;
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+use tide::Request;
+
+async fn set_cookie(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(set_cookie) = req.url().query() {
+        res.insert_header(SET_COOKIE, set_cookie);
+    }
+    Ok(res)
+}
+
+fn build_client(middleware: CookieMiddleware) -> Client {
+    let mut server = tide::new();
+    server.at("/").get(set_cookie);
+    Client::with_http_client(server).with(middleware)
+}
+
+#[async_std::test]
+async fn flush_writes_debounced_changes() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_save_debounce(Some(Duration::from_secs(60)));
+    let client = build_client(middleware.clone());
+
+    client.get("http://example.com/?a=1;Max-Age=3600").await?;
+    client.get("http://example.com/?b=2;Max-Age=3600").await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+
+    middleware.flush().await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 2);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn saves_when_the_last_clone_is_dropped() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_manual_persistence(true)
+        .with_save_on_drop(true);
+    let client = build_client(middleware.clone());
+
+    client.get("http://example.com/?a=1;Max-Age=3600").await?;
+    drop(middleware);
+    assert_eq!(fs::read_to_string(jar.path()).await?, "");
+
+    drop(client);
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,447 @@
+use serde_json::{json, Value};
+use surf::Client;
+use surf_cookie_middleware::{
+    formats::{CookieEditor, CopyAsCurl, Har, Json, Netscape, Selenium, StorageState},
+    CookieMiddleware,
+};
+
+mod common;
+use common::{build_app, client};
+
+#[async_std::test]
+async fn storage_state_import() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let storage_state = json!({
+        "cookies": [
+            {
+                "name": "host_only", "value": "1", "domain": "example.com",
+                "path": "/", "expires": -1, "httpOnly": false, "secure": false,
+                "sameSite": "Lax"
+            },
+            {
+                "name": "subdomains", "value": "2", "domain": ".example.com",
+                "path": "/", "expires": 4102444800.5, "httpOnly": true, "secure": false,
+                "sameSite": "None"
+            },
+            {
+                "name": "secure", "value": "3", "domain": "example.com",
+                "path": "/", "expires": -1, "httpOnly": false, "secure": true,
+                "sameSite": "Strict"
+            }
+        ],
+        "origins": [{ "origin": "https://example.com", "localStorage": [] }]
+    });
+
+    let imported = middleware
+        .import(&StorageState, &storage_state.to_string())
+        .await?;
+    assert_eq!(imported, 3);
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+    let cookies = client
+        .get("http://example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "host_only=1; subdomains=2");
+
+    let cookies = client
+        .get("http://www.example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "subdomains=2");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn storage_state_round_trip() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let storage_state = json!({
+        "cookies": [{
+            "name": "subdomains", "value": "2", "domain": ".example.com",
+            "path": "/app", "expires": 4102444800i64, "httpOnly": true, "secure": true,
+            "sameSite": "Strict"
+        }]
+    });
+    middleware
+        .import(&StorageState, &storage_state.to_string())
+        .await?;
+
+    let exported: Value = serde_json::from_str(&middleware.export(&StorageState).await?)?;
+    assert_eq!(
+        exported,
+        json!({
+            "cookies": [{
+                "name": "subdomains", "value": "2", "domain": ".example.com",
+                "path": "/app", "expires": 4102444800.0, "httpOnly": true, "secure": true,
+                "sameSite": "Strict"
+            }],
+            "origins": []
+        })
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn storage_state_invalid() {
+    let middleware = CookieMiddleware::new();
+    assert!(middleware.import(&StorageState, "{}").await.is_err());
+    assert!(middleware.import(&StorageState, "not json").await.is_err());
+}
+
+#[async_std::test]
+async fn selenium_round_trip() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let cookies = json!([
+        {
+            "name": "session", "value": "abc", "domain": "example.com", "path": "/",
+            "secure": false, "httpOnly": true, "sameSite": "Lax"
+        },
+        {
+            "name": "tracking", "value": "xyz", "domain": ".example.com",
+            "expiry": 4102444800i64
+        }
+    ]);
+    assert_eq!(middleware.import(&Selenium, &cookies.to_string()).await?, 2);
+
+    let client = client(&middleware);
+    let cookies = client
+        .get("http://www.example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "tracking=xyz");
+
+    let exported: Value = serde_json::from_str(&middleware.export(&Selenium).await?)?;
+    assert_eq!(
+        exported,
+        json!([
+            {
+                "name": "session", "value": "abc", "domain": "example.com", "path": "/",
+                "secure": false, "httpOnly": true, "sameSite": "Lax"
+            },
+            {
+                "name": "tracking", "value": "xyz", "domain": ".example.com", "path": "/",
+                "secure": false, "httpOnly": false, "expiry": 4102444800i64
+            }
+        ])
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn selenium_requires_domain() {
+    let middleware = CookieMiddleware::new();
+    let cookies = json!([{ "name": "session", "value": "abc" }]);
+    assert!(middleware
+        .import(&Selenium, &cookies.to_string())
+        .await
+        .is_err());
+}
+
+const COOKIES_TXT: &str = "# Netscape HTTP Cookie File
+# https://curl.se/docs/http-cookies.html
+
+.example.com\tTRUE\t/\tFALSE\t4102444800\ttracking\txyz
+#HttpOnly_example.com\tFALSE\t/\tFALSE\t0\tsession\tabc
+example.com\tFALSE\t/account\tTRUE\t4102444800\ttoken\t
+";
+
+#[async_std::test]
+async fn netscape_round_trip() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    assert_eq!(middleware.import(&Netscape, COOKIES_TXT).await?, 3);
+
+    let client = client(&middleware);
+    let cookies = client
+        .get("http://www.example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "tracking=xyz");
+
+    assert_eq!(
+        middleware.export(&Netscape).await?,
+        "# Netscape HTTP Cookie File
+
+.example.com\tTRUE\t/\tFALSE\t4102444800\ttracking\txyz
+#HttpOnly_example.com\tFALSE\t/\tFALSE\t0\tsession\tabc
+example.com\tFALSE\t/account\tTRUE\t4102444800\ttoken\t
+"
+    );
+
+    assert!(middleware
+        .import(&Netscape, "example.com\tFALSE\t/\n")
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn cookie_editor_import() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let cookies = json!([
+        {
+            "domain": "example.com", "hostOnly": true, "httpOnly": true,
+            "name": "session", "path": "/", "sameSite": "unspecified",
+            "secure": false, "session": true, "storeId": "0", "value": "abc", "id": 1
+        },
+        {
+            "domain": ".example.com", "expirationDate": 4102444800.25, "hostOnly": false,
+            "httpOnly": false, "name": "consent", "path": "/", "sameSite": "no_restriction",
+            "secure": false, "session": false, "storeId": "0", "value": "yes", "id": 2
+        }
+    ]);
+    assert_eq!(
+        middleware
+            .import(&CookieEditor, &cookies.to_string())
+            .await?,
+        2
+    );
+
+    let client = client(&middleware);
+    let cookies = client
+        .get("http://www.example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "consent=yes");
+
+    let exported: Value = serde_json::from_str(&middleware.export(&StorageState).await?)?;
+    assert_eq!(exported["cookies"][0]["expires"], json!(-1.0));
+    assert_eq!(exported["cookies"][0]["domain"], json!("example.com"));
+    assert_eq!(exported["cookies"][1]["expires"], json!(4102444800.0));
+    assert_eq!(exported["cookies"][1]["sameSite"], json!("None"));
+
+    assert!(middleware.export(&CookieEditor).await.is_err());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn copy_as_curl_import() -> surf::Result<()> {
+    let chrome = r#"curl 'https://example.com/account?tab=1' \
+  -H 'accept: text/html' \
+  -b $'session=abc\'123; theme=dark' \
+  -H 'user-agent: Mozilla/5.0' \
+  --compressed"#;
+
+    let middleware = CookieMiddleware::new();
+    assert_eq!(middleware.import(&CopyAsCurl::new(), chrome).await?, 2);
+    let client = Client::with_http_client(build_app()).with(middleware);
+    let cookies = client
+        .get("http://example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "session=abc'123; theme=dark");
+
+    let cookies = client
+        .get("http://www.example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "");
+
+    let firefox =
+        r#"curl "https://example.com/" -X POST -H "Cookie: session=xyz" --data-raw "a=b""#;
+    let middleware = CookieMiddleware::new();
+    let format = CopyAsCurl::new().with_url(surf::Url::parse("http://example.org/")?);
+    assert_eq!(middleware.import(&format, firefox).await?, 1);
+    let client = Client::with_http_client(build_app()).with(middleware);
+    let cookies = client
+        .get("http://example.org/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "session=xyz");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn copy_as_curl_invalid() {
+    let middleware = CookieMiddleware::new();
+    let format = CopyAsCurl::new();
+    assert!(middleware.import(&format, "curl -b 'a=b'").await.is_err());
+    assert!(middleware
+        .import(&format, "curl 'https://example.com -b 'a=b'")
+        .await
+        .is_err());
+}
+
+#[async_std::test]
+async fn json_round_trip() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let cookies = json!([
+        {
+            "name": "session", "value": "abc123", "domain": "example.com",
+            "expires": "2099-12-31T00:00:00Z", "http_only": true
+        },
+        {
+            "name": "region", "value": "eu", "domain": "example.com",
+            "host_only": false, "path": "/shop", "same_site": "Lax"
+        }
+    ]);
+    assert_eq!(middleware.import(&Json, &cookies.to_string()).await?, 2);
+
+    let exported = middleware.export(&Json).await?;
+    let exported_value: Value = serde_json::from_str(&exported)?;
+    assert_eq!(
+        exported_value,
+        json!([
+            {
+                "name": "session", "value": "abc123", "domain": "example.com",
+                "host_only": true, "path": "/", "expires": "2099-12-31T00:00:00Z",
+                "secure": false, "http_only": true
+            },
+            {
+                "name": "region", "value": "eu", "domain": "example.com",
+                "host_only": false, "path": "/shop", "secure": false, "http_only": false,
+                "same_site": "Lax"
+            }
+        ])
+    );
+
+    let other = CookieMiddleware::new();
+    assert_eq!(other.import(&Json, &exported).await?, 2);
+    assert_eq!(other.export(&Json).await?, exported);
+    Ok(())
+}
+
+#[async_std::test]
+async fn har_import() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let har = json!({
+        "log": {
+            "version": "1.2",
+            "entries": [
+                {
+                    "request": {
+                        "url": "https://example.com/account/login",
+                        "cookies": [{ "name": "consent", "value": "yes" }]
+                    },
+                    "response": {
+                        "cookies": [
+                            {
+                                "name": "session", "value": "abc123",
+                                "expires": "2099-12-31T00:00:00.000Z",
+                                "httpOnly": true, "secure": true, "sameSite": "Lax"
+                            },
+                            {
+                                "name": "region", "value": "eu", "path": "/",
+                                "domain": ".example.com"
+                            }
+                        ]
+                    }
+                },
+                {
+                    "request": { "url": "https://example.com/account", "cookies": [] },
+                    "response": {
+                        "cookies": [{ "name": "region", "value": "us", "path": "/", "domain": ".example.com" }]
+                    }
+                }
+            ]
+        }
+    });
+    assert_eq!(middleware.import(&Har, &har.to_string()).await?, 4);
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+    let cookies = client
+        .get("https://www.example.com/account/settings")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "region=us");
+
+    let cookies = client
+        .get("https://example.com/account/settings")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "session=abc123; consent=yes; region=us");
+    Ok(())
+}
+
+#[async_std::test]
+async fn har_round_trip() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let cookies = json!([
+        {
+            "name": "session", "value": "abc123", "domain": "example.com",
+            "expires": "2099-12-31T00:00:00Z", "secure": true, "same_site": "Strict"
+        },
+        { "name": "region", "value": "eu", "domain": "example.org", "host_only": false }
+    ]);
+    middleware.import(&Json, &cookies.to_string()).await?;
+
+    let exported: Value = serde_json::from_str(&middleware.export(&Har).await?)?;
+    let entries = exported["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["request"]["url"], "https://example.com/");
+    assert_eq!(
+        entries[0]["response"]["cookies"],
+        json!([{
+            "name": "session", "value": "abc123", "path": "/", "domain": "example.com",
+            "expires": "2099-12-31T00:00:00Z", "httpOnly": false, "secure": true,
+            "sameSite": "Strict"
+        }])
+    );
+    assert_eq!(entries[1]["request"]["url"], "http://example.org/");
+    assert_eq!(
+        entries[1]["response"]["cookies"][0]["domain"],
+        ".example.org"
+    );
+
+    let other = CookieMiddleware::new();
+    assert_eq!(other.import(&Har, &exported.to_string()).await?, 2);
+    assert_eq!(other.export_json().await?, middleware.export_json().await?);
+    Ok(())
+}
+
+#[async_std::test]
+async fn har_invalid() {
+    let middleware = CookieMiddleware::new();
+    assert!(middleware.import(&Har, r#"{"log": {}}"#).await.is_ok());
+    assert!(middleware.import(&Har, "[]").await.is_err());
+}
+
+#[cfg(feature = "yaml")]
+#[async_std::test]
+async fn yaml_round_trip() -> surf::Result<()> {
+    use surf_cookie_middleware::formats::Yaml;
+
+    let middleware = CookieMiddleware::new();
+    let yaml = r#"
+- name: session
+  value: abc123
+  domain: example.com
+  expires: 2099-12-31T00:00:00Z
+  http_only: true
+- name: region
+  value: eu
+  domain: example.com
+  host_only: false
+  path: /shop
+  same_site: Lax
+"#;
+    assert_eq!(middleware.import(&Yaml, yaml).await?, 2);
+
+    let client = client(&middleware);
+    let cookies = client
+        .get("http://www.example.com/shop/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "region=eu");
+
+    let exported = middleware.export(&Yaml).await?;
+    let other = CookieMiddleware::new();
+    assert_eq!(other.import(&Yaml, &exported).await?, 2);
+    assert_eq!(other.export(&Yaml).await?, exported);
+    assert!(exported.contains("2099-12-31T00:00:00Z"));
+
+    assert!(middleware
+        .import(
+            &Yaml,
+            "- { name: a, value: b, domain: example.com, expires: soon }"
+        )
+        .await
+        .is_err());
+
+    Ok(())
+}
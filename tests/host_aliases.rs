@@ -0,0 +1,61 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+async fn echo_cookies(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(set_cookie) = req.url().query() {
+        res.insert_header(SET_COOKIE, set_cookie.replace("%20", " "));
+    }
+    res.set_body(
+        req.header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default(),
+    );
+    Ok(res)
+}
+
+fn client() -> Client {
+    let mut server = tide::new();
+    server.at("/").get(echo_cookies);
+    Client::with_http_client(server).with(
+        CookieMiddleware::new()
+            .with_host_aliases(vec!["app.example.com", "APP.internal"])
+            .with_host_aliases(vec!["staging.example.com", "localhost"]),
+    )
+}
+
+#[async_std::test]
+async fn aliases_share_cookies() -> surf::Result<()> {
+    let client = client();
+    client.get("http://app.internal/?a=1").await?;
+    client
+        .get("http://app.example.com/?b=2;%20Domain=app.internal")
+        .await?;
+    client.get("http://localhost/?c=3").await?;
+
+    for host in ["app.example.com", "app.internal"] {
+        let mut cookies = client
+            .get(format!("http://{}/", host))
+            .recv_string()
+            .await?
+            .split("; ")
+            .map(String::from)
+            .collect::<Vec<_>>();
+        cookies.sort();
+        assert_eq!(cookies, ["a=1", "b=2"], "{}", host);
+    }
+
+    for host in ["staging.example.com", "localhost"] {
+        let cookies = client
+            .get(format!("http://{}/", host))
+            .recv_string()
+            .await?;
+        assert_eq!(cookies, "c=3", "{}", host);
+    }
+
+    let cookies = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(cookies, "");
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use surf::{Client, Url};
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+
+async fn client(middleware: &CookieMiddleware) -> surf::Result<Client> {
+    let client = common::client(middleware);
+    client
+        .get("http://example.com/?session=1;HttpOnly&theme=dark")
+        .await?;
+    Ok(client)
+}
+
+#[async_std::test]
+async fn http_only_cookies_are_readable_by_default() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    client(&middleware).await?;
+    let url = Url::parse("http://example.com/")?;
+
+    assert_eq!(middleware.cookies().await.len(), 2);
+    assert_eq!(middleware.get(&url, "session").await.as_deref(), Some("1"));
+    Ok(())
+}
+
+#[async_std::test]
+async fn http_only_cookies_are_hidden_but_sent() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_http_only_hidden(true);
+    let client = client(&middleware).await?;
+    let url = Url::parse("http://example.com/")?;
+
+    let names = |cookies: Vec<surf_cookie_middleware::cookie_store::Cookie<'static>>| {
+        cookies
+            .iter()
+            .map(|cookie| cookie.name().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(middleware.cookies().await), vec!["theme"]);
+    assert_eq!(names(middleware.matches_for_url(&url).await), vec!["theme"]);
+    assert_eq!(middleware.get(&url, "session").await, None);
+    assert_eq!(middleware.find("example.com").await.len(), 1);
+    assert_eq!(middleware.len().await, 2);
+
+    let mut sent = client
+        .get("http://example.com/")
+        .recv_string()
+        .await?
+        .split("; ")
+        .map(String::from)
+        .collect::<Vec<_>>();
+    sent.sort();
+    assert_eq!(sent, vec!["session=1", "theme=dark"]);
+    Ok(())
+}
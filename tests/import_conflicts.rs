@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex};
+use surf_cookie_middleware::{
+    cookie_store::{Cookie, CookieDomain},
+    formats::Json,
+    CookieFormat, CookieLimits, CookieMiddleware, CookiePattern, ImportConflict, ImportConflicts,
+};
+
+const EXISTING: &str = r#"[
+  { "name": "a", "value": "existing", "domain": "example.com", "expires": "2090-01-01T00:00:00Z" },
+  { "name": "b", "value": "existing", "domain": "example.com", "expires": "2090-01-01T00:00:00Z" }
+]"#;
+
+const IMPORTED: &str = r#"[
+  { "name": "a", "value": "imported", "domain": "example.com", "expires": "2080-01-01T00:00:00Z" },
+  { "name": "b", "value": "imported", "domain": "example.com", "expires": "2095-01-01T00:00:00Z" },
+  { "name": "c", "value": "imported", "domain": "example.com" }
+]"#;
+
+async fn import(conflicts: ImportConflicts) -> std::io::Result<(Vec<String>, Vec<bool>, usize)> {
+    let middleware = CookieMiddleware::new();
+    middleware.import(&Json, EXISTING).await?;
+    let report = middleware.import_with(&Json, IMPORTED, conflicts).await?;
+
+    let values = middleware
+        .find(CookiePattern::glob("*").revealing_values(true))
+        .await
+        .into_iter()
+        .map(|view| format!("{}={}", view.name, view.value.unwrap()))
+        .collect();
+
+    assert_eq!(
+        report
+            .conflicts
+            .iter()
+            .map(
+                |ImportConflict {
+                     name, domain, path, ..
+                 }| format!("{}{}{}", domain, path, name)
+            )
+            .collect::<Vec<_>>(),
+        ["example.com/a", "example.com/b"]
+    );
+    let replaced = report
+        .conflicts
+        .iter()
+        .map(|conflict| conflict.replaced)
+        .collect();
+    Ok((values, replaced, report.imported))
+}
+
+#[async_std::test]
+async fn prefer_imported() -> std::io::Result<()> {
+    let (values, replaced, imported) = import(ImportConflicts::PreferImported).await?;
+    assert_eq!(values, ["a=imported", "b=imported", "c=imported"]);
+    assert_eq!(replaced, [true, true]);
+    assert_eq!(imported, 3);
+    Ok(())
+}
+
+#[async_std::test]
+async fn prefer_existing() -> std::io::Result<()> {
+    let (values, replaced, imported) = import(ImportConflicts::PreferExisting).await?;
+    assert_eq!(values, ["a=existing", "b=existing", "c=imported"]);
+    assert_eq!(replaced, [false, false]);
+    assert_eq!(imported, 1);
+    Ok(())
+}
+
+#[async_std::test]
+async fn prefer_newer_expiry() -> std::io::Result<()> {
+    let (values, replaced, imported) = import(ImportConflicts::PreferNewerExpiry).await?;
+    assert_eq!(values, ["a=existing", "b=imported", "c=imported"]);
+    assert_eq!(replaced, [false, true]);
+    assert_eq!(imported, 2);
+    Ok(())
+}
+
+#[async_std::test]
+async fn imports_are_reported_and_limited() -> std::io::Result<()> {
+    let stored = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new()
+        .with_limits(Some(CookieLimits::new().max_total(2)))
+        .on_cookie_set({
+            let stored = stored.clone();
+            move |cookie, _, action| {
+                stored
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {:?}", cookie.name(), action))
+            }
+        });
+    middleware.import(&Json, EXISTING).await?;
+    let report = middleware
+        .import_with(&Json, IMPORTED, ImportConflicts::PreferImported)
+        .await?;
+
+    assert_eq!(report.imported, 3);
+    assert_eq!(
+        *stored.lock().unwrap(),
+        [
+            "a Created",
+            "b Created",
+            "a Updated",
+            "b Updated",
+            "c Created"
+        ]
+    );
+    assert_eq!(middleware.len().await, 2);
+    Ok(())
+}
+
+/// Json, except that the cookie named `bad` loses its domain, as a
+/// custom format might leave it
+struct Domainless;
+
+impl CookieFormat for Domainless {
+    fn parse(&self, input: &str) -> std::io::Result<Vec<Cookie<'static>>> {
+        let mut cookies = Json.parse(input)?;
+        for cookie in &mut cookies {
+            if cookie.name() == "bad" {
+                cookie.domain = CookieDomain::Empty;
+            }
+        }
+        Ok(cookies)
+    }
+}
+
+#[async_std::test]
+async fn bad_records_are_skipped() -> std::io::Result<()> {
+    let jar = tempfile::NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_limits(Some(CookieLimits::new().max_total(1)));
+    let report = middleware
+        .import_with(
+            &Domainless,
+            r#"[
+              { "name": "a", "value": "1", "domain": "example.com", "expires": "2090-01-01T00:00:00Z" },
+              { "name": "bad", "value": "1", "domain": "example.com", "expires": "2090-01-01T00:00:00Z" },
+              { "name": "c", "value": "1", "domain": "example.com", "expires": "2090-01-01T00:00:00Z" }
+            ]"#,
+            ImportConflicts::PreferImported,
+        )
+        .await?;
+
+    assert_eq!(report.imported, 2);
+    assert_eq!(report.skipped, 1);
+    assert_eq!(middleware.len().await, 1);
+    let persisted = std::fs::read_to_string(jar.path())?;
+    assert_eq!(persisted.lines().count(), 1);
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+use surf::{utils::async_trait, Client, Url};
+use surf_cookie_middleware::{
+    cookie_store::Cookie,
+    events::{CookieEvent, RejectionReason},
+    CookieMiddleware, CookieStorage,
+};
+
+mod common;
+use common::build_app;
+
+/// Counts the saves of a jar
+#[derive(Debug, Default, Clone)]
+struct CountingStorage(Arc<Mutex<usize>>);
+
+#[async_trait]
+impl CookieStorage for CountingStorage {
+    async fn load(&self) -> io::Result<Vec<Cookie<'static>>> {
+        Ok(vec![])
+    }
+
+    async fn persist(&self, _: &[Cookie<'static>]) -> io::Result<()> {
+        *self.0.lock().unwrap() += 1;
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn headers_are_stored_with_one_save() -> surf::Result<()> {
+    let storage = CountingStorage::default();
+    let middleware = CookieMiddleware::builder()
+        .storage(storage.clone())
+        .build()
+        .await?;
+
+    let login = Url::parse("https://example.com/account/login")?;
+    let other = Url::parse("https://example.org/")?;
+    let stored = middleware
+        .import_set_cookie_headers([
+            ("session=old; Max-Age=3600", &login),
+            ("session=new; Max-Age=3600", &login),
+            ("theme=dark; Path=/; Max-Age=3600", &login),
+            ("region=eu; Domain=example.org; Max-Age=3600", &other),
+        ])
+        .await?;
+    assert_eq!(stored, 4);
+    assert_eq!(*storage.0.lock().unwrap(), 1);
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+    let cookies = client
+        .get("https://example.com/account/settings")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "session=new; theme=dark");
+
+    let cookies = client.get("https://www.example.org/").recv_string().await?;
+    assert_eq!(cookies, "region=eu");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn rejected_headers_are_reported() -> surf::Result<()> {
+    let events = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new().with_event_listener({
+        let events = events.clone();
+        move |event| {
+            if let CookieEvent::Rejected(rejection) = event {
+                events.lock().unwrap().push(rejection.reason.clone());
+            }
+        }
+    });
+
+    let url = Url::parse("https://example.com/")?;
+    let stored = middleware
+        .import_set_cookie_headers([("HttpOnly; Path=/", &url), ("valid=1", &url)])
+        .await?;
+    assert_eq!(stored, 1);
+    assert!(matches!(
+        events.lock().unwrap()[..],
+        [RejectionReason::AttributeOnly]
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use async_std::fs::{self, OpenOptions};
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+use surf::utils::async_trait;
+use surf_cookie_middleware::{formats::Netscape, AsyncIo, CookieMiddleware, FileStorage, JarIo};
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+async fn async_io(jar: &NamedTempFile) -> io::Result<AsyncIo<fs::File>> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(jar.path())
+        .await?;
+    Ok(AsyncIo::new(file))
+}
+
+#[async_std::test]
+async fn async_io_shrinks_without_truncating() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::builder()
+        .storage(FileStorage::from_io(async_io(&jar).await?))
+        .build()
+        .await?;
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/?a=1;Max-Age=3600&b=2;Max-Age=3600&c=3;Max-Age=3600")
+        .await?;
+    let full = fs::read_to_string(jar.path()).await?;
+    assert_eq!(full.lines().count(), 3);
+
+    client
+        .get("http://example.com/?a=;Max-Age=0&b=;Max-Age=0")
+        .await?;
+    let shrunk = fs::read_to_string(jar.path()).await?;
+    assert_eq!(shrunk.len(), full.len());
+    assert!(shrunk.ends_with(' '));
+
+    let reloaded = CookieMiddleware::builder()
+        .storage(FileStorage::from_io(async_io(&jar).await?))
+        .build()
+        .await?;
+    let cookies = self::client(&reloaded)
+        .get("http://example.com/")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "c=3");
+
+    Ok(())
+}
+
+/// A jar kept in memory, standing in for storage without a filesystem
+#[derive(Debug, Default, Clone)]
+struct MemoryIo(Arc<Mutex<Vec<u8>>>);
+
+#[async_trait]
+impl JarIo for MemoryIo {
+    async fn read(&mut self) -> io::Result<Vec<u8>> {
+        Ok(self.0.lock().unwrap().clone())
+    }
+
+    async fn replace(&mut self, bytes: &[u8]) -> io::Result<()> {
+        *self.0.lock().unwrap() = bytes.to_vec();
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn custom_io_is_written_in_its_format() -> surf::Result<()> {
+    let io = MemoryIo::default();
+    let middleware = CookieMiddleware::builder()
+        .storage(FileStorage::from_io_with_format(io.clone(), Netscape))
+        .build()
+        .await?;
+    client(&middleware)
+        .get("http://example.com/?a=1;Max-Age=3600")
+        .await?;
+
+    let contents = String::from_utf8(io.0.lock().unwrap().clone())?;
+    assert!(
+        contents
+            .lines()
+            .any(|line| line.starts_with("example.com\tFALSE\t/\tFALSE\t")
+                && line.ends_with("\ta\t1"))
+    );
+
+    let reloaded = CookieMiddleware::builder()
+        .storage(FileStorage::from_io_with_format(io, Netscape))
+        .build()
+        .await?;
+    let cookies = client(&reloaded)
+        .get("http://example.com/")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "a=1");
+
+    Ok(())
+}
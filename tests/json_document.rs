@@ -0,0 +1,42 @@
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn exported_json_round_trips() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    client(&middleware)
+        .get("http://example.com/?b=2;Path=/docs&a=1;Max-Age=3600&c=3;HttpOnly")
+        .await?;
+
+    let json = middleware.export_json().await?;
+    let document: serde_json::Value = serde_json::from_str(&json)?;
+    let names = document
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|cookie| cookie["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["a", "c", "b"]);
+    assert_eq!(json, middleware.export_json().await?);
+
+    let restored = CookieMiddleware::new();
+    assert_eq!(restored.import_json(&json).await?, 3);
+    assert_eq!(restored.export_json().await?, json);
+
+    let cookies = client(&restored)
+        .get("http://example.com/docs")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "b=2; a=1; c=3");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn invalid_json_is_an_error() {
+    let middleware = CookieMiddleware::new();
+    let error = middleware.import_json("{").await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
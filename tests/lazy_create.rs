@@ -0,0 +1,47 @@
+use async_std::fs;
+use surf::Client;
+use surf_cookie_middleware::{formats::Json, CookieMiddleware};
+
+mod common;
+use common::build_app;
+
+#[async_std::test]
+async fn creates_the_jar_on_the_first_persistent_cookie() -> surf::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cookies.ndjson");
+
+    let middleware = CookieMiddleware::builder()
+        .path(&path)
+        .lazy_create(true)
+        .build()
+        .await?;
+    assert!(!path.exists());
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+
+    client.get("http://example.com/?session=1").await?;
+    assert!(!path.exists());
+
+    client
+        .get("http://example.com/?persistent=1;Max-Age=3600")
+        .await?;
+    let persisted = fs::read_to_string(&path).await?;
+    assert_eq!(persisted.lines().count(), 1);
+    assert!(persisted.contains("persistent=1"));
+
+    let middleware = CookieMiddleware::builder()
+        .path(&path)
+        .lazy_create(true)
+        .build()
+        .await?;
+    assert_eq!(
+        middleware
+            .export(&Json)
+            .await?
+            .matches("persistent")
+            .count(),
+        1
+    );
+
+    Ok(())
+}
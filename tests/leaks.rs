@@ -0,0 +1,67 @@
+use http::headers::SET_COOKIE;
+use std::sync::{Arc, Mutex};
+use surf::{http, Client};
+use surf_cookie_middleware::{events::CookieEvent, CookieMiddleware};
+use tide::Request;
+
+async fn set_cookie(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(set_cookie) = req.url().query() {
+        res.insert_header(SET_COOKIE, set_cookie);
+    }
+    Ok(res)
+}
+
+async fn leaks(detect_leaks: bool) -> surf::Result<Vec<(String, String, String, String)>> {
+    let leaks = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new()
+        .with_leak_detection(detect_leaks)
+        .with_event_listener({
+            let leaks = leaks.clone();
+            move |event| {
+                if let CookieEvent::PossibleLeak(leak) = event {
+                    leaks.lock().unwrap().push((
+                        leak.url.host_str().unwrap().to_string(),
+                        leak.name.clone(),
+                        leak.source_domain.clone(),
+                        leak.source_name.clone(),
+                    ));
+                }
+            }
+        });
+
+    let mut server = tide::new();
+    server.at("/").get(set_cookie);
+    let client = Client::with_http_client(server).with(middleware);
+
+    client.get("http://a.example/?token=s3cr3t-t0ken").await?;
+    client.get("http://a.example/?flag=1").await?;
+    client.get("http://b.example/?copied=s3cr3t-t0ken").await?;
+    client.get("http://b.example/?flag=1").await?;
+    client.get("http://b.example/").await?;
+    client.get("http://www.a.example/").await?;
+
+    let leaks = leaks.lock().unwrap().clone();
+    Ok(leaks)
+}
+
+#[async_std::test]
+async fn copied_values_are_reported() -> surf::Result<()> {
+    let found = leaks(true).await?;
+    let expected = (
+        String::from("b.example"),
+        String::from("copied"),
+        String::from("a.example"),
+        String::from("token"),
+    );
+    assert!(found.contains(&expected), "{:?}", found);
+    assert!(found.iter().all(|(_, name, _, _)| name != "flag"));
+    assert!(found.iter().all(|(host, _, _, _)| host != "www.a.example"));
+    Ok(())
+}
+
+#[async_std::test]
+async fn detection_is_off_by_default() -> surf::Result<()> {
+    assert!(leaks(false).await?.is_empty());
+    Ok(())
+}
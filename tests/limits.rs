@@ -0,0 +1,59 @@
+use async_std::task;
+use std::time::Duration;
+use surf::Client;
+use surf_cookie_middleware::{CookieLimits, CookieMiddleware, Eviction};
+
+mod common;
+use common::build_app;
+
+fn build_client(middleware: CookieMiddleware) -> Client {
+    Client::with_http_client(build_app()).with(middleware)
+}
+
+async fn names(middleware: &CookieMiddleware) -> Vec<String> {
+    let mut names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+async fn visit(client: &Client, url: &str) -> surf::Result<()> {
+    client.get(url).await?;
+    // keeps use times distinct
+    task::sleep(Duration::from_millis(5)).await;
+    Ok(())
+}
+
+#[async_std::test]
+async fn evicts_the_least_recently_used_cookie_of_a_site() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_limits(Some(CookieLimits::new().max_per_site(2)));
+    let client = build_client(middleware.clone());
+
+    visit(&client, "http://a.example.com/?a=1").await?;
+    visit(&client, "http://b.example.com/?b=1").await?;
+    visit(&client, "http://a.example.com/").await?;
+    visit(&client, "http://other.com/?other=1").await?;
+    visit(&client, "http://c.example.com/?c=1").await?;
+
+    assert_eq!(names(&middleware).await, ["a", "c", "other"]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn evicts_the_oldest_cookies_over_the_total() -> surf::Result<()> {
+    let limits = CookieLimits::new().max_total(2).eviction(Eviction::Oldest);
+    let middleware = CookieMiddleware::new().with_limits(Some(limits));
+    let client = build_client(middleware.clone());
+
+    visit(&client, "http://one.com/?one=1").await?;
+    visit(&client, "http://two.com/?two=1").await?;
+    visit(&client, "http://one.com/").await?;
+    visit(&client, "http://three.com/?three=1").await?;
+
+    assert_eq!(names(&middleware).await, ["three", "two"]);
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use async_std::fs;
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+async fn cookies(client: &Client) -> surf::Result<Vec<String>> {
+    let mut cookies = client
+        .get("http://example.com/")
+        .recv_string()
+        .await?
+        .split("; ")
+        .filter(|cookie| !cookie.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    cookies.sort();
+    Ok(cookies)
+}
+
+#[async_std::test]
+async fn saves_and_reloads_only_on_request() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_manual_persistence(true);
+
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/?saved=1;Max-Age=3600&session=1")
+        .await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?, "");
+
+    middleware.save().await?;
+    let persisted = fs::read_to_string(jar.path()).await?;
+    assert_eq!(persisted.lines().count(), 1);
+    assert!(persisted.contains("saved=1"));
+
+    client
+        .get("http://example.com/?saved=2;Max-Age=3600&unsaved=1;Max-Age=3600")
+        .await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?, persisted);
+    assert_eq!(
+        cookies(&client).await?,
+        ["saved=2", "session=1", "unsaved=1"]
+    );
+
+    middleware.reload().await?;
+    assert_eq!(cookies(&client).await?, ["saved=1", "session=1"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reload_without_storage_does_nothing() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_manual_persistence(true);
+    middleware.save().await?;
+    middleware.reload().await?;
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use surf::Url;
+use surf_cookie_middleware::{cookie_store::Cookie, CookieMiddleware};
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn lists_the_cookies_a_request_would_send() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+
+    let client = client(&middleware);
+
+    client
+        .get("https://example.com/?root=1&account=2;Path=/account&tls=3;Secure&other=4;Path=/other")
+        .await?;
+
+    // cookies with paths of the same length may be sent in any order
+    let names = |cookies: Vec<Cookie<'static>>| {
+        let mut names = cookies
+            .iter()
+            .map(|cookie| cookie.name().to_string())
+            .collect::<Vec<_>>();
+        names[1..].sort();
+        names
+    };
+
+    let url = Url::parse("https://example.com/account/settings")?;
+    assert_eq!(
+        names(middleware.matches_for_url(&url).await),
+        ["account", "root", "tls"]
+    );
+
+    let url = Url::parse("http://example.com/account")?;
+    assert_eq!(
+        names(middleware.matches_for_url(&url).await),
+        ["account", "root"]
+    );
+
+    let url = Url::parse("wss://example.com/")?;
+    let mut names = names(middleware.matches_for_url(&url).await);
+    names.sort();
+    assert_eq!(names, ["root", "tls"]);
+
+    assert_eq!(middleware.stats().sent, 0);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn gets_one_value() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+
+    let client = client(&middleware);
+
+    client
+        .get("https://example.com/?id=outer&id=inner;Path=/account")
+        .await?;
+
+    let url = Url::parse("https://example.com/account/settings")?;
+    assert_eq!(middleware.get(&url, "id").await.as_deref(), Some("inner"));
+
+    let url = Url::parse("https://example.com/")?;
+    assert_eq!(middleware.get(&url, "id").await.as_deref(), Some("outer"));
+    assert_eq!(middleware.get(&url, "missing").await, None);
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use http::headers::SET_COOKIE;
+use std::sync::{Arc, Mutex};
+use surf::{http, Client};
+use surf_cookie_middleware::{
+    events::{CookieEvent, RejectionReason},
+    CookieMiddleware,
+};
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, format!("small={}", "a".repeat(10)));
+        res.append_header(SET_COOKIE, format!("large={}", "a".repeat(5000)));
+        res.append_header(SET_COOKIE, format!("limit={}", "a".repeat(4091)));
+        Ok(res)
+    });
+    server
+}
+
+async fn stored_names(middleware: CookieMiddleware) -> surf::Result<Vec<String>> {
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    client.get("http://example.com/").await?;
+    let mut names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+#[async_std::test]
+async fn cookies_larger_than_browsers_allow_are_rejected() -> surf::Result<()> {
+    let rejections = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new().with_event_listener({
+        let rejections = rejections.clone();
+        move |event| {
+            if let CookieEvent::Rejected(rejection) = event {
+                rejections.lock().unwrap().push(rejection.reason.clone());
+            }
+        }
+    });
+
+    assert_eq!(stored_names(middleware).await?, vec!["limit", "small"]);
+    assert_eq!(
+        *rejections.lock().unwrap(),
+        vec![RejectionReason::TooLarge {
+            size: 5005,
+            max_bytes: 4096
+        }]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn configured_limit() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_max_cookie_size(Some(100));
+    assert_eq!(stored_names(middleware).await?, vec!["small"]);
+
+    let middleware = CookieMiddleware::new().with_max_cookie_size(None);
+    assert_eq!(
+        stored_names(middleware).await?,
+        vec!["large", "limit", "small"]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn default_applies_the_browser_limit() -> surf::Result<()> {
+    assert_eq!(
+        stored_names(CookieMiddleware::default()).await?,
+        vec!["limit", "small"]
+    );
+    Ok(())
+}
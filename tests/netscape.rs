@@ -0,0 +1,44 @@
+use async_std::fs;
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::build_app;
+
+#[async_std::test]
+async fn shares_cookies_txt_with_curl() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    fs::write(
+        jar.path(),
+        "# Netscape HTTP Cookie File\nexample.com\tFALSE\t/\tFALSE\t4102444800\tfrom_curl\t1\n",
+    )
+    .await?;
+
+    let middleware = CookieMiddleware::from_netscape_path(jar.path()).await?;
+    let client = Client::with_http_client(build_app()).with(middleware);
+
+    let cookies = client
+        .get("http://example.com/?from_surf=2;Max-Age=3600")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "from_curl=1");
+
+    let cookies_txt = fs::read_to_string(jar.path()).await?;
+    assert!(cookies_txt.starts_with("# Netscape HTTP Cookie File\n"));
+    assert!(cookies_txt.contains("example.com\tFALSE\t/\tFALSE\t4102444800\tfrom_curl\t1\n"));
+    assert!(cookies_txt.contains("\tfrom_surf\t2\n"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn unparseable_cookies_txt_is_an_error() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    fs::write(jar.path(), "not a cookie file\n").await?;
+    assert!(CookieMiddleware::from_netscape_path(jar.path())
+        .await
+        .is_err());
+    assert_eq!(fs::read_to_string(jar.path()).await?, "not a cookie file\n");
+    Ok(())
+}
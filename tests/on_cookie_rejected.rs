@@ -0,0 +1,49 @@
+use http::headers::SET_COOKIE;
+use std::sync::{Arc, Mutex};
+use surf::{http, Client};
+use surf_cookie_middleware::{
+    cookie_store::CookieError, events::RejectionReason, CookieMiddleware,
+};
+use tide::Request;
+
+#[async_std::test]
+async fn callback_sees_each_rejection() -> surf::Result<()> {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new().on_cookie_rejected({
+        let calls = calls.clone();
+        move |header, url, reason| {
+            calls
+                .lock()
+                .unwrap()
+                .push((header.to_string(), url.to_string(), reason.clone()));
+        }
+    });
+
+    let mut server = tide::new();
+    server.at("/").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, "valid=1");
+        res.append_header(SET_COOKIE, "=nameless");
+        res.append_header(SET_COOKIE, "other=1; Domain=example.org");
+        Ok(res)
+    });
+    let client = Client::with_http_client(server).with(middleware);
+    client.get("http://example.com/").await?;
+
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![
+            (
+                String::from("=nameless"),
+                String::from("http://example.com/"),
+                RejectionReason::EmptyName
+            ),
+            (
+                String::from("other=1; Domain=example.org"),
+                String::from("http://example.com/"),
+                RejectionReason::CookieStore(CookieError::DomainMismatch)
+            ),
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+use surf::Url;
+use surf_cookie_middleware::{cookie::Cookie, events::StoreOutcome, CookieMiddleware};
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn callback_sees_each_stored_cookie() -> surf::Result<()> {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new().on_cookie_set({
+        let calls = calls.clone();
+        move |cookie, url, action| {
+            calls.lock().unwrap().push((
+                format!("{}={}", cookie.name(), cookie.value()),
+                url.path().to_string(),
+                action,
+            ));
+        }
+    });
+
+    let client = client(&middleware);
+    client.get("http://example.com/login?auth=1;Path=/").await?;
+    client
+        .get("http://example.com/refresh?auth=2;Path=/&=bad")
+        .await?;
+    client
+        .get("http://example.com/logout?auth=;Path=/;Max-Age=0")
+        .await?;
+    middleware
+        .set_cookie(
+            Cookie::new("manual", "1"),
+            &Url::parse("http://example.com/")?,
+        )
+        .await?;
+
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![
+            (
+                String::from("auth=1"),
+                String::from("/login"),
+                StoreOutcome::Created
+            ),
+            (
+                String::from("auth=2"),
+                String::from("/refresh"),
+                StoreOutcome::Updated
+            ),
+            (
+                String::from("auth="),
+                String::from("/logout"),
+                StoreOutcome::Removed
+            ),
+            (
+                String::from("manual=1"),
+                String::from("/"),
+                StoreOutcome::Created
+            ),
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use serde_json::{json, Value};
+use surf::{http, Client};
+use surf_cookie_middleware::{formats::Selenium, CookieMiddleware};
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/set").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.insert_header(SET_COOKIE, "encoded=caf%C3%A9%3B%20au%20lait; Path=/");
+        Ok(res)
+    });
+
+    server.at("/cookies").get(|req: Request<_>| async move {
+        Ok(req
+            .header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default())
+    });
+
+    server
+}
+
+fn imported_cookies() -> String {
+    json!([{
+        "name": "imported", "value": "a b,c;d\"é", "domain": "example.com"
+    }])
+    .to_string()
+}
+
+#[async_std::test]
+async fn values_are_encoded_on_send() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_percent_encoding(true);
+    middleware.import(&Selenium, &imported_cookies()).await?;
+
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    client.get("http://example.com/set").await?;
+    let cookies = client
+        .get("http://example.com/cookies")
+        .recv_string()
+        .await?;
+
+    assert_eq!(
+        cookies,
+        "imported=a%20b%2Cc%3Bd%22%C3%A9; encoded=caf%C3%A9%3B%20au%20lait"
+    );
+
+    let exported: Value = serde_json::from_str(&middleware.export(&Selenium).await?)?;
+    assert_eq!(exported[0]["value"], json!("a b,c;d\"é"));
+    assert_eq!(exported[1]["value"], json!("café; au lait"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn values_are_sent_verbatim_by_default() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    client.get("http://example.com/set").await?;
+    middleware.import(&Selenium, &imported_cookies()).await?;
+
+    let exported: Value = serde_json::from_str(&middleware.export(&Selenium).await?)?;
+    assert_eq!(exported[0]["value"], json!("caf%C3%A9%3B%20au%20lait"));
+    assert_eq!(exported[1]["value"], json!("a b,c;d\"é"));
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use async_std::fs;
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::build_app;
+
+async fn build_client(jar: &NamedTempFile, persist_session_cookies: bool) -> surf::Result<Client> {
+    let middleware = CookieMiddleware::builder()
+        .path(jar.path())
+        .persist_session_cookies(persist_session_cookies)
+        .build()
+        .await?;
+    Ok(Client::with_http_client(build_app()).with(middleware))
+}
+
+#[async_std::test]
+async fn session_cookies_survive_a_restart() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+
+    let client = build_client(&jar, true).await?;
+    client
+        .get("http://example.com/?session=1&persistent=2;Max-Age=3600")
+        .await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 2);
+
+    let client = build_client(&jar, true).await?;
+    let mut cookies = client
+        .get("http://example.com/")
+        .recv_string()
+        .await?
+        .split("; ")
+        .map(String::from)
+        .collect::<Vec<_>>();
+    cookies.sort();
+    assert_eq!(cookies, ["persistent=2", "session=1"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn session_cookies_are_not_persisted_by_default() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+
+    let client = build_client(&jar, false).await?;
+    client
+        .get("http://example.com/?session=1&persistent=2;Max-Age=3600")
+        .await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn loaded_session_cookies_can_be_discarded() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+
+    let client = build_client(&jar, true).await?;
+    client
+        .get("http://example.com/?session=1&persistent=2;Max-Age=3600")
+        .await?;
+
+    let middleware = CookieMiddleware::builder()
+        .path(jar.path())
+        .discard_session_cookies(true)
+        .build()
+        .await?;
+    let names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["persistent"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reloaded_session_cookies_can_be_discarded() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::builder()
+        .path(jar.path())
+        .discard_session_cookies(true)
+        .build()
+        .await?;
+
+    let client = build_client(&jar, true).await?;
+    client
+        .get("http://example.com/?session=1&persistent=2;Max-Age=3600")
+        .await?;
+
+    middleware.reload().await?;
+    let names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["persistent"]);
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+use surf::{utils::async_trait, Client};
+use surf_cookie_middleware::{
+    cookie_store::Cookie, CookieMiddleware, CookieStorage, PersistenceFailurePolicy,
+};
+
+mod common;
+
+#[derive(Debug)]
+struct BrokenStorage;
+
+#[async_trait]
+impl CookieStorage for BrokenStorage {
+    async fn load(&self) -> io::Result<Vec<Cookie<'static>>> {
+        Ok(vec![])
+    }
+
+    async fn persist(&self, _: &[Cookie<'static>]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "read-only"))
+    }
+}
+
+async fn client(policy: Option<PersistenceFailurePolicy>) -> io::Result<Client> {
+    let mut middleware = CookieMiddleware::builder()
+        .storage(BrokenStorage)
+        .build()
+        .await?;
+    if let Some(policy) = policy {
+        middleware = middleware.with_persistence_failure_policy(policy);
+    }
+
+    Ok(common::client(&middleware))
+}
+
+#[async_std::test]
+async fn failures_fail_the_request_by_default() -> surf::Result<()> {
+    let client = client(None).await?;
+    let error = client
+        .get("http://example.com/?a=1;Max-Age=3600")
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), 500);
+    Ok(())
+}
+
+#[async_std::test]
+async fn failures_can_be_logged() -> surf::Result<()> {
+    let client = client(Some(PersistenceFailurePolicy::LogAndContinue)).await?;
+    let mut res = client.get("http://example.com/?a=1;Max-Age=3600").await?;
+    assert_eq!(res.status(), 200);
+    res.body_string().await?;
+
+    let cookies = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(cookies, "a=1");
+    Ok(())
+}
+
+#[async_std::test]
+async fn failures_can_be_passed_to_a_callback() -> surf::Result<()> {
+    let errors = Arc::new(Mutex::new(vec![]));
+    let policy = PersistenceFailurePolicy::callback({
+        let errors = errors.clone();
+        move |e| errors.lock().unwrap().push(e.kind())
+    });
+    let client = client(Some(policy)).await?;
+
+    let res = client.get("http://example.com/?a=1;Max-Age=3600").await?;
+    assert_eq!(res.status(), 200);
+    assert_eq!(*errors.lock().unwrap(), [io::ErrorKind::PermissionDenied]);
+    Ok(())
+}
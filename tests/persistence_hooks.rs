@@ -0,0 +1,86 @@
+use async_std::fs;
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+use surf::utils::async_trait;
+use surf_cookie_middleware::{cookie_store::Cookie, CookieMiddleware, CookieStorage};
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+#[derive(Debug)]
+struct BrokenStorage;
+
+#[async_trait]
+impl CookieStorage for BrokenStorage {
+    async fn load(&self) -> io::Result<Vec<Cookie<'static>>> {
+        Ok(vec![])
+    }
+
+    async fn persist(&self, _: &[Cookie<'static>]) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "read-only"))
+    }
+}
+
+type Summaries = Arc<Mutex<Vec<(usize, Option<u64>, Option<io::ErrorKind>)>>>;
+
+fn record(summaries: &Summaries) -> impl Fn(&surf_cookie_middleware::PersistenceSummary) {
+    let summaries = summaries.clone();
+    move |summary| {
+        summaries.lock().unwrap().push((
+            summary.cookies,
+            summary.bytes,
+            summary.error.as_ref().map(io::Error::kind),
+        ))
+    }
+}
+
+#[async_std::test]
+async fn saves_and_loads_are_summarized() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let saves = Summaries::default();
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .on_save(record(&saves));
+
+    client(&middleware)
+        .get("http://example.com/?a=1;Max-Age=3600&b=2;Max-Age=3600&session=3")
+        .await?;
+    let size = fs::metadata(jar.path()).await?.len();
+    assert_eq!(*saves.lock().unwrap(), vec![(2, Some(size), None)]);
+
+    let loads = Summaries::default();
+    let middleware = CookieMiddleware::builder()
+        .path(jar.path())
+        .on_load(record(&loads))
+        .build()
+        .await?;
+    middleware.reload().await?;
+    assert_eq!(
+        *loads.lock().unwrap(),
+        vec![(2, Some(size), None), (2, Some(size), None)]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn failed_saves_are_summarized() -> surf::Result<()> {
+    let saves = Summaries::default();
+    let middleware = CookieMiddleware::builder()
+        .storage(BrokenStorage)
+        .build()
+        .await?
+        .on_save(record(&saves));
+
+    assert!(client(&middleware)
+        .get("http://example.com/?a=1;Max-Age=3600")
+        .await
+        .is_err());
+    assert_eq!(
+        *saves.lock().unwrap(),
+        vec![(0, None, Some(io::ErrorKind::PermissionDenied))]
+    );
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use http::headers::SET_COOKIE;
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+async fn set_cookie(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(query) = req.url().query() {
+        res.insert_header(SET_COOKIE, query);
+    }
+    Ok(res)
+}
+
+async fn is_stored(url: &str) -> surf::Result<bool> {
+    let middleware = CookieMiddleware::new();
+    let mut server = tide::new();
+    server.at("*").get(set_cookie);
+    server.at("/").get(set_cookie);
+    Client::with_http_client(server)
+        .with(middleware.clone())
+        .get(url)
+        .await?;
+    Ok(!middleware.is_empty().await)
+}
+
+#[async_std::test]
+async fn secure_prefix() -> surf::Result<()> {
+    assert!(is_stored("https://example.com/?__Secure-id=1;Secure").await?);
+    assert!(is_stored("https://example.com/?__Secure-id=1;Secure;Domain=example.com").await?);
+    assert!(!is_stored("https://example.com/?__Secure-id=1").await?);
+    assert!(!is_stored("http://example.com/?__Secure-id=1;Secure").await?);
+    assert!(!is_stored("https://example.com/?__secure-id=1").await?);
+    Ok(())
+}
+
+#[async_std::test]
+async fn host_prefix() -> surf::Result<()> {
+    assert!(is_stored("https://example.com/?__Host-id=1;Secure;Path=/").await?);
+    assert!(!is_stored("https://example.com/?__Host-id=1;Path=/").await?);
+    assert!(!is_stored("http://example.com/?__Host-id=1;Secure;Path=/").await?);
+    assert!(!is_stored("https://example.com/?__Host-id=1;Secure;Path=/;Domain=example.com").await?);
+    assert!(!is_stored("https://example.com/account/?__Host-id=1;Secure").await?);
+    assert!(!is_stored("https://example.com/?__Host-id=1;Secure;Path=/account").await?);
+    Ok(())
+}
+
+#[async_std::test]
+async fn other_names_are_unaffected() -> surf::Result<()> {
+    assert!(is_stored("http://example.com/?_Host-id=1").await?);
+    assert!(is_stored("http://example.com/?id=__Host-1").await?);
+    Ok(())
+}
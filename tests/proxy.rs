@@ -0,0 +1,52 @@
+use http::headers::HOST;
+use surf::{http, Client, Url};
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::build_app;
+
+#[async_std::test]
+async fn host_header_names_the_target() -> surf::Result<()> {
+    let client = Client::with_http_client(build_app()).with(CookieMiddleware::new());
+    client
+        .get("http://10.0.0.1:3128/login?session=abc;Path=/")
+        .header(HOST, "example.com")
+        .await?;
+
+    let cookies = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(cookies, "session=abc");
+
+    let cookies = client
+        .get("http://10.0.0.1:3128/")
+        .header(HOST, "example.com")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "session=abc");
+
+    let cookies = client.get("http://10.0.0.1:3128/").recv_string().await?;
+    assert_eq!(cookies, "");
+    Ok(())
+}
+
+#[async_std::test]
+async fn custom_target_url() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_target_url(|req| {
+        let url = req.url();
+        let target = url.path().trim_start_matches("/gateway/");
+        Url::parse(&format!("https://{}", target)).unwrap_or_else(|_| url.clone())
+    });
+    let client = Client::with_http_client(build_app()).with(middleware);
+    client
+        .get("http://gateway.local/gateway/example.com/login?session=abc;Path=/")
+        .await?;
+
+    let cookies = client.get("https://example.com/").recv_string().await?;
+    assert_eq!(cookies, "session=abc");
+
+    let cookies = client
+        .get("http://gateway.local/gateway/example.com/account")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "session=abc");
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use std::time::Duration;
+use surf::{http, Client};
+use surf_cookie_middleware::{CookieMiddleware, PruningPolicy};
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/set").get(|req: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        if let Some(query) = req.url().query() {
+            for header in query.split('&') {
+                res.append_header(SET_COOKIE, header);
+            }
+        }
+        Ok(res)
+    });
+    server.at("/").get(cookies);
+    server.at("*").get(cookies);
+    server
+}
+
+async fn cookies(req: Request<()>) -> tide::Result<String> {
+    Ok(req
+        .header(COOKIE)
+        .map(|cookie| cookie.as_str().to_string())
+        .unwrap_or_default())
+}
+
+const IDLE: Duration = Duration::from_millis(200);
+
+#[async_std::test]
+async fn prune_removes_cookies_that_were_not_used() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_pruning(Some(PruningPolicy::new(IDLE)));
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+
+    client
+        .get("http://example.com/set?used=1;Max-Age=3600&unused=1;Path=/elsewhere;Max-Age=3600")
+        .await?;
+    assert_eq!(middleware.prune().await?, 0);
+
+    async_std::task::sleep(IDLE / 2).await;
+    client.get("http://example.com/").recv_string().await?;
+    async_std::task::sleep(IDLE / 2).await;
+
+    assert_eq!(middleware.prune().await?, 1);
+    assert_eq!(
+        client
+            .get("http://example.com/elsewhere")
+            .recv_string()
+            .await?,
+        "used=1"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn prunes_periodically_when_responses_are_processed() -> surf::Result<()> {
+    let policy = PruningPolicy::new(IDLE).with_interval(Duration::ZERO);
+    let client = Client::with_http_client(build_app())
+        .with(CookieMiddleware::new().with_pruning(Some(policy)));
+
+    client
+        .get("http://example.com/set?stale=1;Path=/stale;Max-Age=3600")
+        .await?;
+    async_std::task::sleep(IDLE).await;
+    client
+        .get("http://example.com/set?fresh=1;Max-Age=3600")
+        .await?;
+
+    assert_eq!(
+        client.get("http://example.com/stale").recv_string().await?,
+        "fresh=1"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn no_pruning_by_default() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+
+    client
+        .get("http://example.com/set?kept=1;Max-Age=3600")
+        .await?;
+    async_std::task::sleep(IDLE).await;
+
+    assert_eq!(middleware.prune().await?, 0);
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "kept=1"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn use_times_persist_across_restarts() -> surf::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cookies.ndjson");
+
+    let middleware = CookieMiddleware::builder().path(&path).build().await?;
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    client
+        .get("http://example.com/set?stale=1;Path=/stale;Max-Age=3600&fresh=1;Max-Age=3600")
+        .await?;
+    async_std::task::sleep(IDLE).await;
+    client.get("http://example.com/").recv_string().await?;
+    middleware.flush().await?;
+    drop((client, middleware));
+
+    let middleware = CookieMiddleware::builder()
+        .path(&path)
+        .pruning(Some(PruningPolicy::new(IDLE)))
+        .build()
+        .await?;
+    let client = Client::with_http_client(build_app()).with(middleware);
+    assert_eq!(
+        client.get("http://example.com/stale").recv_string().await?,
+        "fresh=1"
+    );
+    assert!(!async_std::fs::read_to_string(&path)
+        .await?
+        .contains("stale"));
+    Ok(())
+}
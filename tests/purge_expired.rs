@@ -0,0 +1,35 @@
+use async_std::task;
+use std::time::Duration;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn purges_expired_cookies_from_memory() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/?brief=1;Max-Age=1&lasting=1;Max-Age=3600")
+        .await?;
+    task::sleep(Duration::from_millis(1100)).await;
+
+    assert_eq!(middleware.len().await, 1);
+    assert_eq!(
+        middleware
+            .cookie_store()
+            .await
+            .iter_any()
+            .filter(|cookie| cookie.is_expired())
+            .count(),
+        1
+    );
+
+    assert_eq!(middleware.purge_expired().await, 1);
+    assert_eq!(middleware.purge_expired().await, 0);
+    assert_eq!(middleware.cookie_store().await.iter_any().count(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use async_std::{fs, task};
+use std::time::Duration;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+async fn save_after_expiry(middleware: CookieMiddleware) -> surf::Result<()> {
+    let client = client(&middleware);
+    client
+        .get("http://example.com/?brief=1;Max-Age=1&lasting=1;Max-Age=3600")
+        .await?;
+    task::sleep(Duration::from_millis(1100)).await;
+    middleware.save().await?;
+    Ok(())
+}
+
+#[async_std::test]
+async fn expired_cookies_are_never_written() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path()).await?;
+    save_after_expiry(middleware.clone()).await?;
+
+    let contents = fs::read_to_string(jar.path()).await?;
+    assert!(contents.contains("lasting"));
+    assert!(!contents.contains("brief"));
+    assert_eq!(middleware.cookie_store().await.iter_any().count(), 2);
+    Ok(())
+}
+
+#[async_std::test]
+async fn purge_on_save() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_purge_on_save(true);
+    save_after_expiry(middleware.clone()).await?;
+
+    let contents = fs::read_to_string(jar.path()).await?;
+    assert!(contents.contains("lasting"));
+    assert!(!contents.contains("brief"));
+    assert_eq!(middleware.cookie_store().await.iter_any().count(), 1);
+    Ok(())
+}
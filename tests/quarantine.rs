@@ -0,0 +1,48 @@
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::{build_app, client};
+
+/// A jar backed by a file that can't be written
+async fn read_only_jar() -> std::io::Result<(tempfile::TempDir, CookieMiddleware)> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cookies.ndjson");
+    std::fs::write(&path, "")?;
+    let file = std::fs::File::open(&path)?;
+    Ok((dir, CookieMiddleware::from_file(file).await?))
+}
+
+#[async_std::test]
+async fn persistence_errors_fail_requests_by_default() -> surf::Result<()> {
+    let (_dir, middleware) = read_only_jar().await?;
+    let client = Client::with_http_client(build_app()).with(middleware);
+    assert!(client
+        .get("http://example.com/set?a=1;Max-Age=3600")
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[async_std::test]
+async fn quarantine_holds_changes_for_a_bounded_number_of_failed_saves() -> surf::Result<()> {
+    let (_dir, middleware) = read_only_jar().await?;
+    let middleware = middleware.with_persistence_quarantine(Some(2));
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/set?a=1;Max-Age=3600")
+        .await?;
+    client
+        .get("http://example.com/set?b=2;Max-Age=3600")
+        .await?;
+    assert_eq!(middleware.stats().unsaved, 2);
+    assert_eq!(middleware.find("b").await.len(), 1);
+
+    assert!(client
+        .get("http://example.com/set?c=3;Max-Age=3600")
+        .await
+        .is_err());
+    assert_eq!(middleware.stats().unsaved, 2);
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/set").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, r#"quoted="a b c"; Path=/"#);
+        res.append_header(SET_COOKIE, r#"spaced=" x "; Path=/"#);
+        res.append_header(SET_COOKIE, "plain=value; Path=/");
+        Ok(res)
+    });
+
+    server.at("/cookies").get(|req: Request<_>| async move {
+        Ok(req
+            .header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default())
+    });
+
+    server
+}
+
+async fn round_trip(middleware: CookieMiddleware) -> surf::Result<String> {
+    let client = Client::with_http_client(build_app()).with(middleware);
+    client.get("http://example.com/set").await?;
+    client.get("http://example.com/cookies").recv_string().await
+}
+
+#[async_std::test]
+async fn quotes_are_stripped_by_default() -> surf::Result<()> {
+    let cookies = round_trip(CookieMiddleware::new()).await?;
+    assert_eq!(cookies, "quoted=a b c; spaced=x; plain=value");
+    Ok(())
+}
+
+#[async_std::test]
+async fn quotes_can_be_preserved() -> surf::Result<()> {
+    let cookies = round_trip(CookieMiddleware::new().with_preserved_quotes(true)).await?;
+    assert_eq!(cookies, r#"quoted="a b c"; spaced=" x "; plain=value"#);
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use http::headers::{COOKIE, LOCATION, SET_COOKIE};
+use surf::{http, Client, StatusCode};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+fn redirect(location: &str) -> tide::Response {
+    let mut res = tide::Response::new(302);
+    res.insert_header(LOCATION, location);
+    res
+}
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/login").post(|_: Request<_>| async move {
+        let mut res = tide::Response::new(303);
+        res.insert_header(LOCATION, "/session");
+        res.insert_header(SET_COOKIE, "login=1; Path=/");
+        Ok(res)
+    });
+    server.at("/session").get(|_: Request<_>| async move {
+        let mut res = redirect("/account/home");
+        res.insert_header(SET_COOKIE, "session=2; Path=/account");
+        Ok(res)
+    });
+    server
+        .at("/elsewhere")
+        .get(|_: Request<_>| async move { Ok(redirect("http://other.example.org/account/home")) });
+    server.at("/loop").get(|_: Request<_>| async move {
+        let mut res = redirect("/loop");
+        res.append_header(SET_COOKIE, "looped=1");
+        Ok(res)
+    });
+    server.at("*").get(|req: Request<_>| async move {
+        Ok(req
+            .header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default())
+    });
+    server
+}
+
+#[async_std::test]
+async fn stores_and_sends_cookies_on_each_hop() -> surf::Result<()> {
+    let client =
+        Client::with_http_client(build_app()).with(CookieMiddleware::new().with_redirects(5));
+    let mut res = client.post("http://example.com/login").await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    assert_eq!(res.body_string().await?, "session=2; login=1");
+
+    let cookies = client
+        .get("http://example.com/elsewhere")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "");
+    Ok(())
+}
+
+#[async_std::test]
+async fn stops_after_max_redirects() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_redirects(2);
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    let res = client.get("http://example.com/loop").await?;
+    assert_eq!(res.status(), StatusCode::Found);
+    assert_eq!(middleware.stats().stored, 3);
+    Ok(())
+}
+
+#[async_std::test]
+async fn redirects_are_not_followed_by_default() -> surf::Result<()> {
+    let client = Client::with_http_client(build_app()).with(CookieMiddleware::new());
+    let res = client.post("http://example.com/login").await?;
+    assert_eq!(res.status(), StatusCode::SeeOther);
+    Ok(())
+}
+
+#[async_std::test]
+async fn hops_driven_by_the_caller() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = Client::with_http_client(build_app());
+
+    let mut url = surf::Url::parse("http://example.com/session")?;
+    loop {
+        let mut req = surf::get(url.clone()).build();
+        middleware.prepare_request(&mut req).await;
+        let mut res = client.send(req).await?;
+        middleware.process_response(&url, &res).await?;
+
+        match res.header(LOCATION) {
+            Some(location) => url = url.join(location.as_str())?,
+            None => {
+                assert_eq!(res.body_string().await?, "session=2");
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn caller_cookies_stay_on_the_original_host() -> surf::Result<()> {
+    let client =
+        Client::with_http_client(build_app()).with(CookieMiddleware::new().with_redirects(5));
+    let cookies = client
+        .get("http://example.com/elsewhere")
+        .header(COOKIE, "debug=1")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "");
+
+    let cookies = client
+        .post("http://example.com/login")
+        .header(COOKIE, "debug=1")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "debug=1; session=2; login=1");
+    Ok(())
+}
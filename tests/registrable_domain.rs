@@ -0,0 +1,105 @@
+use async_std::prelude::*;
+use std::sync::{Arc, Mutex};
+use surf_cookie_middleware::{
+    cookie::Cookie, publicsuffix::List, CookieLimits, CookieMiddleware, JarChange,
+};
+
+mod common;
+use common::client;
+
+const SUFFIXES: &str = "// ===BEGIN ICANN DOMAINS===\ncom\nuk\nco.uk\n// ===END ICANN DOMAINS===\n";
+
+#[async_std::test]
+async fn cookie_is_sent_to_subdomains() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_public_suffix_list(List::from_str(SUFFIXES)?);
+    middleware
+        .set_for_registrable_domain(".Example.co.uk", Cookie::new("sso", "token"))
+        .await?;
+    let mut secure = Cookie::new("secure_sso", "token");
+    secure.set_secure(true);
+    middleware
+        .set_for_registrable_domain("example.co.uk", secure)
+        .await?;
+
+    let client = client(&middleware);
+    for url in ["http://example.co.uk/", "http://www.example.co.uk/"] {
+        assert_eq!(client.get(url).recv_string().await?, "sso=token");
+    }
+    assert_eq!(
+        client
+            .get("https://a.b.example.co.uk/")
+            .recv_string()
+            .await?,
+        "sso=token; secure_sso=token"
+    );
+    assert_eq!(client.get("http://other.co.uk/").recv_string().await?, "");
+    Ok(())
+}
+
+#[async_std::test]
+async fn public_suffixes_are_refused() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_public_suffix_list(List::from_str(SUFFIXES)?);
+    for domain in ["co.uk", "com", ".uk", "127.0.0.1", "::1"] {
+        assert!(
+            middleware
+                .set_for_registrable_domain(domain, Cookie::new("sso", "token"))
+                .await
+                .is_err(),
+            "{}",
+            domain
+        );
+    }
+
+    let middleware = CookieMiddleware::new();
+    assert!(middleware
+        .set_for_registrable_domain("localhost", Cookie::new("sso", "token"))
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[async_std::test]
+async fn listeners_and_limits_apply() -> surf::Result<()> {
+    let stored = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new()
+        .with_public_suffix_list(List::from_str(SUFFIXES)?)
+        .with_limits(Some(CookieLimits::new().max_total(1)))
+        .on_cookie_set({
+            let stored = stored.clone();
+            move |cookie, url, action| {
+                stored
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {} {:?}", cookie.name(), url, action))
+            }
+        });
+    let mut changes = middleware.subscribe();
+
+    middleware
+        .set_for_registrable_domain("example.co.uk", Cookie::new("a", "1"))
+        .await?;
+    middleware
+        .set_for_registrable_domain("example.com", Cookie::new("b", "1"))
+        .await?;
+
+    assert_eq!(
+        *stored.lock().unwrap(),
+        [
+            "a http://example.co.uk/ Created",
+            "b http://example.com/ Created"
+        ]
+    );
+    assert_eq!(middleware.len().await, 1);
+
+    drop(middleware);
+    let mut described = vec![];
+    while let Some(change) = changes.next().await {
+        described.push(match change {
+            JarChange::Added(cookie) => format!("added {}", cookie.name),
+            JarChange::Removed(cookie) => format!("removed {}", cookie.name),
+            other => panic!("unexpected change {:?}", other),
+        });
+    }
+    assert_eq!(described, ["added a", "added b", "removed a"]);
+    Ok(())
+}
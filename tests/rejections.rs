@@ -0,0 +1,205 @@
+use http::headers::SET_COOKIE;
+use std::sync::{Arc, Mutex};
+use surf::{http, Client};
+use surf_cookie_middleware::{
+    cookie_store::CookieError,
+    events::{CookieEvent, RejectionReason},
+    CookieMiddleware, CookieStats,
+};
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, "valid=1; Path=/");
+        res.append_header(SET_COOKIE, "HttpOnly; Path=/");
+        res.append_header(SET_COOKIE, "Secure");
+        res.append_header(SET_COOKIE, "; Max-Age=100");
+        res.append_header(SET_COOKIE, "=nameless");
+        res.append_header(SET_COOKIE, "=value; Path=/");
+        res.append_header(SET_COOKIE, "other=1; Domain=example.org");
+        res.append_header(SET_COOKIE, "gone=1; Max-Age=0");
+        Ok(res)
+    });
+    server
+}
+
+#[async_std::test]
+async fn invalid_set_cookie_headers_are_reported() -> surf::Result<()> {
+    let events = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new().with_event_listener({
+        let events = events.clone();
+        move |event| {
+            if let CookieEvent::Rejected(rejection) = event {
+                events
+                    .lock()
+                    .unwrap()
+                    .push((rejection.header.clone(), rejection.reason.clone()));
+            }
+        }
+    });
+
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    client.get("http://example.com/").await?;
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            (
+                String::from("HttpOnly; Path=/"),
+                RejectionReason::AttributeOnly
+            ),
+            (String::from("Secure"), RejectionReason::AttributeOnly),
+            (
+                String::from("; Max-Age=100"),
+                RejectionReason::AttributeOnly
+            ),
+            (String::from("=nameless"), RejectionReason::EmptyName),
+            (String::from("=value; Path=/"), RejectionReason::EmptyName),
+            (
+                String::from("other=1; Domain=example.org"),
+                RejectionReason::CookieStore(CookieError::DomainMismatch)
+            ),
+        ]
+    );
+
+    assert_eq!(
+        middleware.stats(),
+        CookieStats {
+            sent: 0,
+            stored: 1,
+            rejected: 6,
+            unsaved: 0,
+        }
+    );
+
+    client.get("http://example.com/").await?;
+    assert_eq!(middleware.stats().sent, 1);
+    assert_eq!(middleware.stats().rejected, 12);
+
+    Ok(())
+}
+
+fn build_blank_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, "   =value");
+        res.append_header(SET_COOKIE, "   ");
+        res.append_header(SET_COOKIE, "empty=");
+        res.append_header(SET_COOKIE, "spaces=   ; Path=/");
+        res.append_header(SET_COOKIE, r#"quoted="  ""#);
+        res.append_header(SET_COOKIE, "present=1");
+        Ok(res)
+    });
+
+    server.at("/clear").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, "present=; Max-Age=0");
+        Ok(res)
+    });
+
+    server
+        .at("/cookies")
+        .get(|req: Request<_>| async move { Ok(req[http::headers::COOKIE].as_str().to_string()) });
+    server
+}
+
+fn rejection_reasons(
+    middleware: CookieMiddleware,
+) -> (CookieMiddleware, Arc<Mutex<Vec<RejectionReason>>>) {
+    let reasons = Arc::new(Mutex::new(vec![]));
+    let middleware = middleware.with_event_listener({
+        let reasons = reasons.clone();
+        move |event| {
+            if let CookieEvent::Rejected(rejection) = event {
+                reasons.lock().unwrap().push(rejection.reason.clone());
+            }
+        }
+    });
+    (middleware, reasons)
+}
+
+#[async_std::test]
+async fn empty_names_are_rejected() -> surf::Result<()> {
+    let (middleware, reasons) = rejection_reasons(CookieMiddleware::new());
+    let client = Client::with_http_client(build_blank_app()).with(middleware);
+    client.get("http://example.com/").await?;
+
+    assert_eq!(
+        *reasons.lock().unwrap(),
+        vec![RejectionReason::EmptyName, RejectionReason::EmptyName]
+    );
+
+    let cookies = client
+        .get("http://example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "empty=; spaces=; quoted=; present=1");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn blank_values_can_be_rejected() -> surf::Result<()> {
+    let (middleware, reasons) =
+        rejection_reasons(CookieMiddleware::new().with_blank_values_rejected(true));
+    let client = Client::with_http_client(build_blank_app()).with(middleware);
+    client.get("http://example.com/").await?;
+
+    assert_eq!(
+        *reasons.lock().unwrap(),
+        vec![
+            RejectionReason::EmptyName,
+            RejectionReason::EmptyName,
+            RejectionReason::BlankValue,
+            RejectionReason::BlankValue,
+            RejectionReason::BlankValue,
+        ]
+    );
+
+    let cookies = client
+        .get("http://example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "present=1");
+
+    client.get("http://example.com/clear").await?;
+    let cookies = client
+        .get("http://example.com/cookies")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "");
+    assert_eq!(reasons.lock().unwrap().len(), 5);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn cookies_named_like_attributes_are_stored() -> surf::Result<()> {
+    let mut server = tide::new();
+    server.at("/").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, "Path=1");
+        res.append_header(SET_COOKIE, "Secure=1; Path=/");
+        res.append_header(SET_COOKIE, "domain=x");
+        Ok(res)
+    });
+
+    let (middleware, reasons) = rejection_reasons(CookieMiddleware::new());
+    let client = Client::with_http_client(server).with(middleware.clone());
+    client.get("http://example.com/").await?;
+
+    assert_eq!(*reasons.lock().unwrap(), vec![]);
+    let mut names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["Path", "Secure", "domain"]);
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::build_app;
+
+fn build_client(middleware: CookieMiddleware) -> Client {
+    Client::with_http_client(build_app()).with(middleware)
+}
+
+#[async_std::test]
+async fn picks_up_cookies_saved_by_another_middleware() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let watching = build_client(
+        CookieMiddleware::from_path(jar.path())
+            .await?
+            .with_reload_on_change(true),
+    );
+    let other = build_client(CookieMiddleware::from_path(jar.path()).await?);
+
+    watching
+        .get("http://example.com/?mine=1;Max-Age=3600")
+        .await?;
+    assert_eq!(
+        watching.get("http://example.com/").recv_string().await?,
+        "mine=1"
+    );
+
+    other
+        .get("http://example.com/?theirs=2;Max-Age=3600")
+        .await?;
+    assert_eq!(
+        watching.get("http://example.com/").recv_string().await?,
+        "theirs=2"
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn ignores_changes_without_the_option() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let client = build_client(CookieMiddleware::from_path(jar.path()).await?);
+    let other = build_client(CookieMiddleware::from_path(jar.path()).await?);
+
+    other
+        .get("http://example.com/?theirs=2;Max-Age=3600")
+        .await?;
+    assert_eq!(client.get("http://example.com/").recv_string().await?, "");
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use async_std::fs;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn removes_and_persists() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path()).await?;
+
+    let client = client(&middleware);
+
+    client
+        .get("http://example.com/?session=abc;Max-Age=3600&theme=dark;Max-Age=3600")
+        .await?;
+    assert!(fs::read_to_string(jar.path())
+        .await?
+        .contains("session=abc"));
+
+    assert!(
+        middleware
+            .remove_cookie("example.com", "/", "session")
+            .await?
+    );
+    assert!(
+        !middleware
+            .remove_cookie("example.com", "/", "session")
+            .await?
+    );
+
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "theme=dark"
+    );
+    let persisted = fs::read_to_string(jar.path()).await?;
+    assert!(!persisted.contains("session=abc"));
+    assert!(persisted.contains("theme=dark"));
+
+    Ok(())
+}
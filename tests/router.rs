@@ -0,0 +1,51 @@
+use surf::Client;
+use surf_cookie_middleware::{CookieMiddleware, JarRouter};
+
+mod common;
+use common::build_app;
+
+#[async_std::test]
+async fn each_tenant_has_its_own_jar() -> surf::Result<()> {
+    let tenant_a = CookieMiddleware::new();
+    let tenant_b = CookieMiddleware::new();
+    let fallback = CookieMiddleware::new();
+
+    let router = JarRouter::new(fallback.clone())
+        .with_route(
+            |req| req.header("x-tenant").is_some_and(|tenant| tenant == "a"),
+            tenant_a.clone(),
+        )
+        .with_route(
+            |req| req.header("x-tenant").is_some_and(|tenant| tenant == "b"),
+            tenant_b.clone(),
+        );
+
+    let client = Client::with_http_client(build_app()).with(router);
+
+    client
+        .get("http://example.com/?session=a")
+        .header("x-tenant", "a")
+        .await?;
+    client
+        .get("http://example.com/?session=b")
+        .header("x-tenant", "b")
+        .await?;
+    client.get("http://example.com/?session=anonymous").await?;
+
+    for (tenant, expected) in [("a", "session=a"), ("b", "session=b")] {
+        let cookies = client
+            .get("http://example.com/")
+            .header("x-tenant", tenant)
+            .recv_string()
+            .await?;
+        assert_eq!(cookies, expected);
+    }
+
+    let cookies = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(cookies, "session=anonymous");
+
+    assert_eq!(tenant_a.stats().stored, 1);
+    assert_eq!(tenant_b.stats().stored, 1);
+    assert_eq!(fallback.stats().stored, 1);
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use surf::{http, Client, Url};
+use surf_cookie_middleware::{CookieMiddleware, SiteForCookies};
+use tide::Request;
+
+async fn echo_cookies(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(query) = req.url().query() {
+        for set_cookie in query.split('&') {
+            res.append_header(SET_COOKIE, set_cookie);
+        }
+    }
+    let mut cookies: Vec<String> = req
+        .header(COOKIE)
+        .map(|cookie| cookie.as_str().split("; ").map(String::from).collect())
+        .unwrap_or_default();
+    cookies.sort();
+    res.set_body(cookies.join("; "));
+    Ok(res)
+}
+
+async fn build_client(middleware: CookieMiddleware) -> surf::Result<Client> {
+    let mut server = tide::new();
+    server.at("/").get(echo_cookies).post(echo_cookies);
+    let client = Client::with_http_client(server).with(middleware);
+    client
+        .get("https://example.com/?strict=1;SameSite=Strict&lax=2;SameSite=Lax&none=3;SameSite=None;Secure&unset=4")
+        .await?;
+    Ok(client)
+}
+
+#[async_std::test]
+async fn same_site_requests_send_everything() -> surf::Result<()> {
+    let site = Url::parse("https://www.example.com/")?;
+    let client = build_client(CookieMiddleware::new().with_site_for_cookies(Some(site))).await?;
+    assert_eq!(
+        client.post("https://example.com/").recv_string().await?,
+        "lax=2; none=3; strict=1; unset=4"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn cross_site_requests_withhold_same_site_cookies() -> surf::Result<()> {
+    let site = Url::parse("https://blog.example.net/")?;
+    let client = build_client(CookieMiddleware::new().with_site_for_cookies(Some(site))).await?;
+    assert_eq!(
+        client.get("https://example.com/").recv_string().await?,
+        "lax=2; none=3; unset=4"
+    );
+    assert_eq!(
+        client.post("https://example.com/").recv_string().await?,
+        "none=3; unset=4"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn site_for_cookies_can_be_set_per_request() -> surf::Result<()> {
+    let client = build_client(CookieMiddleware::new()).await?;
+    assert_eq!(
+        client.post("https://example.com/").recv_string().await?,
+        "lax=2; none=3; strict=1; unset=4"
+    );
+
+    let mut req = client.post("https://example.com/").build();
+    req.set_ext(SiteForCookies(Url::parse("https://blog.example.net/")?));
+    assert_eq!(client.recv_string(req).await?, "none=3; unset=4");
+    Ok(())
+}
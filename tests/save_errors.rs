@@ -0,0 +1,56 @@
+use std::io;
+use surf_cookie_middleware::{
+    cookie_store::Cookie, CookieFormat, CookieMiddleware, FileStorage, SerializeError,
+};
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+/// A format that can read an empty jar but never write one
+#[derive(Debug)]
+struct Unwritable;
+
+impl CookieFormat for Unwritable {
+    fn parse(&self, _: &str) -> io::Result<Vec<Cookie<'static>>> {
+        Ok(vec![])
+    }
+
+    fn serialize(&self, _: &[&Cookie<'static>]) -> io::Result<String> {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "unwritable"))
+    }
+}
+
+fn serialize_error(e: &io::Error) -> Option<&SerializeError> {
+    e.get_ref()?.downcast_ref()
+}
+
+#[async_std::test]
+async fn serialization_failures_are_returned() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::builder()
+        .storage(FileStorage::with_format(jar.reopen()?, Unwritable))
+        .build()
+        .await?;
+
+    let client = client(&middleware);
+
+    let error = client
+        .get("http://example.com/?a=1;Max-Age=3600")
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), 500);
+
+    let error = middleware.save().await.unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    let source = serialize_error(&error).expect("a SerializeError");
+    assert_eq!(source.cookie_name(), None);
+    assert_eq!(
+        source.to_string(),
+        "could not serialize cookie jar: unwritable"
+    );
+
+    assert_eq!(middleware.cookies().await.len(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn schemes_are_shared_by_default() -> surf::Result<()> {
+    let client = client(&CookieMiddleware::new());
+    client.get("http://example.com/?plain=1").await?;
+    let cookies = client.get("https://example.com/").recv_string().await?;
+    assert_eq!(cookies, "plain=1");
+    Ok(())
+}
+
+#[async_std::test]
+async fn separated_schemes() -> surf::Result<()> {
+    let client = client(&CookieMiddleware::new().with_scheme_separation(true));
+    client.get("http://example.com/?plain=1").await?;
+    client.get("https://example.com/?tls=2").await?;
+
+    let cookies = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(cookies, "plain=1");
+
+    let cookies = client.get("https://example.com/").recv_string().await?;
+    assert_eq!(cookies, "tls=2");
+    Ok(())
+}
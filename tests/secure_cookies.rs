@@ -0,0 +1,69 @@
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+
+mod common;
+use common::build_app;
+
+fn build_client(middleware: CookieMiddleware) -> Client {
+    Client::with_http_client(build_app()).with(middleware)
+}
+
+#[async_std::test]
+async fn secure_cookies_are_only_sent_over_https() -> surf::Result<()> {
+    let client = build_client(CookieMiddleware::new());
+    client
+        .get("https://example.com/?tls=1;Secure&plain=2")
+        .await?;
+
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "plain=2"
+    );
+    let mut sent = client
+        .get("https://example.com/")
+        .recv_string()
+        .await?
+        .split("; ")
+        .map(String::from)
+        .collect::<Vec<_>>();
+    sent.sort();
+    assert_eq!(sent, ["plain=2", "tls=1"]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn secure_cookies_from_http_are_stored_by_default() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = build_client(middleware.clone());
+    client.get("http://example.com/?tls=1;Secure").await?;
+
+    assert_eq!(middleware.len().await, 1);
+    assert_eq!(client.get("http://example.com/").recv_string().await?, "");
+    assert_eq!(
+        client.get("https://example.com/").recv_string().await?,
+        "tls=1"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn strict_mode_rejects_secure_cookies_from_http() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_strict_secure_cookies(true);
+    let client = build_client(middleware.clone());
+    client
+        .get("http://example.com/?tls=1;Secure&plain=2")
+        .await?;
+    client.get("http://localhost/?local=3;Secure").await?;
+    client.get("https://example.com/?https=4;Secure").await?;
+
+    let mut names = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["https", "local", "plain"]);
+    assert_eq!(middleware.stats().rejected, 1);
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use http::headers::{COOKIE, SET_COOKIE};
+use surf::{http, Client};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+async fn echo_cookies(req: Request<()>) -> tide::Result {
+    let mut res = tide::Response::new(200);
+    if let Some(set_cookie) = req.url().query() {
+        res.insert_header(SET_COOKIE, format!("{}; Secure", set_cookie));
+    }
+    res.set_body(
+        req.header(COOKIE)
+            .map(|cookie| cookie.as_str().to_string())
+            .unwrap_or_default(),
+    );
+    Ok(res)
+}
+
+fn client(middleware: CookieMiddleware) -> Client {
+    let mut server = tide::new();
+    server.at("/").get(echo_cookies);
+    Client::with_http_client(server).with(middleware)
+}
+
+#[async_std::test]
+async fn secure_cookies_over_plain_http() -> surf::Result<()> {
+    let client =
+        client(CookieMiddleware::new().with_secure_hosts(vec!["*.internal", "proxy.corp"]));
+
+    for host in ["app.internal", "a.b.internal", "proxy.corp"] {
+        client.get(format!("http://{}/?token=1", host)).await?;
+        let cookies = client
+            .get(format!("http://{}/", host))
+            .recv_string()
+            .await?;
+        assert_eq!(cookies, "token=1", "{}", host);
+    }
+
+    for host in ["internal", "www.proxy.corp", "example.com"] {
+        client.get(format!("http://{}/?token=1", host)).await?;
+        let cookies = client
+            .get(format!("http://{}/", host))
+            .recv_string()
+            .await?;
+        assert_eq!(cookies, "", "{}", host);
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn secure_hosts_from_the_environment() -> surf::Result<()> {
+    std::env::set_var(CookieMiddleware::SECURE_HOSTS_ENV, "localhost, .test");
+    let ignored = client(CookieMiddleware::new());
+    let client = client(CookieMiddleware::new().with_secure_hosts_from_env());
+    std::env::remove_var(CookieMiddleware::SECURE_HOSTS_ENV);
+
+    client.get("http://app.test/?token=1").await?;
+    let cookies = client.get("http://app.test/").recv_string().await?;
+    assert_eq!(cookies, "token=1");
+
+    ignored.get("http://app.test/?token=1").await?;
+    let cookies = ignored.get("http://app.test/").recv_string().await?;
+    assert_eq!(cookies, "");
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::io::Write;
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::{build_app, client};
+
+const SEED: &str = r#"[
+  { "name": "consent", "value": "yes", "domain": "example.com", "host_only": false },
+  { "name": "region", "value": "eu", "domain": "example.com", "expires": "2099-01-01T00:00:00Z" }
+]"#;
+
+#[async_std::test]
+async fn seed_file_never_overwrites_existing_cookies() -> surf::Result<()> {
+    let mut seed = NamedTempFile::new()?;
+    seed.write_all(SEED.as_bytes())?;
+
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path()).await?;
+
+    let client = client(&middleware);
+    client
+        .get("http://example.com/?region=us;Max-Age=3600")
+        .await?;
+
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_seed_file(seed.path())
+        .await?;
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+    let cookies = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(cookies, "region=us; consent=yes");
+
+    let cookies = client.get("http://www.example.com/").recv_string().await?;
+    assert_eq!(cookies, "consent=yes");
+    Ok(())
+}
+
+#[async_std::test]
+async fn missing_seed_file_is_an_error() {
+    assert!(CookieMiddleware::new()
+        .with_seed_file("/nonexistent/seed.json")
+        .await
+        .is_err());
+}
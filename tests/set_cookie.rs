@@ -0,0 +1,62 @@
+use async_std::fs;
+use surf::Url;
+use surf_cookie_middleware::{cookie::Cookie, CookieMiddleware};
+use tempfile::NamedTempFile;
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn sends_a_cookie_set_out_of_band() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = client(&middleware);
+
+    let url = Url::parse("http://example.com/account/")?;
+    middleware
+        .set_cookie(Cookie::new("csrf", "xyz"), &url)
+        .await?;
+
+    assert_eq!(
+        client
+            .get("http://example.com/account/settings")
+            .recv_string()
+            .await?,
+        "csrf=xyz"
+    );
+    assert_eq!(client.get("http://example.com/").recv_string().await?, "");
+    assert_eq!(
+        client
+            .get("http://other.example.com/account/settings")
+            .recv_string()
+            .await?,
+        ""
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn rejects_a_foreign_domain() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let url = Url::parse("http://example.com/")?;
+    let mut cookie = Cookie::new("session", "abc");
+    cookie.set_domain("other.com");
+    assert!(middleware.set_cookie(cookie, &url).await.is_err());
+    Ok(())
+}
+
+#[async_std::test]
+async fn persists_immediately() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path()).await?;
+    let url = Url::parse("https://example.com/")?;
+
+    let mut cookie = Cookie::new("session", "abc");
+    cookie.set_max_age(time::Duration::hours(1));
+    middleware.set_cookie(cookie, &url).await?;
+
+    assert!(fs::read_to_string(jar.path())
+        .await?
+        .contains("session=abc"));
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use async_std::sync::RwLock;
+use std::sync::Arc;
+use surf::{Client, Url};
+use surf_cookie_middleware::{CookieMiddleware, CookieStore};
+
+mod common;
+use common::echo_cookies;
+
+#[async_std::test]
+async fn shares_the_live_store() -> surf::Result<()> {
+    let cookie_store = Arc::new(RwLock::new(CookieStore::default()));
+
+    let mut server = tide::new();
+    server.at("/").get(echo_cookies);
+    let client = Client::with_http_client(server)
+        .with(CookieMiddleware::with_shared_store(cookie_store.clone()));
+
+    client.get("http://example.com/?session=abc").await?;
+    assert_eq!(
+        cookie_store
+            .read()
+            .await
+            .get("example.com", "/", "session")
+            .map(|cookie| cookie.value()),
+        Some("abc")
+    );
+
+    let url = Url::parse("http://example.com/")?;
+    cookie_store
+        .write()
+        .await
+        .parse("theme=dark", &url)
+        .map_err(|e| surf::Error::from_str(500, e.to_string()))?;
+
+    let sent = client.get(url).recv_string().await?;
+    assert!(sent.contains("session=abc"));
+    assert!(sent.contains("theme=dark"));
+
+    Ok(())
+}
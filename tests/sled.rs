@@ -0,0 +1,49 @@
+#![cfg(feature = "sled")]
+use surf::Client;
+use surf_cookie_middleware::{CookieMiddleware, SledCookieStore};
+
+mod common;
+use common::build_app;
+
+async fn build_client(database: &sled::Db) -> surf::Result<Client> {
+    let middleware = CookieMiddleware::builder()
+        .storage(SledCookieStore::with_tree(database.open_tree("cookies")?))
+        .build()
+        .await?;
+    Ok(Client::with_http_client(build_app()).with(middleware))
+}
+
+#[async_std::test]
+async fn cookies_persist_across_runs() -> surf::Result<()> {
+    // sled releases its lock on a database asynchronously after it is
+    // dropped, so each run shares one open database
+    let dir = tempfile::tempdir()?;
+    let database = sled::open(dir.path())?;
+
+    let client = build_client(&database).await?;
+    client
+        .get("http://example.com/?a=1;Max-Age=3600&b=2;Max-Age=3600&session=1")
+        .await?;
+    client.get("http://example.com/?a=3;Max-Age=3600").await?;
+
+    drop(client);
+    let client = build_client(&database).await?;
+    let mut cookies = client
+        .get("http://example.com/?b=gone;Max-Age=0")
+        .recv_string()
+        .await?
+        .split("; ")
+        .map(String::from)
+        .collect::<Vec<_>>();
+    cookies.sort();
+    assert_eq!(cookies, ["a=3", "b=2"]);
+
+    drop(client);
+    let client = build_client(&database).await?;
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "a=3"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use async_std::prelude::*;
+use std::sync::{Arc, Mutex};
+use surf_cookie_middleware::{CookieJarSnapshot, CookieLimits, CookieMiddleware, JarChange};
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn snapshots_round_trip_through_serde() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    client(&middleware)
+        .get("https://example.com/?session=abc123&consent=yes;Max-Age=3600")
+        .await?;
+
+    let snapshot = middleware.snapshot().await;
+    assert_eq!(snapshot.len(), 2);
+    let blob = serde_json::to_string(&snapshot)?;
+
+    let restored = CookieMiddleware::new();
+    client(&restored)
+        .get("https://example.com/?stale=1")
+        .await?;
+    let snapshot: CookieJarSnapshot = serde_json::from_str(&blob)?;
+    restored.restore(&snapshot).await?;
+
+    let mut names = restored
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["consent", "session"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn snapshots_keep_schemes_apart() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_scheme_separation(true);
+    client(&middleware)
+        .get("http://example.com/?plain=1")
+        .await?;
+    client(&middleware)
+        .get("https://example.com/?tls=1")
+        .await?;
+
+    let restored = CookieMiddleware::new().with_scheme_separation(true);
+    restored.restore(&middleware.snapshot().await).await?;
+
+    let client = client(&restored);
+    let cookies = client.get("http://example.com/").recv_string().await?;
+    assert_eq!(cookies, "plain=1");
+    let cookies = client.get("https://example.com/").recv_string().await?;
+    assert_eq!(cookies, "tls=1");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn restored_cookies_are_reported_and_limited() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    client(&middleware).get("https://a.com/?a=1").await?;
+    client(&middleware).get("https://b.com/?b=1").await?;
+    let snapshot = middleware.snapshot().await;
+
+    let stored = Arc::new(Mutex::new(vec![]));
+    let restored = CookieMiddleware::new()
+        .with_limits(Some(CookieLimits::new().max_total(1)))
+        .on_cookie_set({
+            let stored = stored.clone();
+            move |cookie, _, action| {
+                stored
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {:?}", cookie.name(), action))
+            }
+        });
+    client(&restored).get("https://c.com/?c=1").await?;
+    let mut changes = restored.subscribe();
+    restored.restore(&snapshot).await?;
+
+    let mut stored = stored.lock().unwrap().clone();
+    stored.sort();
+    assert_eq!(stored, ["a Created", "b Created", "c Created"]);
+    let kept = restored.cookies().await;
+    assert_eq!(kept.len(), 1);
+    let evicted = if kept[0].name() == "a" { "b" } else { "a" };
+
+    drop(restored);
+    let mut described = vec![];
+    while let Some(change) = changes.next().await {
+        described.push(match change {
+            JarChange::Added(cookie) => format!("added {}", cookie.name),
+            JarChange::Removed(cookie) => format!("removed {}", cookie.name),
+            other => panic!("unexpected change {:?}", other),
+        });
+    }
+    described.sort();
+    let evicted = format!("removed {}", evicted);
+    assert_eq!(described, ["added a", "added b", &evicted, "removed c"]);
+    Ok(())
+}
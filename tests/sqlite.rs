@@ -0,0 +1,45 @@
+#![cfg(feature = "sqlite")]
+use surf::Client;
+use surf_cookie_middleware::{CookieMiddleware, SqliteCookieStore};
+use tempfile::NamedTempFile;
+
+mod common;
+use common::build_app;
+
+async fn build_client(database: &NamedTempFile) -> surf::Result<Client> {
+    let middleware = CookieMiddleware::builder()
+        .storage(SqliteCookieStore::open(database.path())?)
+        .build()
+        .await?;
+    Ok(Client::with_http_client(build_app()).with(middleware))
+}
+
+#[async_std::test]
+async fn cookies_persist_across_runs() -> surf::Result<()> {
+    let database = NamedTempFile::new()?;
+
+    let client = build_client(&database).await?;
+    client
+        .get("http://example.com/?a=1;Max-Age=3600&b=2;Max-Age=3600&session=1")
+        .await?;
+    client.get("http://example.com/?a=3;Max-Age=3600").await?;
+
+    let client = build_client(&database).await?;
+    let mut cookies = client
+        .get("http://example.com/?b=gone;Max-Age=0")
+        .recv_string()
+        .await?
+        .split("; ")
+        .map(String::from)
+        .collect::<Vec<_>>();
+    cookies.sort();
+    assert_eq!(cookies, ["a=3", "b=2"]);
+
+    let client = build_client(&database).await?;
+    assert_eq!(
+        client.get("http://example.com/").recv_string().await?,
+        "a=3"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+use surf::{utils::async_trait, Client, Url};
+use surf_cookie_middleware::{cookie_store::Cookie, CookieMiddleware, CookieStorage};
+
+mod common;
+use common::build_app;
+
+#[derive(Debug, Default, Clone)]
+struct MemoryStorage(Arc<Mutex<Vec<Cookie<'static>>>>);
+
+#[async_trait]
+impl CookieStorage for MemoryStorage {
+    async fn load(&self) -> io::Result<Vec<Cookie<'static>>> {
+        Ok(self.0.lock().unwrap().clone())
+    }
+
+    async fn persist(&self, cookies: &[Cookie<'static>]) -> io::Result<()> {
+        *self.0.lock().unwrap() = cookies.to_vec();
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn custom_storage_loads_and_persists() -> surf::Result<()> {
+    let url = Url::parse("http://example.com/")?;
+    let loaded = Cookie::parse("loaded=yes; Max-Age=3600", &url)?.into_owned();
+    let storage = MemoryStorage(Arc::new(Mutex::new(vec![loaded])));
+
+    let middleware = CookieMiddleware::builder()
+        .storage(storage.clone())
+        .build()
+        .await?;
+
+    let client = Client::with_http_client(build_app()).with(middleware);
+
+    let cookies = client
+        .get("http://example.com/?persistent=1;Max-Age=3600&session=1")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "loaded=yes");
+
+    let mut persisted = storage
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    persisted.sort();
+    assert_eq!(persisted, ["loaded", "persistent"]);
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use surf_cookie_middleware::{events::MalformedSetCookie, CookieMiddleware};
+
+mod common;
+use common::client;
+
+#[async_std::test]
+async fn malformed_headers_fail_the_request() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_strict_parsing(true);
+    let error = client(&middleware)
+        .get("http://example.com/?valid=1&HttpOnly;Path=/&=nameless")
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.status(), 502);
+    let malformed = error
+        .downcast_ref::<MalformedSetCookie>()
+        .expect("a MalformedSetCookie");
+    let headers = malformed
+        .rejections
+        .iter()
+        .map(|rejection| rejection.header.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(headers, ["HttpOnly;Path=/", "=nameless"]);
+
+    let stored = middleware
+        .cookies()
+        .await
+        .iter()
+        .map(|cookie| cookie.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(stored, ["valid"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn well_formed_rejections_do_not_fail_the_request() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new().with_strict_parsing(true);
+    let res = client(&middleware)
+        .get("http://example.com/?other=1;Domain=example.org")
+        .await?;
+    assert_eq!(res.status(), 200);
+    Ok(())
+}
+
+#[async_std::test]
+async fn malformed_headers_are_dropped_by_default() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let res = client(&middleware)
+        .get("http://example.com/?valid=1&=nameless")
+        .await?;
+    assert_eq!(res.status(), 200);
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use async_std::{prelude::*, task};
+use std::time::Duration;
+use surf::Url;
+use surf_cookie_middleware::{cookie::Cookie, CookieMiddleware, JarChange};
+
+mod common;
+use common::client;
+
+fn describe(change: JarChange) -> String {
+    match change {
+        JarChange::Added(cookie) => format!("added {}", cookie.name),
+        JarChange::Updated(cookie) => format!("updated {}", cookie.name),
+        JarChange::Removed(cookie) => format!("removed {}", cookie.name),
+        JarChange::Expired(cookie) => format!("expired {}", cookie.name),
+        other => panic!("unexpected change {:?}", other),
+    }
+}
+
+#[async_std::test]
+async fn changes_are_streamed() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = client(&middleware);
+
+    let mut changes = middleware.subscribe();
+    client
+        .get("http://example.com/?a=1&brief=1;Max-Age=1")
+        .await?;
+    client.get("http://example.com/?a=2").await?;
+    client.get("http://example.com/?a=;Max-Age=0").await?;
+    middleware
+        .set_cookie(Cookie::new("b", "1"), &Url::parse("http://example.com/")?)
+        .await?;
+    middleware.remove_cookie("example.com", "/", "b").await?;
+    task::sleep(Duration::from_millis(1100)).await;
+    middleware.purge_expired().await;
+    drop(client);
+    drop(middleware);
+
+    let mut described = vec![];
+    while let Some(change) = changes.next().await {
+        described.push(describe(change));
+    }
+    assert_eq!(
+        described,
+        vec![
+            "added a",
+            "added brief",
+            "updated a",
+            "removed a",
+            "added b",
+            "removed b",
+            "expired brief"
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,83 @@
+#![cfg(all(feature = "tokio", not(feature = "async-std")))]
+use std::time::Duration;
+use surf_cookie_middleware::CookieMiddleware;
+use tempfile::NamedTempFile;
+use tokio::fs;
+
+mod common;
+use common::client;
+
+#[tokio::test]
+async fn persists_and_reloads_on_tokio() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path()).await?;
+    client(&middleware)
+        .get("http://example.com/?a=1;Max-Age=3600&b=2;Max-Age=3600")
+        .await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 2);
+
+    let reloaded = CookieMiddleware::from_path(jar.path()).await?;
+    let cookies = client(&reloaded)
+        .get("http://example.com/")
+        .recv_string()
+        .await?;
+    assert_eq!(cookies, "a=1; b=2");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn debounced_saves_run_on_tokio() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_save_debounce(Some(Duration::from_millis(300)));
+    let client = client(&middleware);
+
+    client.get("http://example.com/?a=1;Max-Age=3600").await?;
+    client.get("http://example.com/?b=2;Max-Age=3600").await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn saves_on_drop_inside_a_tokio_runtime() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let middleware = CookieMiddleware::from_path(jar.path())
+        .await?
+        .with_manual_persistence(true)
+        .with_save_on_drop(true);
+
+    client(&middleware)
+        .get("http://example.com/?a=1;Max-Age=3600")
+        .await?;
+    assert_eq!(fs::read_to_string(jar.path()).await?, "");
+
+    drop(middleware);
+    assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn autosave_interval_configured_outside_a_runtime() -> surf::Result<()> {
+    let jar = NamedTempFile::new()?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    let middleware = runtime
+        .block_on(CookieMiddleware::from_path(jar.path()))?
+        .with_manual_persistence(true)
+        .with_autosave_interval(Some(Duration::from_millis(50)));
+
+    runtime.block_on(async {
+        client(&middleware)
+            .get("http://example.com/?a=1;Max-Age=3600")
+            .await?;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(fs::read_to_string(jar.path()).await?.lines().count(), 1);
+        Ok(())
+    })
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "tracing")]
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+use surf::Client;
+use surf_cookie_middleware::CookieMiddleware;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+mod common;
+use common::build_app;
+
+/// Records the name and action of every event
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<(String, String)>>>);
+
+#[derive(Default)]
+struct Fields {
+    name: String,
+    action: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "name" => self.name = value.to_string(),
+            "action" => self.action = value.to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, format!("{:?}", value).trim_matches('"'));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        if !fields.action.is_empty() {
+            self.0.lock().unwrap().push((fields.name, fields.action));
+        }
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+#[test]
+fn cookie_decisions_are_traced() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        async_std::task::block_on(async {
+            let client = Client::with_http_client(build_app()).with(CookieMiddleware::new());
+            client
+                .get("http://example.com/?session=1&=nameless")
+                .await?;
+            client.get("http://example.com/?session=;Max-Age=0").await?;
+            surf::Result::Ok(())
+        })
+    })
+    .unwrap();
+
+    let events = recorder.0.lock().unwrap();
+    let actions = events
+        .iter()
+        .map(|(name, action)| (name.as_str(), action.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        vec![
+            ("session", "store"),
+            ("", "reject"),
+            ("session", "send"),
+            ("session", "remove")
+        ]
+    );
+}
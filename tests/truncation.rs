@@ -0,0 +1,81 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use surf::Client;
+use surf_cookie_middleware::{events::CookieEvent, CookieMiddleware, TruncationStrategy};
+
+mod common;
+use common::build_app;
+
+/// Sets `big`, then `a`, then `c` scoped to /deep, and returns the
+/// Cookie header sent to /deep/page with a 20 byte budget along with
+/// the names of the dropped cookies
+async fn truncate(strategy: TruncationStrategy) -> surf::Result<(String, Vec<String>)> {
+    let dropped = Arc::new(Mutex::new(vec![]));
+    let middleware = CookieMiddleware::new()
+        .with_cookie_header_budget(20, strategy)
+        .with_event_listener({
+            let dropped = dropped.clone();
+            move |event| {
+                if let CookieEvent::Truncated(truncation) = event {
+                    assert_eq!(truncation.max_bytes, 20);
+                    dropped.lock().unwrap().extend(truncation.dropped.clone());
+                }
+            }
+        });
+    let client = Client::with_http_client(build_app()).with(middleware);
+
+    for cookie in ["big=xxxxxxxxxx;Path=/", "a=1;Path=/", "c=3;Path=/deep"] {
+        client
+            .get(format!("http://example.com/set?{}", cookie))
+            .await?;
+        async_std::task::sleep(Duration::from_millis(10)).await;
+    }
+
+    let header = client
+        .get("http://example.com/deep/page")
+        .recv_string()
+        .await?;
+    let dropped = dropped.lock().unwrap().clone();
+    Ok((header, dropped))
+}
+
+#[async_std::test]
+async fn drop_largest() -> surf::Result<()> {
+    let (header, dropped) = truncate(TruncationStrategy::DropLargest).await?;
+    assert_eq!(header, "c=3; a=1");
+    assert_eq!(dropped, ["big"]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn drop_oldest() -> surf::Result<()> {
+    let (header, dropped) = truncate(TruncationStrategy::DropOldest).await?;
+    assert_eq!(header, "c=3; a=1");
+    assert_eq!(dropped, ["big"]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn drop_least_specific_path() -> surf::Result<()> {
+    let (header, dropped) = truncate(TruncationStrategy::DropLeastSpecificPath).await?;
+    assert_eq!(header, "c=3; big=xxxxxxxxxx");
+    assert_eq!(dropped, ["a"]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn headers_within_budget_are_untouched() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new()
+        .with_cookie_header_budget(4096, TruncationStrategy::DropLargest)
+        .with_event_listener(|event| panic!("unexpected event {:?}", event));
+    let client = Client::with_http_client(build_app()).with(middleware);
+
+    client.get("http://example.com/set?a=1").await?;
+    assert_eq!(
+        client.get("http://example.com/x").recv_string().await?,
+        "a=1"
+    );
+    Ok(())
+}
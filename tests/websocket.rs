@@ -0,0 +1,54 @@
+use http::headers::SET_COOKIE;
+use surf::{http, Client, Url};
+use surf_cookie_middleware::CookieMiddleware;
+use tide::Request;
+
+fn build_app() -> tide::Server<()> {
+    let mut server = tide::new();
+    server.at("/login").get(|_: Request<_>| async move {
+        let mut res = tide::Response::new(200);
+        res.append_header(SET_COOKIE, "session=abc; Path=/; HttpOnly");
+        res.append_header(SET_COOKIE, "token=1; Path=/; Secure");
+        res.append_header(SET_COOKIE, "api=1; Path=/api");
+        Ok(res)
+    });
+    server
+}
+
+#[async_std::test]
+async fn websocket_urls_share_the_jar() -> surf::Result<()> {
+    let middleware = CookieMiddleware::new();
+    let client = Client::with_http_client(build_app()).with(middleware.clone());
+    client.get("https://example.com/login").await?;
+
+    let header = |url: &str| {
+        let middleware = middleware.clone();
+        let url = Url::parse(url).unwrap();
+        async move {
+            middleware
+                .websocket_cookie_header(&url)
+                .await
+                .map(|header| {
+                    let mut cookies = header.split("; ").map(String::from).collect::<Vec<_>>();
+                    cookies.sort();
+                    cookies.join("; ")
+                })
+        }
+    };
+
+    assert_eq!(
+        header("wss://example.com/socket").await.as_deref(),
+        Some("session=abc; token=1")
+    );
+    assert_eq!(
+        header("ws://example.com/socket").await.as_deref(),
+        Some("session=abc")
+    );
+    assert_eq!(
+        header("wss://example.com/api/socket").await.as_deref(),
+        Some("api=1; session=abc; token=1")
+    );
+    assert_eq!(header("wss://other.example.org/socket").await, None);
+
+    Ok(())
+}